@@ -34,7 +34,7 @@ impl XmlHelper for Element {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Version {
     major: u32,
     minor: u32,
@@ -287,6 +287,42 @@ impl Field {
     pub fn values(&self) -> Option<&[Value]> {
         self.values.as_deref()
     }
+
+    /// Override name and type (when given) and add or replace enum values.
+    fn merge_xml(&mut self, element: &Element) -> Result<()> {
+        if let Some(name) = element.attributes.get("name") {
+            if !name.is_ascii() {
+                bail!("Non ASCII characters in field name: {}", name);
+            }
+            self.name.clone_from(name);
+        }
+        if let Some(type_) = element.attributes.get("type") {
+            self.type_ = type_.parse()?;
+        }
+        for value in element.get_child_elements().map(Value::from_xml) {
+            let value = value?;
+            let values = self.values.get_or_insert_with(Vec::new);
+            match values.iter_mut().find(|v| v.value == value.value) {
+                Some(v) => *v = value,
+                None => values.push(value),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Add new members, for already present ones override `required` flag.
+fn merge_members(members: &mut Vec<Member>, overlay: Vec<Member>) -> Result<()> {
+    for member in overlay {
+        match members.iter_mut().find(|m| m.name == member.name) {
+            Some(m) if m.kind != member.kind => {
+                bail!("Member `{}` kind mismatch", member.name);
+            }
+            Some(m) => m.required = member.required,
+            None => members.push(member),
+        }
+    }
+    Ok(())
 }
 
 fn deserialize_yes_no(input: &str) -> Result<bool> {
@@ -401,6 +437,19 @@ impl Component {
     pub fn members(&self) -> &[Member] {
         &self.members
     }
+
+    fn merge(&mut self, overlay: Component) -> Result<()> {
+        match (&mut self.number_of_elements, overlay.number_of_elements) {
+            (Some(number_of_elements), Some(overlay))
+                if number_of_elements.name == overlay.name =>
+            {
+                number_of_elements.required = overlay.required;
+            }
+            (None, None) => {}
+            _ => bail!("Group definition mismatch in `{}` component", self.name),
+        }
+        merge_members(&mut self.members, overlay.members)
+    }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -463,7 +512,7 @@ impl FromStr for MsgCat {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Message {
     name: String,
     msg_cat: MsgCat,
@@ -512,9 +561,25 @@ impl Message {
     pub fn members(&self) -> &[Member] {
         &self.members
     }
+
+    fn merge(&mut self, overlay: Message) -> Result<()> {
+        if self.name != overlay.name {
+            bail!(
+                "Message `{}` overlaid with different name `{}`",
+                self.name,
+                overlay.name
+            );
+        }
+        merge_members(&mut self.members, overlay.members)
+    }
 }
 
-#[derive(Debug, PartialEq)]
+/// BeginSeqNo<7>, EndSeqNo<16>, MsgSeqNum<34>, NewSeqNo<36>,
+/// RefSeqNum<45>, LastMsgSeqNumProcessed<369>
+/// and NextExpectedMsgSeqNum<789>.
+const LEGACY_SEQ_NUM_TAGS: &[u16] = &[7, 16, 34, 36, 45, 369, 789];
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct Dictionary {
     fix_version: Option<Version>,
     fixt_version: Option<Version>,
@@ -581,7 +646,21 @@ impl Dictionary {
         self.trailer = Some(trailer);
         self.components.extend(trailer_groups);
 
-        self.process_common(&root)
+        self.process_common(&root)?;
+
+        // SEQNUM type was introduced in FIX.4.4, older descriptions
+        // use INT for sequence numbers
+        for field in self
+            .fields
+            .values_mut()
+            .chain(self.fields_by_name.values_mut())
+        {
+            if LEGACY_SEQ_NUM_TAGS.contains(&field.number) && field.type_ == BasicType::Int {
+                field.type_ = BasicType::SeqNum;
+            }
+        }
+
+        Ok(())
     }
 
     pub fn process_fixt_xml(&mut self, xml: &str) -> Result<()> {
@@ -664,20 +743,21 @@ impl Dictionary {
         );
 
         // XXX: Drop MsgType values which does not match Messages list
-        let msg_type_field = self.fields.get_mut(&35).expect("MsgType field not defined");
-        msg_type_field.values = Some(
-            msg_type_field
-                .values
-                .take()
-                .expect("MsgType enum fields not defined")
-                .iter()
-                .filter(|v| {
-                    self.messages
-                        .contains_key(&MsgType::from_str(&v.value).expect("MsgType value error"))
-                })
-                .cloned()
-                .collect(),
-        );
+        let Some(msg_type_field) = self.fields.get_mut(&35) else {
+            bail!("MsgType field not defined");
+        };
+        let Some(values) = msg_type_field.values.take() else {
+            bail!("MsgType enum fields not defined");
+        };
+        let mut msg_type_values = Vec::with_capacity(values.len());
+        for value in values {
+            let msg_type = MsgType::from_str(&value.value)
+                .with_context(|| format!("Invalid MsgType value `{}`", value.value))?;
+            if self.messages.contains_key(&msg_type) {
+                msg_type_values.push(value);
+            }
+        }
+        msg_type_field.values = Some(msg_type_values);
 
         self.fields_by_name.extend(
             self.fields
@@ -688,6 +768,161 @@ impl Dictionary {
         Ok(())
     }
 
+    /// Layer venue specific overlay on top of already processed
+    /// dictionary. Overlay has the same structure as FIX XML description,
+    /// but all sections (`header`, `trailer`, `messages`, `components`
+    /// and `fields`) are optional and contain only differences:
+    /// - new fields are added, for existing ones (matched by number)
+    ///   `name` and `type` attributes are optional and override current
+    ///   ones, enum values are added or replaced,
+    /// - new components and messages are added, existing ones (matched by
+    ///   name and MsgType) get new members appended and `required` flag
+    ///   of already present members overridden,
+    /// - header and trailer members are merged the same way.
+    ///
+    /// Overlays may be applied several times, later ones win. Dictionary
+    /// is validated after merge, so renaming a field still referenced by
+    /// its old name, or to the name of another field, is an error. On
+    /// error dictionary is left unchanged.
+    pub fn process_overlay_xml(&mut self, xml: &str) -> Result<()> {
+        let root = Element::parse(xml.as_bytes()).context("Failed to parse overlay description")?;
+
+        if self.fix_version.is_none() {
+            bail!("Overlay must be processed after FIX XML");
+        }
+
+        let mut dictionary = self.clone();
+        dictionary.merge_overlay(&root)?;
+        *self = dictionary;
+        Ok(())
+    }
+
+    fn merge_overlay(&mut self, root: &Element) -> Result<()> {
+        if let Some(fields) = root.get_child("fields") {
+            for element in fields.get_child_elements() {
+                let number = element.get_attribute("number")?.parse()?;
+                match self.fields.get_mut(&number) {
+                    Some(field) => field
+                        .merge_xml(element)
+                        .with_context(|| format!("Failed to overlay field {}", number))?,
+                    None => {
+                        self.fields.insert(number, Field::from_xml(element)?);
+                    }
+                }
+            }
+        }
+
+        let mut components = Vec::new();
+        for (name, component) in [
+            ("header", self.header.as_mut()),
+            ("trailer", self.trailer.as_mut()),
+        ] {
+            let Some(element) = root.get_child(name) else {
+                continue;
+            };
+            let Some(component) = component else {
+                bail!("No {} to overlay", name);
+            };
+            let (overlay, groups) = Component::from_header_or_trailer(element)?;
+            component.merge(overlay)?;
+            components.extend(groups);
+        }
+        if let Some(element) = root.get_child("components") {
+            components.extend(
+                element
+                    .get_child_elements()
+                    .map(Component::from_xml)
+                    .collect::<Result<Vec<_>>>()?,
+            );
+        }
+        for overlay in components {
+            match self
+                .components
+                .iter_mut()
+                .rev()
+                .find(|c| c.name == overlay.name)
+            {
+                Some(component) => component.merge(overlay)?,
+                None => self.components.push(overlay),
+            }
+        }
+
+        if let Some(element) = root.get_child("messages") {
+            for element in element.get_child_elements() {
+                let overlay = Message::from_xml(element)?;
+                match self.messages.get_mut(&overlay.msg_type) {
+                    Some(message) => message.merge(overlay)?,
+                    None => {
+                        // Every message needs corresponding MsgType<35> value
+                        let Some(msg_type_field) = self.fields.get_mut(&35) else {
+                            bail!("MsgType field not defined");
+                        };
+                        let values = msg_type_field.values.get_or_insert_with(Vec::new);
+                        let value = std::str::from_utf8(&overlay.msg_type)?;
+                        if !values.iter().any(|v| v.value == value) {
+                            values.push(Value {
+                                value: value.to_owned(),
+                                description: overlay.name.clone(),
+                            });
+                        }
+                        self.messages.insert(overlay.msg_type, overlay);
+                    }
+                }
+            }
+        }
+
+        self.components_by_name = self
+            .components
+            .iter()
+            .map(|c| (c.name().to_owned(), c.clone()))
+            .collect();
+        self.fields_by_name = self
+            .fields
+            .values()
+            .map(|f| (f.name().to_owned(), f.to_owned()))
+            .collect();
+
+        if self.fields_by_name.len() != self.fields.len() {
+            bail!("Overlay field name already used by another field");
+        }
+
+        // Renamed fields may still be referenced by their old names
+        // anywhere, not only in overlay, so whole dictionary is checked
+        let components = self
+            .header
+            .iter()
+            .chain(&self.trailer)
+            .chain(&self.components);
+        for component in components {
+            // Group counter is a field, despite being `group` element
+            let number_of_elements = component.number_of_elements.iter().map(|m| Member {
+                kind: MemberKind::Field,
+                ..m.clone()
+            });
+            for member in number_of_elements.chain(component.members.iter().cloned()) {
+                self.check_member_defined(&member, &component.name)?;
+            }
+        }
+        for message in self.messages.values() {
+            for member in &message.members {
+                self.check_member_defined(member, &message.name)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn check_member_defined(&self, member: &Member, parent: &str) -> Result<()> {
+        let defined = match member.kind {
+            MemberKind::Field => self.fields_by_name.contains_key(&member.name),
+            MemberKind::Component => self.components_by_name.contains_key(&member.name),
+        };
+        if !defined {
+            bail!("Member `{}` of `{}` not defined", member.name, parent);
+        }
+        Ok(())
+    }
+
     pub fn fixt_version(&self) -> Option<&Version> {
         self.fixt_version.as_ref()
     }
@@ -760,7 +995,76 @@ mod tests {
 
     use std::str::FromStr;
 
-    use super::MsgType;
+    use super::{BasicType, Dictionary, MemberKind, MsgType};
+
+    const FIX_XML: &str = r#"
+<fix type="FIX" major="4" minor="4" servicepack="0">
+  <header>
+    <field name="BeginString" required="Y"/>
+    <field name="BodyLength" required="Y"/>
+    <field name="MsgType" required="Y"/>
+  </header>
+  <trailer>
+    <field name="CheckSum" required="Y"/>
+  </trailer>
+  <messages>
+    <message name="Heartbeat" msgtype="0" msgcat="admin">
+      <field name="TestReqID" required="N"/>
+    </message>
+  </messages>
+  <components>
+    <component name="PartyGrp">
+      <group name="NoPartyIDs" required="N">
+        <field name="PartyID" required="N"/>
+      </group>
+    </component>
+  </components>
+  <fields>
+    <field number="8" name="BeginString" type="STRING"/>
+    <field number="9" name="BodyLength" type="LENGTH"/>
+    <field number="10" name="CheckSum" type="STRING"/>
+    <field number="35" name="MsgType" type="STRING">
+      <value enum="0" description="HEARTBEAT"/>
+    </field>
+    <field number="112" name="TestReqID" type="STRING"/>
+    <field number="447" name="PartyIDSource" type="CHAR">
+      <value enum="B" description="BIC"/>
+    </field>
+    <field number="448" name="PartyID" type="STRING"/>
+    <field number="453" name="NoPartyIDs" type="NUMINGROUP"/>
+  </fields>
+</fix>
+"#;
+
+    const OVERLAY_XML: &str = r#"
+<fix>
+  <header>
+    <field name="VenueSession" required="N"/>
+  </header>
+  <messages>
+    <message name="Heartbeat" msgtype="0" msgcat="admin">
+      <field name="TestReqID" required="Y"/>
+    </message>
+    <message name="VenueStatus" msgtype="U1" msgcat="app">
+      <component name="PartyGrp" required="Y"/>
+    </message>
+  </messages>
+  <components>
+    <component name="PartyGrp">
+      <group name="NoPartyIDs" required="Y">
+        <field name="PartyIDSource" required="N"/>
+      </group>
+    </component>
+  </components>
+  <fields>
+    <field number="447">
+      <value enum="B" description="BIC_CODE"/>
+      <value enum="Z" description="VENUE_CODE"/>
+    </field>
+    <field number="5001" name="VenueSession" type="STRING"/>
+  </fields>
+</fix>
+"#;
 
     #[test]
     fn parse_msg_type() {
@@ -773,4 +1077,132 @@ mod tests {
         assert!(MsgType::from_str("AAA").is_err());
         assert!(MsgType::from_str("\0A").is_err());
     }
+
+    #[test]
+    fn invalid_msg_type_values() {
+        let invalid_value = FIX_XML.replace(r#"enum="0""#, r#"enum="AAA""#);
+        let mut dictionary = Dictionary::new(None);
+        assert!(dictionary.process_legacy_fix_xml(&invalid_value).is_err());
+
+        let no_values = FIX_XML.replace(r#"<value enum="0" description="HEARTBEAT"/>"#, "");
+        let mut dictionary = Dictionary::new(None);
+        assert!(dictionary.process_legacy_fix_xml(&no_values).is_err());
+    }
+
+    #[test]
+    fn legacy_seq_num_fields() {
+        let fix_xml = FIX_XML.replace(
+            r#"<field number="112" name="TestReqID" type="STRING"/>"#,
+            r#"<field number="34" name="MsgSeqNum" type="INT"/>
+    <field number="108" name="HeartBtInt" type="INT"/>
+    <field number="112" name="TestReqID" type="STRING"/>"#,
+        );
+        let mut dictionary = Dictionary::new(None);
+        dictionary.process_legacy_fix_xml(&fix_xml).unwrap();
+        assert_eq!(dictionary.fields()[&34].type_(), BasicType::SeqNum);
+        assert_eq!(
+            dictionary.fields_by_name()["MsgSeqNum"].type_(),
+            BasicType::SeqNum
+        );
+        assert_eq!(dictionary.fields()[&108].type_(), BasicType::Int);
+    }
+
+    #[test]
+    fn overlay() {
+        let mut dictionary = Dictionary::new(None);
+        assert!(dictionary.process_overlay_xml(OVERLAY_XML).is_err());
+        dictionary.process_legacy_fix_xml(FIX_XML).unwrap();
+        dictionary.process_overlay_xml(OVERLAY_XML).unwrap();
+
+        let header = dictionary.header().unwrap();
+        assert_eq!(header.members().last().unwrap().name(), "VenueSession");
+        assert_eq!(dictionary.fields_by_name()["VenueSession"].number(), 5001);
+
+        let party_id_source = &dictionary.fields()[&447];
+        assert_eq!(party_id_source.name(), "PartyIDSource");
+        let values = party_id_source.values().unwrap();
+        assert_eq!(values.len(), 2);
+        assert_eq!(values[0].description(), "BIC_CODE");
+        assert_eq!(values[1].value(), "Z");
+
+        let party_grp = dictionary.component("PartyGrp").unwrap();
+        assert!(party_grp.number_of_elements().unwrap().required());
+        let names = party_grp
+            .members()
+            .iter()
+            .map(|m| m.name())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["PartyID", "PartyIDSource"]);
+
+        let heartbeat = dictionary
+            .message(&MsgType::from_str("0").unwrap())
+            .unwrap();
+        assert_eq!(heartbeat.members().len(), 1);
+        assert!(heartbeat.members()[0].required());
+
+        let venue_status = dictionary
+            .message(&MsgType::from_str("U1").unwrap())
+            .unwrap();
+        assert_eq!(venue_status.name(), "VenueStatus");
+        assert_eq!(venue_status.members()[0].kind(), MemberKind::Component);
+        assert!(dictionary.fields()[&35]
+            .values()
+            .unwrap()
+            .iter()
+            .any(|v| v.value() == "U1" && v.description() == "VenueStatus"));
+
+        let undefined_member = r#"
+<fix>
+  <messages>
+    <message name="Heartbeat" msgtype="0" msgcat="admin">
+      <field name="Undefined" required="N"/>
+    </message>
+  </messages>
+</fix>
+"#;
+        assert!(dictionary.process_overlay_xml(undefined_member).is_err());
+    }
+
+    #[test]
+    fn overlay_field_rename() {
+        let mut dictionary = Dictionary::new(None);
+        dictionary.process_legacy_fix_xml(FIX_XML).unwrap();
+
+        // PartyIDSource<447> is not referenced anywhere
+        let unreferenced = r#"
+<fix>
+  <fields>
+    <field number="447" name="VenuePartyIDSource"/>
+  </fields>
+</fix>
+"#;
+        dictionary.process_overlay_xml(unreferenced).unwrap();
+        assert_eq!(
+            dictionary.fields_by_name()["VenuePartyIDSource"].number(),
+            447
+        );
+        assert!(!dictionary.fields_by_name().contains_key("PartyIDSource"));
+
+        // TestReqID<112> is member of Heartbeat<0>
+        let referenced = r#"
+<fix>
+  <fields>
+    <field number="112" name="VenueTestReqID"/>
+  </fields>
+</fix>
+"#;
+        assert!(dictionary.process_overlay_xml(referenced).is_err());
+        assert_eq!(dictionary.fields()[&112].name(), "TestReqID");
+        assert!(!dictionary.fields_by_name().contains_key("VenueTestReqID"));
+
+        let duplicated = r#"
+<fix>
+  <fields>
+    <field number="447" name="PartyID"/>
+  </fields>
+</fix>
+"#;
+        assert!(dictionary.process_overlay_xml(duplicated).is_err());
+        assert_eq!(dictionary.fields()[&447].name(), "VenuePartyIDSource");
+    }
 }
@@ -1,22 +1,47 @@
-use std::{env, path::PathBuf};
+use std::{env, error::Error, path::PathBuf};
 
-use easyfix_messages_gen::generate_fix_messages;
+use easyfix_messages_gen::generate_fix_messages_with_overlays;
 
-fn main() {
+// Errors (e.g. invalid overlay) are reported without panic
+fn main() -> Result<(), Box<dyn Error>> {
     let dir = env::var("CARGO_MANIFEST_DIR").unwrap();
     let out_path = PathBuf::from(env::var("OUT_DIR").expect("OUT_DIR undefined"));
-    let fixt_xml_path = env::var("FIXT_XML").unwrap_or_else(|_| format!("{}/xml/FIXT11.xml", dir));
+    // Empty FIXT_XML means FIX_XML is FIX 4.x description (with header
+    // and trailer). Messages of both kinds implement `SessionMessage`, so
+    // easyfix-session runs sessions of the generated version on them.
+    let fixt_xml_path = match env::var("FIXT_XML") {
+        Ok(path) if path.is_empty() => None,
+        Ok(path) => Some(path),
+        Err(_) => Some(format!("{}/xml/FIXT11.xml", dir)),
+    };
     let fix_xml_path = env::var("FIX_XML").unwrap_or_else(|_| format!("{}/xml/FIX50SP2.xml", dir));
+    // Venue specific overlays, applied in order on top of FIX_XML,
+    // separated like PATH entries.
+    let overlay_xml_paths = env::var_os("FIX_OVERLAY_XML")
+        .map(|paths| {
+            env::split_paths(&paths)
+                .filter(|path| !path.as_os_str().is_empty())
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
     println!("cargo:rerun-if-changed=build.rs");
-    println!("cargo:rerun-if-changed={}", fixt_xml_path);
+    println!("cargo:rerun-if-env-changed=FIXT_XML");
+    println!("cargo:rerun-if-env-changed=FIX_XML");
+    if let Some(fixt_xml_path) = &fixt_xml_path {
+        println!("cargo:rerun-if-changed={}", fixt_xml_path);
+    }
     println!("cargo:rerun-if-changed={}", fix_xml_path);
-    generate_fix_messages(
-        Some(fixt_xml_path),
+    println!("cargo:rerun-if-env-changed=FIX_OVERLAY_XML");
+    for overlay_xml_path in &overlay_xml_paths {
+        println!("cargo:rerun-if-changed={}", overlay_xml_path.display());
+    }
+    generate_fix_messages_with_overlays(
+        fixt_xml_path,
         fix_xml_path,
         out_path.join("generated_fields.rs"),
         out_path.join("generated_groups.rs"),
         out_path.join("generated_messages.rs"),
         None,
+        &overlay_xml_paths,
     )
-    .expect("failed to generate FIX messages");
 }
@@ -1,5 +1,6 @@
 mod enumeration;
 mod member;
+mod session;
 mod structure;
 
 use std::{collections::HashMap, rc::Rc};
@@ -24,6 +25,9 @@ pub struct Generator {
     fields_names: Vec<Ident>,
     fields_numbers: Vec<u16>,
     reject_reason_overrides: HashMap<ParseRejectReason, String>,
+    // Path to `easyfix-messages` crate, version specific modules
+    // (`fields`, `groups`, `messages`) are always siblings.
+    runtime_path: TokenStream,
 }
 
 fn process_members(
@@ -259,9 +263,15 @@ impl Generator {
             fields_names,
             fields_numbers,
             reject_reason_overrides: dictionary.reject_reason_overrides().clone(),
+            runtime_path: quote! { crate },
         }
     }
 
+    /// Use `easyfix-messages` types from `runtime_path` (`crate` by default).
+    pub fn set_runtime_path(&mut self, runtime_path: TokenStream) {
+        self.runtime_path = runtime_path;
+    }
+
     pub fn generate_fields(&self) -> TokenStream {
         let mut enums = Vec::new();
         for enum_ in &self.enums {
@@ -284,8 +294,9 @@ impl Generator {
 	    })
 	    .collect();
 
+        let runtime_path = &self.runtime_path;
         quote! {
-        use crate::deserializer::ParseRejectReason;
+        use #runtime_path::deserializer::ParseRejectReason;
 
         pub fn parse_reject_reason_to_session_reject_reason(input: ParseRejectReason) -> SessionRejectReason {
         match input {
@@ -306,13 +317,16 @@ impl Generator {
             }
         }
 
+        let runtime_path = &self.runtime_path;
         quote! {
         #[allow(unused_imports)]
-            use crate::{
-                deserializer::{DeserializeError, Deserializer, ParseRejectReason},
-                fields::{self, basic_types::*, SessionRejectReason},
+            use #runtime_path::{
+                deserializer::{DeserializeError, Deserializer, ParseRejectReason, UnknownFields},
+                fields::basic_types::*,
                 serializer::Serializer,
             };
+        #[allow(unused_imports)]
+            use super::fields::{self, SessionRejectReason};
 
             #(#groups_defs)*
         }
@@ -356,14 +370,20 @@ impl Generator {
             .map(|num| Literal::u16_suffixed(*num))
             .collect::<Vec<_>>();
 
+        let runtime_path = &self.runtime_path;
+        let session_impls = session::generate(&self.structs, runtime_path);
         quote! {
         #[allow(unused_imports)]
-            use crate::{
+            use #runtime_path::{
                 deserializer::{raw_message, DeserializeError, Deserializer, RawMessage, ParseRejectReason},
-                fields::{self, basic_types::*, SessionRejectReason},
-                groups::*,
+                fields::basic_types::*,
                 serializer::Serializer,
             };
+        #[allow(unused_imports)]
+            use super::{
+                fields::{self, SessionRejectReason},
+                groups::*,
+            };
             use std::fmt;
 
             pub const BEGIN_STRING: &FixStr = unsafe { FixStr::from_ascii_unchecked(#begin_string) };
@@ -465,7 +485,11 @@ impl Generator {
 
             impl FixtMessage {
                 pub fn serialize(&self) -> Vec<u8> {
-                    let mut serializer = Serializer::new();
+                    self.serialize_with(Serializer::new())
+                }
+
+                /// Serialize message with preconfigured `serializer`.
+                pub fn serialize_with(&self, mut serializer: Serializer) -> Vec<u8> {
                     self.header.serialize(&mut serializer);
                     self.body.serialize(&mut serializer);
                     self.trailer.serialize(&mut serializer);
@@ -528,6 +552,8 @@ impl Generator {
                     self.body.msg_cat()
                 }
             }
+
+            #session_impls
         }
     }
 }
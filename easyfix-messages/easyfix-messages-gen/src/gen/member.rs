@@ -275,6 +275,22 @@ impl SimpleMember {
         SimpleMember::new(name, tag, required, Type::basic_type(BasicType::NumInGroup))
     }
 
+    pub fn name(&self) -> &Ident {
+        &self.name
+    }
+
+    pub fn tag(&self) -> u16 {
+        self.tag
+    }
+
+    pub fn required(&self) -> bool {
+        self.required
+    }
+
+    pub fn type_(&self) -> &Type {
+        &self.type_
+    }
+
     /// Create `SimpleMember` object of `Group` type.
     ///
     /// # Arguments
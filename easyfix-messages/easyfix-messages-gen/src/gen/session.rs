@@ -0,0 +1,388 @@
+use easyfix_dictionary::BasicType;
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::quote;
+
+use crate::gen::{
+    member::{MemberDesc, SimpleMember, Type},
+    structure::Struct,
+};
+
+/// `AdminMessage` variants with their fields (name, tag, required).
+const ADMIN_MESSAGES: &[(&str, &[(&str, u16, bool)])] = &[
+    ("Heartbeat", &[("test_req_id", 112, false)]),
+    ("TestRequest", &[("test_req_id", 112, true)]),
+    (
+        "ResendRequest",
+        &[("begin_seq_no", 7, true), ("end_seq_no", 16, true)],
+    ),
+    (
+        "Reject",
+        &[
+            ("ref_seq_num", 45, true),
+            ("ref_tag_id", 371, false),
+            ("ref_msg_type", 372, false),
+            ("session_reject_reason", 373, false),
+            ("text", 58, false),
+        ],
+    ),
+    (
+        "SequenceReset",
+        &[("gap_fill_flag", 123, false), ("new_seq_no", 36, true)],
+    ),
+    (
+        "Logout",
+        &[("session_status", 1409, false), ("text", 58, false)],
+    ),
+    (
+        "Logon",
+        &[
+            ("encrypt_method", 98, true),
+            ("heart_bt_int", 108, true),
+            ("reset_seq_num_flag", 141, false),
+            ("next_expected_msg_seq_num", 789, false),
+            ("default_appl_ver_id", 1137, false),
+            ("username", 553, false),
+            ("password", 554, false),
+        ],
+    ),
+];
+
+fn ident(name: &str) -> Ident {
+    Ident::new(name, Span::call_site())
+}
+
+fn find_struct<'a>(structs: &'a [Struct], name: &str) -> Option<&'a Struct> {
+    structs.iter().find(|struct_| struct_.name() == name)
+}
+
+fn find_member(struct_: &Struct, tag: u16) -> Option<&SimpleMember> {
+    struct_.members().iter().find_map(|member| match member {
+        MemberDesc::Simple(member) if member.tag() == tag => Some(member),
+        _ => None,
+    })
+}
+
+fn find_header_member(header: &Struct, tag: u16, type_: BasicType) -> Option<&SimpleMember> {
+    find_member(header, tag)
+        .filter(|member| matches!(member.type_(), Type::Basic(t) if *t == type_))
+}
+
+/// Generate `SessionHeader` getter and setter of required header field.
+fn gen_required_accessors(
+    header: &Struct,
+    tag: u16,
+    type_: BasicType,
+    getter: &str,
+    setter: &str,
+) -> Option<TokenStream> {
+    let member = find_header_member(header, tag, type_).filter(|member| member.required())?;
+    let name = member.name();
+    let getter = ident(getter);
+    let setter = ident(setter);
+    Some(match type_ {
+        BasicType::String => quote! {
+            fn #getter(&self) -> &FixStr {
+                &self.#name
+            }
+
+            fn #setter(&mut self, #name: FixString) {
+                self.#name = #name;
+            }
+        },
+        _ => {
+            let type_ = member.type_().gen_type();
+            quote! {
+                fn #getter(&self) -> #type_ {
+                    self.#name
+                }
+
+                fn #setter(&mut self, #name: #type_) {
+                    self.#name = #name;
+                }
+            }
+        }
+    })
+}
+
+/// Generate `SessionHeader` getter and setter of optional header field,
+/// ignored if not defined by the dictionary.
+fn gen_optional_accessors(
+    header: &Struct,
+    tag: u16,
+    type_: BasicType,
+    getter: &str,
+    setter: &str,
+) -> Option<TokenStream> {
+    let getter = ident(getter);
+    let setter = ident(setter);
+    let type_ = Type::basic_type(type_).gen_type();
+    match find_member(header, tag) {
+        Some(member) => {
+            let Type::Basic(member_type) = member.type_() else {
+                return None;
+            };
+            if member.required()
+                || Type::basic_type(*member_type).gen_type().to_string() != type_.to_string()
+            {
+                return None;
+            }
+            let name = member.name();
+            Some(quote! {
+                fn #getter(&self) -> Option<#type_> {
+                    self.#name
+                }
+
+                fn #setter(&mut self, #name: Option<#type_>) {
+                    self.#name = #name;
+                }
+            })
+        }
+        None => Some(quote! {
+            fn #getter(&self) -> Option<#type_> {
+                None
+            }
+
+            fn #setter(&mut self, _: Option<#type_>) {}
+        }),
+    }
+}
+
+/// Read field of admin message struct `msg` as value of `AdminMessage` field.
+fn gen_to_admin_value(member: Option<&SimpleMember>, required: bool) -> TokenStream {
+    match member {
+        None if required => quote! { Default::default() },
+        None => quote! { None },
+        Some(member) => {
+            let name = member.name();
+            match (member.required(), required) {
+                (true, true) => quote! { AdminValue::to_admin_value(&msg.#name) },
+                (true, false) => quote! { Some(AdminValue::to_admin_value(&msg.#name)) },
+                (false, false) => quote! { msg.#name.as_ref().map(AdminValue::to_admin_value) },
+                (false, true) => quote! {
+                    msg.#name.as_ref().map(AdminValue::to_admin_value).unwrap_or_default()
+                },
+            }
+        }
+    }
+}
+
+/// Build field of admin message struct from `AdminMessage` field `value`.
+fn gen_from_admin_value(member: &SimpleMember, value: &Ident, required: bool) -> TokenStream {
+    match (required, member.required()) {
+        (true, true) => quote! { AdminValue::from_admin_value(#value).unwrap_or_default() },
+        (true, false) => quote! { AdminValue::from_admin_value(#value) },
+        (false, false) => quote! { #value.and_then(AdminValue::from_admin_value) },
+        (false, true) => quote! {
+            #value.and_then(AdminValue::from_admin_value).unwrap_or_default()
+        },
+    }
+}
+
+/// Generate `AdminValue<FixString>` implementation for enumeration.
+fn gen_enum_admin_value(name: &Ident) -> TokenStream {
+    quote! {
+        impl AdminValue<FixString> for fields::#name {
+            fn to_admin_value(&self) -> FixString {
+                self.as_fix_str().to_owned()
+            }
+
+            fn from_admin_value(value: FixString) -> Option<fields::#name> {
+                fields::#name::from_fix_str(&value)
+            }
+        }
+    }
+}
+
+/// Generate `SessionMessage` implementation for `FixtMessage` and
+/// `SessionHeader` implementation for `Header`.
+///
+/// Returns `None` if dictionary lacks any of session level messages
+/// or standard header fields, such module is a codec only.
+pub fn generate(structs: &[Struct], runtime_path: &TokenStream) -> Option<TokenStream> {
+    let header = find_struct(structs, "Header")?;
+    let header_accessors = [
+        gen_required_accessors(
+            header,
+            8,
+            BasicType::String,
+            "begin_string",
+            "set_begin_string",
+        )?,
+        gen_required_accessors(
+            header,
+            49,
+            BasicType::String,
+            "sender_comp_id",
+            "set_sender_comp_id",
+        )?,
+        gen_required_accessors(
+            header,
+            56,
+            BasicType::String,
+            "target_comp_id",
+            "set_target_comp_id",
+        )?,
+        gen_required_accessors(
+            header,
+            34,
+            BasicType::SeqNum,
+            "msg_seq_num",
+            "set_msg_seq_num",
+        )?,
+        gen_optional_accessors(
+            header,
+            43,
+            BasicType::Boolean,
+            "poss_dup_flag",
+            "set_poss_dup_flag",
+        )?,
+        gen_required_accessors(
+            header,
+            52,
+            BasicType::UtcTimestamp,
+            "sending_time",
+            "set_sending_time",
+        )?,
+        gen_optional_accessors(
+            header,
+            122,
+            BasicType::UtcTimestamp,
+            "orig_sending_time",
+            "set_orig_sending_time",
+        )?,
+    ];
+    let msg_type_member = find_member(header, 35)?.name();
+    let sending_time_member = find_header_member(header, 52, BasicType::UtcTimestamp)?.name();
+
+    let mut to_admin_arms = Vec::new();
+    let mut from_admin_arms = Vec::new();
+    let mut enums = Vec::new();
+    for (msg_name, admin_fields) in ADMIN_MESSAGES {
+        let msg = find_struct(structs, msg_name).filter(|msg| msg.msg_props().is_some())?;
+        let msg_name = msg.name();
+
+        let mut to_admin_fields = Vec::new();
+        let mut bindings = Vec::new();
+        let mut from_admin_fields = Vec::new();
+        for (field_name, tag, required) in admin_fields.iter() {
+            let field_name = ident(field_name);
+            let member = find_member(msg, *tag);
+            if let Some(Type::Group(_)) = member.map(SimpleMember::type_) {
+                return None;
+            }
+            let value = gen_to_admin_value(member, *required);
+            to_admin_fields.push(quote! { #field_name: #value });
+            if let Some(member) = member {
+                let name = member.name();
+                let value = gen_from_admin_value(member, &field_name, *required);
+                bindings.push(quote! { #field_name });
+                from_admin_fields.push(quote! { #name: #value });
+                if let Type::Enum((enum_name, _)) = member.type_() {
+                    if !enums.contains(enum_name) {
+                        enums.push(enum_name.clone());
+                    }
+                }
+            }
+        }
+
+        to_admin_arms.push(quote! {
+            Message::#msg_name(msg) => Some(AdminMessage::#msg_name {
+                #(#to_admin_fields,)*
+            }),
+        });
+        from_admin_arms.push(quote! {
+            AdminMessage::#msg_name { #(#bindings,)* .. } => Message::#msg_name(#msg_name {
+                #(#from_admin_fields,)*
+                ..Default::default()
+            }),
+        });
+    }
+    let enums_admin_values = enums.iter().map(gen_enum_admin_value);
+
+    Some(quote! {
+        use #runtime_path::session::{AdminMessage, AdminValue, SessionHeader, SessionMessage};
+
+        #(#enums_admin_values)*
+
+        impl SessionHeader for Header {
+            #(#header_accessors)*
+        }
+
+        impl SessionMessage for FixtMessage {
+            type Header = Header;
+            type Body = Message;
+            type MsgType = MsgType;
+
+            fn is_supported_begin_string(begin_string: &FixStr) -> bool {
+                begin_string == BEGIN_STRING
+            }
+
+            fn deserialize(deserializer: Deserializer) -> Result<Box<FixtMessage>, DeserializeError> {
+                FixtMessage::deserialize(deserializer)
+            }
+
+            fn serialize_with(&self, serializer: Serializer) -> Vec<u8> {
+                FixtMessage::serialize_with(self, serializer)
+            }
+
+            fn from_body(body: Box<Message>) -> Box<FixtMessage> {
+                Box::new(FixtMessage {
+                    header: Box::new(Header {
+                        #msg_type_member: body.msg_type(),
+                        #sending_time_member: UtcTimestamp::MIN_UTC,
+                        ..Default::default()
+                    }),
+                    body,
+                    trailer: Box::default(),
+                })
+            }
+
+            fn into_body(self: Box<FixtMessage>) -> Box<Message> {
+                self.body
+            }
+
+            fn header(&self) -> &Header {
+                &self.header
+            }
+
+            fn header_mut(&mut self) -> &mut Header {
+                &mut self.header
+            }
+
+            fn msg_type(&self) -> MsgType {
+                self.body.msg_type()
+            }
+
+            fn msg_cat(&self) -> MsgCat {
+                self.body.msg_cat()
+            }
+
+            fn update_msg_type(&mut self) {
+                self.header.#msg_type_member = self.body.msg_type();
+            }
+
+            fn msg_type_as_fix_str(msg_type: MsgType) -> &'static FixStr {
+                msg_type.as_fix_str()
+            }
+
+            fn msg_type_from_fix_str(msg_type: &FixStr) -> Option<MsgType> {
+                MsgType::from_fix_str(msg_type)
+            }
+
+            #[allow(unreachable_patterns)]
+            fn to_admin(&self) -> Option<AdminMessage> {
+                match &*self.body {
+                    #(#to_admin_arms)*
+                    _ => None,
+                }
+            }
+
+            fn from_admin(admin: AdminMessage) -> Box<FixtMessage> {
+                let body = match admin {
+                    #(#from_admin_arms)*
+                };
+                FixtMessage::from_body(Box::new(body))
+            }
+        }
+    })
+}
@@ -59,6 +59,10 @@ impl Struct {
         &self.name
     }
 
+    pub fn members(&self) -> &[MemberDesc] {
+        &self.members
+    }
+
     pub fn msg_props(&self) -> Option<&MessageProperties> {
         self.msg_props.as_ref()
     }
@@ -101,10 +105,24 @@ impl Struct {
             .gen_deserialize_struct_entries()
             .map(|entry| quote! { #entry, });
 
+        // Fields undefined in dictionary (i.e. custom ones) are part of group
+        // entry, when they are not rejected, other fields end the group.
+        let is_unknown_field = quote! {
+            deserializer.unknown_fields() != UnknownFields::Reject
+                && super::messages::FieldTag::from_tag_num(tag).is_none()
+        };
         let deserialize_loop = if members.is_empty() {
             // No members - no loop
             quote! {
-                if let Some(tag) = deserializer.deserialize_tag_num()? {
+                while let Some(tag) = deserializer.deserialize_tag_num()? {
+                    if #is_unknown_field {
+                        deserializer.deserialize_unknown_field(
+                            tag,
+                            ParseRejectReason::UndefinedTag,
+                            &mut unknown_fields,
+                        )?;
+                        continue;
+                    }
                     if tag == #first_member_tag && last_run || tag != #first_member_tag && !last_run {
                         return Err(deserializer.reject(
                             Some(num_in_group_tag),
@@ -112,6 +130,7 @@ impl Struct {
                         ));
                     }
                     deserializer.put_tag(tag);
+                    break;
                 }
             }
         } else {
@@ -119,6 +138,14 @@ impl Struct {
                 while let Some(tag) = deserializer.deserialize_tag_num()? {
                     match tag {
                         #(#de_match_entries,)*
+                        tag if #is_unknown_field => {
+                            deserializer.deserialize_unknown_field(
+                                tag,
+                                ParseRejectReason::UndefinedTag,
+                                &mut unknown_fields,
+                            )?;
+                            continue;
+                        }
                         tag => {
                             if tag == #first_member_tag && last_run || tag != #first_member_tag && !last_run {
                                 return Err(deserializer.reject(
@@ -160,6 +187,7 @@ impl Struct {
                 // Check if tag of first group member is present
                 #first_member_def
                 #(#variables_definitions)*
+                let mut unknown_fields = Vec::new();
                 if let Some(#first_member_tag) = deserializer.deserialize_tag_num()? {
                     #first_member_deserialize_value
                 } else {
@@ -183,6 +211,7 @@ impl Struct {
                 Ok(#name {
                     #first_member_struct_entry
                     #(#de_struct_entries,)*
+                    unknown_fields,
                 })
             }
         }
@@ -195,7 +224,11 @@ impl Struct {
         let mut de_struct_entries = Vec::with_capacity(self.members.len());
         let mut de_trailer_entries = Vec::with_capacity(self.members.len());
         let mut de_match_entries = Vec::with_capacity(self.members.len()); //self.generate_de_match_entries();
+        let mut header_tags = Vec::new();
         for member in self.msg_props().unwrap().header_members.iter() {
+            if !header_tags.contains(&member.tag_num()) {
+                header_tags.push(member.tag_num());
+            }
             variables_definitions.push(member.gen_opt_variables());
             if let Some(de_match_entry) = member.gen_deserialize_match_entries() {
                 de_match_entries.push(de_match_entry);
@@ -230,25 +263,46 @@ impl Struct {
                 msg_type: MsgType
             ) -> Result<Box<FixtMessage>, DeserializeError> {
                 #(#variables_definitions)*
+                let mut header_unknown_fields = Vec::new();
+                let mut unknown_fields = Vec::new();
+                // Unknown fields preceding first body or trailer field
+                // belong to header
+                let mut in_header = true;
                 while let Some(tag) = deserializer.deserialize_tag_num()? {
+                    if in_header
+                        && !matches!(tag, #(#header_tags)|*)
+                        && FieldTag::from_tag_num(tag).is_some()
+                    {
+                        in_header = false;
+                    }
                     match tag {
                         #(#de_match_entries,)*
                         tag => {
+                            // Only fields undefined in dictionary are subject
+                            // to unknown fields handling, like in groups
                             if FieldTag::from_tag_num(tag).is_some() {
                                 return Err(deserializer.reject(Some(tag), ParseRejectReason::TagNotDefinedForThisMessageType));
-                            } else {
-                                return Err(deserializer.reject(Some(tag), ParseRejectReason::UndefinedTag
-                ));
                             }
+                            deserializer.deserialize_unknown_field(
+                                tag,
+                                ParseRejectReason::UndefinedTag,
+                                if in_header {
+                                    &mut header_unknown_fields
+                                } else {
+                                    &mut unknown_fields
+                                },
+                            )?;
                         },
                     }
                 }
                 Ok(Box::new(FixtMessage {
                     header: Box::new(Header {
                         #(#de_header_entries,)*
+                        unknown_fields: header_unknown_fields,
                     }),
                     body: Box::new(Message::#name(#name {
                         #(#de_struct_entries,)*
+                        unknown_fields,
                     })),
                     trailer: Box::new(Trailer {
                         #(#de_trailer_entries,)*
@@ -278,6 +332,22 @@ impl Struct {
 
         let serialize = self.generate_serialize();
 
+        // Trailer is deserialized together with message body, so unknown
+        // fields between body and trailer are kept in the body. Header keeps
+        // unknown fields preceding the body.
+        let (unknown_fields_def, serialize_unknown_fields) = if self.name == "Trailer" {
+            (None, None)
+        } else {
+            (
+                Some(quote! {
+                    pub unknown_fields: Vec<(TagNum, FixString)>,
+                }),
+                Some(quote! {
+                    serializer.serialize_unknown_fields(&self.unknown_fields);
+                }),
+            )
+        };
+
         let fn_msg_type_msg_cat = if let Some(props) = self.msg_props() {
             let msg_cat = Ident::new(&format!("{:?}", props.msg_cat), Span::call_site());
             Some(quote! {
@@ -299,11 +369,13 @@ impl Struct {
             #[cfg_attr(feature = "deserialize", derive(serde::Deserialize))]
             pub struct #name {
                 #(#members_definitions,)*
+                #unknown_fields_def
             }
 
             impl #name {
                 pub(crate) fn serialize(&self, serializer: &mut Serializer) {
                     #(#serialize;)*
+                    #serialize_unknown_fields
                 }
 
                 #fn_deserialize
@@ -10,9 +10,11 @@ use std::{
     time::Instant,
 };
 
+use anyhow::Context;
 pub use easyfix_dictionary as dictionary;
 use easyfix_dictionary::{Dictionary, ParseRejectReason};
 use proc_macro2::TokenStream;
+use quote::quote;
 
 use crate::gen::Generator;
 
@@ -70,17 +72,12 @@ fn log_duration<T>(msg: &str, action: impl FnOnce() -> T) -> T {
     result
 }
 
-pub fn generate_fix_messages(
+fn load_dictionary(
     fixt_xml_path: Option<impl AsRef<Path>>,
     fix_xml_path: impl AsRef<Path>,
-    fields_file: impl AsRef<Path>,
-    groups_file: impl AsRef<Path>,
-    messages_file: impl AsRef<Path>,
     reject_reason_overrides: Option<HashMap<ParseRejectReason, String>>,
-) -> std::result::Result<(), Box<dyn std::error::Error + 'static>> {
-    eprintln!("fields file path: {}", fields_file.as_ref().display());
-    eprintln!("groups file path: {}", groups_file.as_ref().display());
-    eprintln!("messages file path: {}", messages_file.as_ref().display());
+    overlay_xml_paths: impl IntoIterator<Item = impl AsRef<Path>>,
+) -> std::result::Result<Dictionary, Box<dyn std::error::Error + 'static>> {
     let fix_xml = fs::read_to_string(fix_xml_path)?;
     let mut dictionary = Dictionary::new(reject_reason_overrides);
 
@@ -97,6 +94,61 @@ pub fn generate_fix_messages(
         })?;
     }
 
+    for overlay_xml_path in overlay_xml_paths {
+        let overlay_xml_path = overlay_xml_path.as_ref();
+        log_duration("Overlay XML processed", || {
+            let overlay_xml = fs::read_to_string(overlay_xml_path)?;
+            dictionary.process_overlay_xml(&overlay_xml)
+        })
+        .with_context(|| format!("Failed to apply overlay {}", overlay_xml_path.display()))?;
+    }
+
+    Ok(dictionary)
+}
+
+/// Generate FIX messages into separate `fields`, `groups` and `messages`
+/// files.
+pub fn generate_fix_messages(
+    fixt_xml_path: Option<impl AsRef<Path>>,
+    fix_xml_path: impl AsRef<Path>,
+    fields_file: impl AsRef<Path>,
+    groups_file: impl AsRef<Path>,
+    messages_file: impl AsRef<Path>,
+    reject_reason_overrides: Option<HashMap<ParseRejectReason, String>>,
+) -> std::result::Result<(), Box<dyn std::error::Error + 'static>> {
+    generate_fix_messages_with_overlays(
+        fixt_xml_path,
+        fix_xml_path,
+        fields_file,
+        groups_file,
+        messages_file,
+        reject_reason_overrides,
+        std::iter::empty::<&Path>(),
+    )
+}
+
+/// Same as [`generate_fix_messages`], with overlays from `overlay_xml_paths`
+/// layered, in order, on top of FIX XML (see
+/// [`Dictionary::process_overlay_xml`]).
+pub fn generate_fix_messages_with_overlays(
+    fixt_xml_path: Option<impl AsRef<Path>>,
+    fix_xml_path: impl AsRef<Path>,
+    fields_file: impl AsRef<Path>,
+    groups_file: impl AsRef<Path>,
+    messages_file: impl AsRef<Path>,
+    reject_reason_overrides: Option<HashMap<ParseRejectReason, String>>,
+    overlay_xml_paths: impl IntoIterator<Item = impl AsRef<Path>>,
+) -> std::result::Result<(), Box<dyn std::error::Error + 'static>> {
+    eprintln!("fields file path: {}", fields_file.as_ref().display());
+    eprintln!("groups file path: {}", groups_file.as_ref().display());
+    eprintln!("messages file path: {}", messages_file.as_ref().display());
+    let dictionary = load_dictionary(
+        fixt_xml_path,
+        fix_xml_path,
+        reject_reason_overrides,
+        overlay_xml_paths,
+    )?;
+
     let generator = log_duration("Generator ready", || Generator::new(&dictionary));
 
     create_source_file(
@@ -114,3 +166,75 @@ pub fn generate_fix_messages(
 
     Ok(())
 }
+
+/// Generate FIX messages into single file with `fields`, `groups` and
+/// `messages` modules, so messages of several FIX versions can live side
+/// by side in one crate, each included into its own module:
+///
+/// ```ignore
+/// pub mod fix42 {
+///     include!(concat!(env!("OUT_DIR"), "/fix42.rs"));
+/// }
+/// ```
+///
+/// Types shared by all versions (basic types, serializer, deserializer,
+/// `MsgCat`) are used from `easyfix-messages` crate, available
+/// at `runtime_path` (i.e. `::easyfix_messages`). Serde traits are derived
+/// when `serialize`/`deserialize` features of the including crate are enabled.
+/// Overlays from `overlay_xml_paths` are layered, in order, on top of FIX XML
+/// (see [`Dictionary::process_overlay_xml`]).
+///
+/// When dictionary defines all session level messages, module's
+/// `FixtMessage` implements `SessionMessage`, so `easyfix-session` can run
+/// sessions on it, i.e. with
+/// `Acceptor::<_, fix42::messages::FixtMessage>::with_codec`. Acceptor
+/// (or initiator) runs all its sessions on one codec, sessions of several
+/// versions are run side by side by separate acceptors.
+/// See `multi-version-test` crate for an example.
+pub fn generate_fix_messages_module(
+    runtime_path: &str,
+    fixt_xml_path: Option<impl AsRef<Path>>,
+    fix_xml_path: impl AsRef<Path>,
+    module_file: impl AsRef<Path>,
+    reject_reason_overrides: Option<HashMap<ParseRejectReason, String>>,
+    overlay_xml_paths: impl IntoIterator<Item = impl AsRef<Path>>,
+) -> std::result::Result<(), Box<dyn std::error::Error + 'static>> {
+    eprintln!("module file path: {}", module_file.as_ref().display());
+    let runtime_path = runtime_path.parse::<TokenStream>()?;
+    let dictionary = load_dictionary(
+        fixt_xml_path,
+        fix_xml_path,
+        reject_reason_overrides,
+        overlay_xml_paths,
+    )?;
+
+    let mut generator = log_duration("Generator ready", || Generator::new(&dictionary));
+    generator.set_runtime_path(runtime_path.clone());
+
+    let fields = log_duration("Fields token stream", || generator.generate_fields());
+    let groups = log_duration("Groups token stream", || generator.generate_groups());
+    let messages = log_duration("Messages token stream", || generator.generate_messages());
+    create_source_file(
+        quote! {
+            pub mod fields {
+                pub use #runtime_path::fields::basic_types::*;
+
+                #fields
+            }
+
+            #[allow(unused_assignments)]
+            pub mod groups {
+                #groups
+            }
+
+            pub mod messages {
+                pub use #runtime_path::messages::MsgCat;
+
+                #messages
+            }
+        },
+        module_file,
+    )?;
+
+    Ok(())
+}
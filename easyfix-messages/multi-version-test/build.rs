@@ -0,0 +1,35 @@
+use std::{env, path::PathBuf};
+
+use easyfix_messages_gen::generate_fix_messages_module;
+
+fn main() {
+    let dir = env::var("CARGO_MANIFEST_DIR").unwrap();
+    let out_path = PathBuf::from(env::var("OUT_DIR").expect("OUT_DIR undefined"));
+    let fixt_xml_path = format!("{}/../xml/FIXT11.xml", dir);
+    let fix42_xml_path = format!("{}/xml/FIX42.xml", dir);
+    let fix50_xml_path = format!("{}/xml/FIX50.xml", dir);
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed={}", fixt_xml_path);
+    println!("cargo:rerun-if-changed={}", fix42_xml_path);
+    println!("cargo:rerun-if-changed={}", fix50_xml_path);
+
+    // FIX 4.2 description comes with its own header and trailer
+    generate_fix_messages_module(
+        "::easyfix_messages",
+        None::<&str>,
+        fix42_xml_path,
+        out_path.join("fix42.rs"),
+        None,
+        std::iter::empty::<&str>(),
+    )
+    .unwrap();
+    generate_fix_messages_module(
+        "::easyfix_messages",
+        Some(fixt_xml_path),
+        fix50_xml_path,
+        out_path.join("fix50.rs"),
+        None,
+        std::iter::empty::<&str>(),
+    )
+    .unwrap();
+}
@@ -0,0 +1,11 @@
+//! Messages of FIX.4.2 and FIXT.1.1/FIX.5.0 generated into one crate
+//! with `generate_fix_messages_module`, from small subsets of both
+//! dictionaries.
+
+pub mod fix42 {
+    include!(concat!(env!("OUT_DIR"), "/fix42.rs"));
+}
+
+pub mod fix50 {
+    include!(concat!(env!("OUT_DIR"), "/fix50.rs"));
+}
@@ -0,0 +1,113 @@
+use assert_matches::assert_matches;
+use easyfix_messages::{deserializer::DeserializeError, session::SessionMessage};
+use easyfix_messages_multi_version_test::{fix42, fix50};
+
+fn fix_string(s: &str) -> easyfix_messages::fields::FixString {
+    easyfix_messages::fields::FixString::from_ascii_lossy(s.as_bytes().to_vec())
+}
+
+fn fix42_order() -> Vec<u8> {
+    use fix42::{
+        fields::{HandlInst, MsgType, OrdType, Side, UtcTimestamp},
+        messages::{FixtMessage, Header, Message, NewOrderSingle, BEGIN_STRING},
+    };
+
+    FixtMessage {
+        header: Box::new(Header {
+            begin_string: BEGIN_STRING.to_owned(),
+            msg_type: MsgType::NewOrderSingle,
+            sender_comp_id: fix_string("sender"),
+            target_comp_id: fix_string("target"),
+            msg_seq_num: 1,
+            sending_time: UtcTimestamp::now(),
+            ..Default::default()
+        }),
+        body: Box::new(Message::NewOrderSingle(NewOrderSingle {
+            cl_ord_id: fix_string("order"),
+            handl_inst: HandlInst::ManualOrderBestExecution,
+            symbol: fix_string("EASY"),
+            side: Side::Buy,
+            transact_time: UtcTimestamp::now(),
+            ord_type: OrdType::Market,
+            ..Default::default()
+        })),
+        trailer: Box::default(),
+    }
+    .serialize()
+}
+
+fn fix50_order() -> Vec<u8> {
+    use fix50::{
+        fields::{MsgType, OrdType, Side, UtcTimestamp},
+        messages::{FixtMessage, Header, Message, NewOrderSingle, BEGIN_STRING},
+    };
+
+    FixtMessage {
+        header: Box::new(Header {
+            begin_string: BEGIN_STRING.to_owned(),
+            msg_type: MsgType::NewOrderSingle,
+            sender_comp_id: fix_string("sender"),
+            target_comp_id: fix_string("target"),
+            msg_seq_num: 1,
+            sending_time: UtcTimestamp::now(),
+            ..Default::default()
+        }),
+        body: Box::new(Message::NewOrderSingle(NewOrderSingle {
+            cl_ord_id: fix_string("order"),
+            symbol: fix_string("EASY"),
+            side: Side::Sell,
+            transact_time: UtcTimestamp::now(),
+            ord_type: OrdType::Limit,
+            ..Default::default()
+        })),
+        trailer: Box::default(),
+    }
+    .serialize()
+}
+
+#[test]
+fn begin_strings() {
+    assert_eq!(fix42::messages::BEGIN_STRING.as_utf8(), "FIX.4.2");
+    assert_eq!(fix50::messages::BEGIN_STRING.as_utf8(), "FIXT.1.1");
+    assert!(
+        !<fix50::messages::FixtMessage as SessionMessage>::is_supported_begin_string(
+            fix42::messages::BEGIN_STRING
+        )
+    );
+}
+
+#[test]
+fn fix42_message_round_trip() {
+    let msg = fix42::messages::FixtMessage::from_bytes(&fix42_order()).unwrap();
+    let fix42::messages::Message::NewOrderSingle(order) = *msg.body else {
+        panic!("unexpected message: {:?}", msg.msg_type());
+    };
+    assert_eq!(order.cl_ord_id, fix_string("order"));
+    assert_eq!(
+        order.handl_inst,
+        fix42::fields::HandlInst::ManualOrderBestExecution
+    );
+    assert_eq!(order.side, fix42::fields::Side::Buy);
+}
+
+#[test]
+fn fix50_message_round_trip() {
+    let msg = fix50::messages::FixtMessage::from_bytes(&fix50_order()).unwrap();
+    let fix50::messages::Message::NewOrderSingle(order) = *msg.body else {
+        panic!("unexpected message: {:?}", msg.msg_type());
+    };
+    assert_eq!(order.cl_ord_id, fix_string("order"));
+    assert_eq!(order.ord_type, fix50::fields::OrdType::Limit);
+}
+
+#[test]
+fn messages_are_parsed_by_own_version_only() {
+    assert_matches!(
+        fix50::messages::FixtMessage::from_bytes(&fix42_order()),
+        Err(DeserializeError::GarbledMessage(_))
+    );
+    assert_matches!(
+        fix42::messages::FixtMessage::from_bytes(&fix50_order()),
+        Err(DeserializeError::GarbledMessage(_))
+    );
+}
@@ -246,6 +246,22 @@ pub fn raw_message(bytes: &[u8]) -> Result<(&[u8], RawMessage), RawMessageError>
 // TODO:
 // enum GarbledReason
 
+/// How fields undefined in dictionary (i.e. custom ones) are handled.
+///
+/// Fields defined in dictionary, but not for given message type, are
+/// always rejected with `TagNotDefinedForThisMessageType` reason.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub enum UnknownFields {
+    /// Reject message with `UndefinedTag` reason.
+    #[default]
+    Reject,
+    /// Skip the field.
+    Ignore,
+    /// Collect the field into `unknown_fields` of message header, body
+    /// (or group entry) and send it back when message is serialized.
+    Keep,
+}
+
 #[derive(Debug)]
 pub struct Deserializer<'de> {
     raw_message: RawMessage<'de>,
@@ -256,6 +272,7 @@ pub struct Deserializer<'de> {
     // Used to put tag back to deserializer, when switching to deserialization
     // another message section.
     tmp_tag: Option<TagNum>,
+    unknown_fields: UnknownFields,
 }
 
 impl Deserializer<'_> {
@@ -268,7 +285,37 @@ impl Deserializer<'_> {
             seq_num: None,
             current_tag: None,
             tmp_tag: None,
+            unknown_fields: UnknownFields::Reject,
+        }
+    }
+
+    pub fn unknown_fields(&self) -> UnknownFields {
+        self.unknown_fields
+    }
+
+    pub fn set_unknown_fields(&mut self, unknown_fields: UnknownFields) {
+        self.unknown_fields = unknown_fields;
+    }
+
+    /// Handle value of field not defined for the message, according
+    /// to `UnknownFields` mode.
+    pub fn deserialize_unknown_field(
+        &mut self,
+        tag: TagNum,
+        reason: ParseRejectReason,
+        unknown_fields: &mut Vec<(TagNum, FixString)>,
+    ) -> Result<(), DeserializeError> {
+        match self.unknown_fields {
+            UnknownFields::Reject => return Err(self.reject(Some(tag), reason)),
+            UnknownFields::Ignore => {
+                self.deserialize_str()?;
+            }
+            UnknownFields::Keep => {
+                let value = self.deserialize_string()?;
+                unknown_fields.push((tag, value));
+            }
         }
+        Ok(())
     }
 
     pub fn begin_string(&self) -> FixString {
@@ -1794,7 +1841,7 @@ mod tests {
     use assert_matches::assert_matches;
     use chrono::{DateTime, NaiveDate, NaiveTime, TimeZone, Utc};
 
-    use super::{deserialize_tag, raw_message, Deserializer, RawMessage};
+    use super::{deserialize_tag, raw_message, Deserializer, RawMessage, UnknownFields};
     use crate::{
         deserializer::{deserialize_checksum, RawMessageError},
         fields::{LocalMktDate, Price, TimePrecision},
@@ -1923,6 +1970,7 @@ mod tests {
             seq_num: Some(1),
             current_tag: None,
             tmp_tag: None,
+            unknown_fields: UnknownFields::Reject,
         }
     }
 
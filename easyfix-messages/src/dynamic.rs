@@ -0,0 +1,838 @@
+//! Messages driven by dictionary loaded at runtime.
+//!
+//! Generated messages know only fields from the compile-time dictionary.
+//! [`DynamicMessage`] is built with [`Schema`] created from any
+//! [`Dictionary`], keeps every received field (including tags missing from
+//! the dictionary) in the wire order and can be converted to and from
+//! [`FixtMessage`], so tools can handle venue specific dialects without
+//! recompilation.
+
+use std::{collections::HashMap, fmt};
+
+pub use easyfix_dictionary::Dictionary;
+use easyfix_dictionary::{BasicType, Member, MemberKind};
+
+use crate::{
+    deserializer::{raw_message, DeserializeError, Deserializer, ParseRejectReason, RawMessage},
+    fields::basic_types::*,
+    messages::FixtMessage,
+    serializer::Serializer,
+};
+
+// BeginString<8>, BodyLength<9> and CheckSum<10> are part of message framing,
+// they are never stored in `FieldMap`.
+const FRAMING_TAGS: &[TagNum] = &[8, 9, 10];
+const MSG_TYPE_TAG: TagNum = 35;
+
+#[derive(Debug, thiserror::Error)]
+pub enum DynamicError {
+    #[error("garbled message: {0}")]
+    GarbledMessage(String),
+    #[error("{reason:?} (tag={tag:?})")]
+    Reject {
+        tag: Option<TagNum>,
+        reason: ParseRejectReason,
+    },
+    #[error("field {0} not found")]
+    FieldNotFound(TagNum),
+    #[error(transparent)]
+    Deserialize(#[from] DeserializeError),
+}
+
+impl DynamicError {
+    fn reject(tag: TagNum, reason: ParseRejectReason) -> DynamicError {
+        DynamicError::Reject {
+            tag: Some(tag),
+            reason,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct FieldDef {
+    name: String,
+    type_: BasicType,
+    values: Option<Vec<Vec<u8>>>,
+}
+
+#[derive(Debug)]
+struct LayoutMember {
+    tag: TagNum,
+    required: bool,
+    // Layout of group entry, when member is NumInGroup field
+    group: Option<Layout>,
+}
+
+/// Flattened list of message (or group entry) fields, with components
+/// resolved to fields they consist of.
+#[derive(Debug, Default)]
+struct Layout {
+    members: Vec<LayoutMember>,
+    index: HashMap<TagNum, usize>,
+}
+
+impl Layout {
+    fn new(dictionary: &Dictionary, members: &[Member], skip: &[TagNum]) -> anyhow::Result<Layout> {
+        let mut layout = Layout::default();
+        layout.extend(dictionary, members, true, skip)?;
+        Ok(layout)
+    }
+
+    fn extend(
+        &mut self,
+        dictionary: &Dictionary,
+        members: &[Member],
+        required: bool,
+        skip: &[TagNum],
+    ) -> anyhow::Result<()> {
+        for member in members {
+            let required = required && member.required();
+            match member.kind() {
+                MemberKind::Field => {
+                    let tag = field_tag(dictionary, member.name())?;
+                    if !skip.contains(&tag) {
+                        self.push(tag, required, None);
+                    }
+                }
+                MemberKind::Component => {
+                    let component = dictionary
+                        .component(member.name())
+                        .ok_or_else(|| anyhow::anyhow!("Unknown component `{}`", member.name()))?;
+                    if let Some(num_in_group) = component.number_of_elements() {
+                        let tag = field_tag(dictionary, num_in_group.name())?;
+                        let group = Layout::new(dictionary, component.members(), &[])?;
+                        if group.members.is_empty() {
+                            anyhow::bail!("Empty group `{}`", component.name());
+                        }
+                        self.push(tag, required && num_in_group.required(), Some(group));
+                    } else {
+                        self.extend(dictionary, component.members(), required, skip)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn push(&mut self, tag: TagNum, required: bool, group: Option<Layout>) {
+        if !self.index.contains_key(&tag) {
+            self.index.insert(tag, self.members.len());
+            self.members.push(LayoutMember {
+                tag,
+                required,
+                group,
+            });
+        }
+    }
+
+    fn member(&self, tag: TagNum) -> Option<&LayoutMember> {
+        self.index.get(&tag).map(|idx| &self.members[*idx])
+    }
+
+    fn contains(&self, tag: TagNum) -> bool {
+        self.index.contains_key(&tag)
+    }
+
+    fn group(&self, tag: TagNum) -> Option<&Layout> {
+        self.member(tag).and_then(|member| member.group.as_ref())
+    }
+
+    /// First field of group entry.
+    fn delimiter(&self) -> TagNum {
+        self.members[0].tag
+    }
+}
+
+fn field_tag(dictionary: &Dictionary, name: &str) -> anyhow::Result<TagNum> {
+    dictionary
+        .fields_by_name()
+        .get(name)
+        .map(|field| field.number())
+        .ok_or_else(|| anyhow::anyhow!("Unknown field `{}`", name))
+}
+
+/// Messages layout, built from dictionary loaded at runtime.
+#[derive(Debug)]
+pub struct Schema {
+    fields: HashMap<TagNum, FieldDef>,
+    tags_by_name: HashMap<String, TagNum>,
+    header: Layout,
+    trailer: Layout,
+    messages: HashMap<FixString, Layout>,
+}
+
+impl Schema {
+    pub fn new(dictionary: &Dictionary) -> anyhow::Result<Schema> {
+        let fields = dictionary
+            .fields()
+            .values()
+            .map(|field| {
+                (
+                    field.number(),
+                    FieldDef {
+                        name: field.name().to_owned(),
+                        type_: field.type_(),
+                        values: field.values().map(|values| {
+                            values
+                                .iter()
+                                .map(|value| value.value().as_bytes().to_vec())
+                                .collect()
+                        }),
+                    },
+                )
+            })
+            .collect::<HashMap<_, _>>();
+        let tags_by_name = fields
+            .iter()
+            .map(|(tag, field)| (field.name.clone(), *tag))
+            .collect();
+        let header = Layout::new(dictionary, dictionary.header()?.members(), FRAMING_TAGS)?;
+        let trailer = Layout::new(dictionary, dictionary.trailer()?.members(), FRAMING_TAGS)?;
+        let mut messages = HashMap::new();
+        for (msg_type, message) in dictionary.messages() {
+            let layout = Layout::new(dictionary, message.members(), &[])?;
+            messages.insert(FixString::from_ascii_lossy(msg_type.to_vec()), layout);
+        }
+        Ok(Schema {
+            fields,
+            tags_by_name,
+            header,
+            trailer,
+            messages,
+        })
+    }
+
+    /// Tag of field with given name.
+    pub fn tag(&self, name: &str) -> Option<TagNum> {
+        self.tags_by_name.get(name).copied()
+    }
+
+    /// Name of field with given tag.
+    pub fn field_name(&self, tag: TagNum) -> Option<&str> {
+        self.fields.get(&tag).map(|field| field.name.as_str())
+    }
+
+    pub fn field_type(&self, tag: TagNum) -> Option<BasicType> {
+        self.fields.get(&tag).map(|field| field.type_)
+    }
+
+    /// Check if message type is defined in dictionary.
+    pub fn is_known_msg_type(&self, msg_type: &FixStr) -> bool {
+        self.messages.contains_key(msg_type)
+    }
+
+    fn message(&self, msg_type: &FixStr) -> Option<&Layout> {
+        self.messages.get(msg_type)
+    }
+
+    fn validate_map(&self, map: &FieldMap, layout: &Layout) -> Result<(), DynamicError> {
+        for (idx, (tag, value)) in map.fields.iter().enumerate() {
+            let tag = *tag;
+            if map.fields[..idx].iter().any(|(t, _)| *t == tag) {
+                return Err(DynamicError::reject(
+                    tag,
+                    ParseRejectReason::TagAppearsMoreThanOnce,
+                ));
+            }
+            let Some(field) = self.fields.get(&tag) else {
+                return Err(DynamicError::reject(tag, ParseRejectReason::UndefinedTag));
+            };
+            let Some(member) = layout.member(tag) else {
+                return Err(DynamicError::reject(
+                    tag,
+                    ParseRejectReason::TagNotDefinedForThisMessageType,
+                ));
+            };
+            match (value, &member.group) {
+                (FieldValue::Value(value), None) => validate_value(tag, field, value)?,
+                (FieldValue::Group(entries), Some(group)) => {
+                    for entry in entries {
+                        if entry.fields.first().map(|(tag, _)| *tag) != Some(group.delimiter()) {
+                            return Err(DynamicError::reject(
+                                tag,
+                                ParseRejectReason::RepeatingGroupFieldsOutOfOrder,
+                            ));
+                        }
+                        self.validate_map(entry, group)?;
+                    }
+                }
+                _ => {
+                    return Err(DynamicError::reject(
+                        tag,
+                        ParseRejectReason::IncorrectDataFormatForValue,
+                    ))
+                }
+            }
+        }
+        for member in &layout.members {
+            if member.required && !map.contains(member.tag) {
+                return Err(DynamicError::reject(
+                    member.tag,
+                    ParseRejectReason::RequiredTagMissing,
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn validate_value(tag: TagNum, field: &FieldDef, value: &[u8]) -> Result<(), DynamicError> {
+    let valid_format = match field.type_ {
+        BasicType::Int => deserialize_value(value, |d| d.deserialize_int()).is_ok(),
+        BasicType::Length => deserialize_value(value, |d| d.deserialize_length()).is_ok(),
+        BasicType::SeqNum => deserialize_value(value, |d| d.deserialize_seq_num()).is_ok(),
+        BasicType::NumInGroup => deserialize_value(value, |d| d.deserialize_num_in_group()).is_ok(),
+        BasicType::Amt
+        | BasicType::Float
+        | BasicType::Percentage
+        | BasicType::Price
+        | BasicType::PriceOffset
+        | BasicType::Qty => deserialize_value(value, |d| d.deserialize_float()).is_ok(),
+        BasicType::Boolean => deserialize_value(value, |d| d.deserialize_boolean()).is_ok(),
+        BasicType::Char => deserialize_value(value, |d| d.deserialize_char()).is_ok(),
+        BasicType::MultipleCharValue => {
+            deserialize_value(value, |d| d.deserialize_multiple_char_value()).is_ok()
+        }
+        BasicType::Country => deserialize_value(value, |d| d.deserialize_country()).is_ok(),
+        BasicType::Currency => deserialize_value(value, |d| d.deserialize_currency()).is_ok(),
+        BasicType::Exchange => deserialize_value(value, |d| d.deserialize_exchange()).is_ok(),
+        BasicType::MonthYear => deserialize_value(value, |d| d.deserialize_month_year()).is_ok(),
+        BasicType::Language => deserialize_value(value, |d| d.deserialize_language()).is_ok(),
+        BasicType::UtcTimestamp => {
+            deserialize_value(value, |d| d.deserialize_utc_timestamp()).is_ok()
+        }
+        BasicType::UtcTimeOnly => {
+            deserialize_value(value, |d| d.deserialize_utc_time_only()).is_ok()
+        }
+        BasicType::UtcDateOnly => {
+            deserialize_value(value, |d| d.deserialize_utc_date_only()).is_ok()
+        }
+        BasicType::LocalMktDate => {
+            deserialize_value(value, |d| d.deserialize_local_mkt_date()).is_ok()
+        }
+        BasicType::TzTimestamp => {
+            deserialize_value(value, |d| d.deserialize_tz_timestamp()).is_ok()
+        }
+        BasicType::TzTimeOnly => deserialize_value(value, |d| d.deserialize_tz_timeonly()).is_ok(),
+        BasicType::String | BasicType::MultipleStringValue => FixStr::from_ascii(value).is_ok(),
+        BasicType::Data | BasicType::XmlData => true,
+    };
+    if !valid_format {
+        return Err(DynamicError::reject(
+            tag,
+            ParseRejectReason::IncorrectDataFormatForValue,
+        ));
+    }
+
+    if let Some(values) = &field.values {
+        let is_valid = |value: &[u8]| values.iter().any(|v| v == value);
+        let valid_value = match field.type_ {
+            BasicType::MultipleCharValue | BasicType::MultipleStringValue => {
+                value.split(|b| *b == b' ').all(is_valid)
+            }
+            _ => is_valid(value),
+        };
+        if !valid_value {
+            return Err(DynamicError::reject(
+                tag,
+                ParseRejectReason::ValueIsIncorrect,
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Deserialize single field value with the same rules as generated messages.
+fn deserialize_value<T>(
+    value: &[u8],
+    deserialize: impl FnOnce(&mut Deserializer<'_>) -> Result<T, DeserializeError>,
+) -> Result<T, DeserializeError> {
+    let mut buf = Vec::with_capacity(value.len() + 1);
+    buf.extend_from_slice(value);
+    buf.push(b'\x01');
+    let mut deserializer = Deserializer::from_raw_message(RawMessage {
+        begin_string: FixStr::from_ascii(b"").expect("empty string is valid FixStr"),
+        body: &buf,
+        checksum: 0,
+    });
+    // Don't let rejects look for MsgSeqNum<34> in the value
+    deserializer.set_seq_num(0);
+    let result = deserialize(&mut deserializer)?;
+    // Whole value has to be consumed
+    match deserializer.deserialize_tag_num() {
+        Ok(None) => Ok(result),
+        _ => Err(DeserializeError::GarbledMessage(
+            "trailing characters in value".into(),
+        )),
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum FieldValue {
+    Value(Vec<u8>),
+    /// Repeating group entries, stored under NumInGroup field.
+    Group(Vec<FieldMap>),
+}
+
+/// Ordered list of fields, i.e. message section or repeating group entry.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FieldMap {
+    fields: Vec<(TagNum, FieldValue)>,
+}
+
+impl FieldMap {
+    pub fn new() -> FieldMap {
+        FieldMap::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (TagNum, &FieldValue)> {
+        self.fields.iter().map(|(tag, value)| (*tag, value))
+    }
+
+    pub fn contains(&self, tag: TagNum) -> bool {
+        self.fields.iter().any(|(t, _)| *t == tag)
+    }
+
+    pub fn get(&self, tag: TagNum) -> Option<&FieldValue> {
+        self.fields
+            .iter()
+            .find(|(t, _)| *t == tag)
+            .map(|(_, value)| value)
+    }
+
+    /// Set field value, replacing the existing one in place or appending
+    /// new field at the end.
+    pub fn set(&mut self, tag: TagNum, value: FieldValue) {
+        match self.fields.iter_mut().find(|(t, _)| *t == tag) {
+            Some((_, v)) => *v = value,
+            None => self.fields.push((tag, value)),
+        }
+    }
+
+    pub fn remove(&mut self, tag: TagNum) -> Option<FieldValue> {
+        let idx = self.fields.iter().position(|(t, _)| *t == tag)?;
+        Some(self.fields.remove(idx).1)
+    }
+
+    /// Raw field value, `None` when field is missing or it's a group.
+    pub fn get_raw(&self, tag: TagNum) -> Option<&[u8]> {
+        match self.get(tag) {
+            Some(FieldValue::Value(value)) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn set_raw(&mut self, tag: TagNum, value: impl Into<Vec<u8>>) {
+        self.set(tag, FieldValue::Value(value.into()));
+    }
+
+    fn get_value<T>(
+        &self,
+        tag: TagNum,
+        deserialize: impl FnOnce(&mut Deserializer<'_>) -> Result<T, DeserializeError>,
+    ) -> Result<T, DynamicError> {
+        let value = self.get_raw(tag).ok_or(DynamicError::FieldNotFound(tag))?;
+        deserialize_value(value, deserialize)
+            .map_err(|_| DynamicError::reject(tag, ParseRejectReason::IncorrectDataFormatForValue))
+    }
+
+    fn set_value(&mut self, tag: TagNum, serialize: impl FnOnce(&mut Serializer)) {
+        let mut serializer = Serializer::new();
+        serialize(&mut serializer);
+        self.set_raw(tag, serializer.take());
+    }
+
+    pub fn get_str(&self, tag: TagNum) -> Result<&FixStr, DynamicError> {
+        let value = self.get_raw(tag).ok_or(DynamicError::FieldNotFound(tag))?;
+        FixStr::from_ascii(value)
+            .map_err(|_| DynamicError::reject(tag, ParseRejectReason::IncorrectDataFormatForValue))
+    }
+
+    pub fn get_int(&self, tag: TagNum) -> Result<Int, DynamicError> {
+        self.get_value(tag, |d| d.deserialize_int())
+    }
+
+    pub fn get_seq_num(&self, tag: TagNum) -> Result<SeqNum, DynamicError> {
+        self.get_value(tag, |d| d.deserialize_seq_num())
+    }
+
+    /// Get value of Float based field (Qty, Price, Amt etc.).
+    pub fn get_float(&self, tag: TagNum) -> Result<Float, DynamicError> {
+        self.get_value(tag, |d| d.deserialize_float())
+    }
+
+    pub fn get_boolean(&self, tag: TagNum) -> Result<Boolean, DynamicError> {
+        self.get_value(tag, |d| d.deserialize_boolean())
+    }
+
+    pub fn get_char(&self, tag: TagNum) -> Result<Char, DynamicError> {
+        self.get_value(tag, |d| d.deserialize_char())
+    }
+
+    pub fn get_utc_timestamp(&self, tag: TagNum) -> Result<UtcTimestamp, DynamicError> {
+        self.get_value(tag, |d| d.deserialize_utc_timestamp())
+    }
+
+    pub fn get_utc_date_only(&self, tag: TagNum) -> Result<UtcDateOnly, DynamicError> {
+        self.get_value(tag, |d| d.deserialize_utc_date_only())
+    }
+
+    pub fn set_str(&mut self, tag: TagNum, value: &FixStr) {
+        self.set_raw(tag, value.as_bytes());
+    }
+
+    pub fn set_int(&mut self, tag: TagNum, value: Int) {
+        self.set_value(tag, |serializer| serializer.serialize_int(&value));
+    }
+
+    pub fn set_seq_num(&mut self, tag: TagNum, value: SeqNum) {
+        self.set_value(tag, |serializer| serializer.serialize_seq_num(&value));
+    }
+
+    pub fn set_float(&mut self, tag: TagNum, value: Float) {
+        self.set_value(tag, |serializer| serializer.serialize_float(&value));
+    }
+
+    pub fn set_boolean(&mut self, tag: TagNum, value: Boolean) {
+        self.set_value(tag, |serializer| serializer.serialize_boolean(&value));
+    }
+
+    pub fn set_char(&mut self, tag: TagNum, value: Char) {
+        self.set_value(tag, |serializer| serializer.serialize_char(&value));
+    }
+
+    pub fn set_utc_timestamp(&mut self, tag: TagNum, value: &UtcTimestamp) {
+        self.set_value(tag, |serializer| serializer.serialize_utc_timestamp(value));
+    }
+
+    pub fn set_utc_date_only(&mut self, tag: TagNum, value: &UtcDateOnly) {
+        self.set_value(tag, |serializer| serializer.serialize_utc_date_only(value));
+    }
+
+    /// Entries of repeating group with given NumInGroup tag.
+    pub fn group(&self, tag: TagNum) -> Result<&[FieldMap], DynamicError> {
+        match self.get(tag) {
+            Some(FieldValue::Group(entries)) => Ok(entries),
+            _ => Err(DynamicError::FieldNotFound(tag)),
+        }
+    }
+
+    pub fn group_mut(&mut self, tag: TagNum) -> Result<&mut Vec<FieldMap>, DynamicError> {
+        match self.fields.iter_mut().find(|(t, _)| *t == tag) {
+            Some((_, FieldValue::Group(entries))) => Ok(entries),
+            _ => Err(DynamicError::FieldNotFound(tag)),
+        }
+    }
+
+    pub fn set_group(&mut self, tag: TagNum, entries: Vec<FieldMap>) {
+        self.set(tag, FieldValue::Group(entries));
+    }
+
+    fn serialize(&self, output: &mut Vec<u8>, skip: &[TagNum]) {
+        let mut buffer = itoa::Buffer::new();
+        for (tag, value) in &self.fields {
+            if skip.contains(tag) {
+                continue;
+            }
+            output.extend_from_slice(buffer.format(*tag).as_bytes());
+            output.push(b'=');
+            match value {
+                FieldValue::Value(value) => {
+                    output.extend_from_slice(value);
+                    output.push(b'\x01');
+                }
+                FieldValue::Group(entries) => {
+                    output.extend_from_slice(buffer.format(entries.len()).as_bytes());
+                    output.push(b'\x01');
+                    for entry in entries {
+                        entry.serialize(output, &[]);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Splits message body into fields, honoring Length fields preceding
+/// Data fields, which may contain SOH.
+fn split_fields<'a>(
+    schema: &Schema,
+    mut buf: &'a [u8],
+) -> Result<Vec<(TagNum, &'a [u8])>, DynamicError> {
+    let mut fields = Vec::new();
+    let mut data_len = None;
+    while !buf.is_empty() {
+        let Some(eq_idx) = buf.iter().position(|b| *b == b'=') else {
+            return Err(DynamicError::GarbledMessage("no value after tag".into()));
+        };
+        let tag = match &buf[..eq_idx] {
+            [b'1'..=b'9', ..] => std::str::from_utf8(&buf[..eq_idx])
+                .ok()
+                .and_then(|tag| tag.parse::<TagNum>().ok()),
+            _ => None,
+        }
+        .ok_or(DynamicError::Reject {
+            tag: None,
+            reason: ParseRejectReason::InvalidTagNumber,
+        })?;
+        buf = &buf[eq_idx + 1..];
+
+        let field_type = schema.field_type(tag);
+        let value_len = match (data_len.take(), field_type) {
+            (Some(len), Some(BasicType::Data | BasicType::XmlData)) => len,
+            _ => buf.iter().position(|b| *b == b'\x01').unwrap_or(buf.len()),
+        };
+        if buf.get(value_len) != Some(&b'\x01') {
+            return Err(DynamicError::GarbledMessage(format!(
+                "no more data to parse tag {tag}"
+            )));
+        }
+        let value = &buf[..value_len];
+        if value.is_empty() {
+            return Err(DynamicError::reject(
+                tag,
+                ParseRejectReason::TagSpecifiedWithoutAValue,
+            ));
+        }
+        if field_type == Some(BasicType::Length) {
+            data_len = deserialize_value(value, |d| d.deserialize_length())
+                .ok()
+                .map(usize::from);
+        }
+        fields.push((tag, value));
+        buf = &buf[value_len + 1..];
+    }
+    Ok(fields)
+}
+
+struct Parser<'a> {
+    schema: &'a Schema,
+    fields: Vec<(TagNum, &'a [u8])>,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<TagNum> {
+        self.fields.get(self.pos).map(|(tag, _)| *tag)
+    }
+
+    fn next(&mut self) -> Option<(TagNum, &'a [u8])> {
+        let field = self.fields.get(self.pos).copied();
+        self.pos += 1;
+        field
+    }
+
+    fn parse_field(&mut self, layout: &Layout, map: &mut FieldMap) -> Result<(), DynamicError> {
+        let Some((tag, value)) = self.next() else {
+            return Ok(());
+        };
+        match layout.group(tag) {
+            Some(group) => {
+                let entries = self.parse_group(tag, value, group)?;
+                map.fields.push((tag, FieldValue::Group(entries)));
+            }
+            None => map.fields.push((tag, FieldValue::Value(value.to_vec()))),
+        }
+        Ok(())
+    }
+
+    /// Check if the first field known to the schema, starting from the
+    /// current one, belongs to the `group`.
+    fn group_continues(&self, group: &Layout) -> bool {
+        self.fields[self.pos..]
+            .iter()
+            .find(|(tag, _)| self.schema.field_type(*tag).is_some())
+            .is_some_and(|(tag, _)| group.contains(*tag))
+    }
+
+    fn parse_group(
+        &mut self,
+        tag: TagNum,
+        value: &[u8],
+        group: &Layout,
+    ) -> Result<Vec<FieldMap>, DynamicError> {
+        let count = deserialize_value(value, |d| d.deserialize_num_in_group()).map_err(|_| {
+            DynamicError::reject(tag, ParseRejectReason::IncorrectDataFormatForValue)
+        })?;
+        let delimiter = group.delimiter();
+        let mut entries = Vec::with_capacity(usize::from(count));
+        while self.peek() == Some(delimiter) {
+            let mut entry = FieldMap::new();
+            self.parse_field(group, &mut entry)?;
+            // Entry ends with next delimiter, repeated tag or known tag
+            // not belonging to the group. Fields unknown to the schema are
+            // part of the entry only when the group continues after them,
+            // otherwise they belong to the enclosing map.
+            while let Some(tag) = self.peek() {
+                if tag == delimiter || entry.contains(tag) {
+                    break;
+                }
+                let in_group = if self.schema.field_type(tag).is_some() {
+                    group.contains(tag)
+                } else {
+                    self.group_continues(group)
+                };
+                if !in_group {
+                    break;
+                }
+                self.parse_field(group, &mut entry)?;
+            }
+            entries.push(entry);
+        }
+        if entries.len() != usize::from(count) {
+            return Err(DynamicError::reject(
+                tag,
+                ParseRejectReason::IncorrectNumingroupCountForRepeatingGroup,
+            ));
+        }
+        Ok(entries)
+    }
+}
+
+/// FIX message with fields layout known only at runtime.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DynamicMessage {
+    pub begin_string: FixString,
+    pub header: FieldMap,
+    pub body: FieldMap,
+    pub trailer: FieldMap,
+}
+
+impl DynamicMessage {
+    pub fn new(begin_string: FixString, msg_type: &FixStr) -> DynamicMessage {
+        let mut header = FieldMap::new();
+        header.set_str(MSG_TYPE_TAG, msg_type);
+        DynamicMessage {
+            begin_string,
+            header,
+            body: FieldMap::new(),
+            trailer: FieldMap::new(),
+        }
+    }
+
+    pub fn msg_type(&self) -> Result<&FixStr, DynamicError> {
+        self.header.get_str(MSG_TYPE_TAG)
+    }
+
+    /// Parse message, fields unknown to the schema are kept in the body
+    /// (or in the group entry, when more fields of the group follow them).
+    pub fn from_raw_message(
+        schema: &Schema,
+        raw_message: RawMessage,
+    ) -> Result<DynamicMessage, DynamicError> {
+        let mut parser = Parser {
+            schema,
+            fields: split_fields(schema, raw_message.body)?,
+            pos: 0,
+        };
+        if parser.peek() != Some(MSG_TYPE_TAG) {
+            return Err(DynamicError::GarbledMessage(
+                "MsgType<35> not third tag".into(),
+            ));
+        }
+
+        let mut header = FieldMap::new();
+        while parser.peek().is_some_and(|tag| schema.header.contains(tag)) {
+            parser.parse_field(&schema.header, &mut header)?;
+        }
+
+        let msg_type = header.get_str(MSG_TYPE_TAG)?;
+        let empty_layout = Layout::default();
+        let layout = schema.message(msg_type).unwrap_or(&empty_layout);
+        let mut body = FieldMap::new();
+        while parser
+            .peek()
+            .is_some_and(|tag| !schema.trailer.contains(tag))
+        {
+            parser.parse_field(layout, &mut body)?;
+        }
+
+        let mut trailer = FieldMap::new();
+        while parser.peek().is_some() {
+            parser.parse_field(&schema.trailer, &mut trailer)?;
+        }
+
+        Ok(DynamicMessage {
+            begin_string: raw_message.begin_string.to_owned(),
+            header,
+            body,
+            trailer,
+        })
+    }
+
+    pub fn from_bytes(schema: &Schema, input: &[u8]) -> Result<DynamicMessage, DynamicError> {
+        let (_, raw_msg) = raw_message(input).map_err(DeserializeError::from)?;
+        DynamicMessage::from_raw_message(schema, raw_msg)
+    }
+
+    pub fn from_fixt_message(
+        schema: &Schema,
+        msg: &FixtMessage,
+    ) -> Result<DynamicMessage, DynamicError> {
+        DynamicMessage::from_bytes(schema, &msg.serialize())
+    }
+
+    /// Convert to generated message, fails when message doesn't match
+    /// compile-time dictionary.
+    pub fn to_fixt_message(&self) -> Result<Box<FixtMessage>, DeserializeError> {
+        FixtMessage::from_bytes(&self.serialize())
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut serializer = Serializer::new();
+        serializer.output_mut().extend_from_slice(b"8=");
+        serializer.serialize_string(&self.begin_string);
+        serializer.output_mut().push(b'\x01');
+        serializer.serialize_body_len();
+        // MsgType<35> always goes first
+        if let Some(msg_type) = self.header.get_raw(MSG_TYPE_TAG) {
+            let output = serializer.output_mut();
+            output.extend_from_slice(b"35=");
+            output.extend_from_slice(msg_type);
+            output.push(b'\x01');
+        }
+        self.header
+            .serialize(serializer.output_mut(), &[8, 9, 10, MSG_TYPE_TAG]);
+        self.body.serialize(serializer.output_mut(), FRAMING_TAGS);
+        self.trailer
+            .serialize(serializer.output_mut(), FRAMING_TAGS);
+        serializer.serialize_checksum();
+        serializer.take()
+    }
+
+    /// Check message against schema: known message type and fields,
+    /// required fields presence, values format and enumerations.
+    pub fn validate(&self, schema: &Schema) -> Result<(), DynamicError> {
+        let msg_type = self
+            .msg_type()
+            .map_err(|_| DynamicError::reject(MSG_TYPE_TAG, ParseRejectReason::InvalidMsgtype))?;
+        let Some(layout) = schema.message(msg_type) else {
+            return Err(DynamicError::reject(
+                MSG_TYPE_TAG,
+                ParseRejectReason::InvalidMsgtype,
+            ));
+        };
+        schema.validate_map(&self.header, &schema.header)?;
+        schema.validate_map(&self.body, layout)?;
+        schema.validate_map(&self.trailer, &schema.trailer)
+    }
+
+    pub fn dbg_fix_str(&self) -> impl fmt::Display {
+        let mut output = self.serialize();
+        for byte in output.iter_mut() {
+            if *byte == b'\x01' {
+                *byte = b'|';
+            }
+        }
+        String::from_utf8_lossy(&output).into_owned()
+    }
+}
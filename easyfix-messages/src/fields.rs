@@ -1,4 +1,4 @@
-pub(crate) mod basic_types;
+pub mod basic_types;
 pub use basic_types::*;
 
 include!(concat!(env!("OUT_DIR"), "/generated_fields.rs"));
@@ -1,7 +1,9 @@
 pub mod country;
 pub mod currency;
 pub mod deserializer;
+pub mod dynamic;
 pub mod fields;
 pub mod groups;
 pub mod messages;
 pub mod serializer;
+pub mod session;
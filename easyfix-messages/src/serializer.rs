@@ -426,6 +426,16 @@ impl Serializer {
 
     // fn serialize_tenor(input: &[u8]) -> Result<Tenor, RejectReason>;
 
+    /// Serialize fields kept by deserializer in `UnknownFields::Keep` mode.
+    pub fn serialize_unknown_fields(&mut self, fields: &[(TagNum, FixString)]) {
+        for (tag, value) in fields {
+            self.serialize_tag_num(tag);
+            self.output.push(b'=');
+            self.serialize_string(value);
+            self.output.push(b'\x01');
+        }
+    }
+
     pub fn serialize_enum<T>(&mut self, value: &T)
     where
         T: Copy + Into<&'static [u8]>,
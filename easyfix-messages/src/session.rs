@@ -0,0 +1,262 @@
+//! Version independent view of messages, used by `easyfix-session` to run
+//! sessions on messages generated for any FIX/FIXT version.
+//!
+//! Modules generated from dictionaries defining all session level messages
+//! implement [`SessionMessage`] for their `FixtMessage` and [`SessionHeader`]
+//! for their `Header`.
+
+use std::{fmt::Debug, hash::Hash};
+
+use crate::{
+    deserializer::{raw_message, DeserializeError, Deserializer},
+    fields::basic_types::{Boolean, FixStr, FixString, Int, SeqNum, UtcTimestamp},
+    messages::MsgCat,
+    serializer::Serializer,
+};
+
+/// Session level message types, MsgType<35> values are the same
+/// in all FIX versions.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum AdminMsgType {
+    Heartbeat,
+    TestRequest,
+    ResendRequest,
+    Reject,
+    SequenceReset,
+    Logout,
+    Logon,
+}
+
+impl AdminMsgType {
+    pub const fn from_fix_str(msg_type: &FixStr) -> Option<AdminMsgType> {
+        match msg_type.as_bytes() {
+            b"0" => Some(AdminMsgType::Heartbeat),
+            b"1" => Some(AdminMsgType::TestRequest),
+            b"2" => Some(AdminMsgType::ResendRequest),
+            b"3" => Some(AdminMsgType::Reject),
+            b"4" => Some(AdminMsgType::SequenceReset),
+            b"5" => Some(AdminMsgType::Logout),
+            b"A" => Some(AdminMsgType::Logon),
+            _ => None,
+        }
+    }
+
+    pub const fn as_fix_str(&self) -> &'static FixStr {
+        let bytes: &'static [u8] = match self {
+            AdminMsgType::Heartbeat => b"0",
+            AdminMsgType::TestRequest => b"1",
+            AdminMsgType::ResendRequest => b"2",
+            AdminMsgType::Reject => b"3",
+            AdminMsgType::SequenceReset => b"4",
+            AdminMsgType::Logout => b"5",
+            AdminMsgType::Logon => b"A",
+        };
+        unsafe { FixStr::from_ascii_unchecked(bytes) }
+    }
+}
+
+/// Session level message body.
+///
+/// Enumerated values are kept as they are on the wire, fields not defined
+/// by given FIX version are ignored when message is built and `None`
+/// (or default) when message is read.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum AdminMessage {
+    Heartbeat {
+        test_req_id: Option<FixString>,
+    },
+    TestRequest {
+        test_req_id: FixString,
+    },
+    ResendRequest {
+        begin_seq_no: SeqNum,
+        end_seq_no: SeqNum,
+    },
+    Reject {
+        ref_seq_num: SeqNum,
+        ref_tag_id: Option<Int>,
+        ref_msg_type: Option<FixString>,
+        session_reject_reason: Option<FixString>,
+        text: Option<FixString>,
+    },
+    SequenceReset {
+        gap_fill_flag: Option<Boolean>,
+        new_seq_no: SeqNum,
+    },
+    Logout {
+        session_status: Option<FixString>,
+        text: Option<FixString>,
+    },
+    Logon {
+        encrypt_method: FixString,
+        heart_bt_int: Int,
+        reset_seq_num_flag: Option<Boolean>,
+        next_expected_msg_seq_num: Option<SeqNum>,
+        default_appl_ver_id: Option<FixString>,
+        username: Option<FixString>,
+        password: Option<FixString>,
+    },
+}
+
+impl AdminMessage {
+    pub const fn msg_type(&self) -> AdminMsgType {
+        match self {
+            AdminMessage::Heartbeat { .. } => AdminMsgType::Heartbeat,
+            AdminMessage::TestRequest { .. } => AdminMsgType::TestRequest,
+            AdminMessage::ResendRequest { .. } => AdminMsgType::ResendRequest,
+            AdminMessage::Reject { .. } => AdminMsgType::Reject,
+            AdminMessage::SequenceReset { .. } => AdminMsgType::SequenceReset,
+            AdminMessage::Logout { .. } => AdminMsgType::Logout,
+            AdminMessage::Logon { .. } => AdminMsgType::Logon,
+        }
+    }
+}
+
+/// Conversion between field value and its representation in [`AdminMessage`].
+pub trait AdminValue<T>: Sized {
+    fn to_admin_value(&self) -> T;
+
+    /// `None` if `value` is not valid for this field.
+    fn from_admin_value(value: T) -> Option<Self>;
+}
+
+impl AdminValue<FixString> for FixString {
+    fn to_admin_value(&self) -> FixString {
+        self.clone()
+    }
+
+    fn from_admin_value(value: FixString) -> Option<FixString> {
+        Some(value)
+    }
+}
+
+impl AdminValue<FixString> for Int {
+    fn to_admin_value(&self) -> FixString {
+        FixString::from_ascii_lossy(self.to_string().into_bytes())
+    }
+
+    fn from_admin_value(value: FixString) -> Option<Int> {
+        value.as_utf8().parse().ok()
+    }
+}
+
+impl AdminValue<Int> for Int {
+    fn to_admin_value(&self) -> Int {
+        *self
+    }
+
+    fn from_admin_value(value: Int) -> Option<Int> {
+        Some(value)
+    }
+}
+
+impl AdminValue<Int> for SeqNum {
+    fn to_admin_value(&self) -> Int {
+        Int::from(*self)
+    }
+
+    fn from_admin_value(value: Int) -> Option<SeqNum> {
+        SeqNum::try_from(value).ok()
+    }
+}
+
+impl AdminValue<SeqNum> for SeqNum {
+    fn to_admin_value(&self) -> SeqNum {
+        *self
+    }
+
+    fn from_admin_value(value: SeqNum) -> Option<SeqNum> {
+        Some(value)
+    }
+}
+
+impl AdminValue<SeqNum> for Int {
+    fn to_admin_value(&self) -> SeqNum {
+        SeqNum::try_from(*self).unwrap_or_default()
+    }
+
+    fn from_admin_value(value: SeqNum) -> Option<Int> {
+        Some(Int::from(value))
+    }
+}
+
+impl AdminValue<Boolean> for Boolean {
+    fn to_admin_value(&self) -> Boolean {
+        *self
+    }
+
+    fn from_admin_value(value: Boolean) -> Option<Boolean> {
+        Some(value)
+    }
+}
+
+/// Standard header fields used by session layer.
+pub trait SessionHeader {
+    fn begin_string(&self) -> &FixStr;
+    fn set_begin_string(&mut self, begin_string: FixString);
+    fn sender_comp_id(&self) -> &FixStr;
+    fn set_sender_comp_id(&mut self, sender_comp_id: FixString);
+    fn target_comp_id(&self) -> &FixStr;
+    fn set_target_comp_id(&mut self, target_comp_id: FixString);
+    fn msg_seq_num(&self) -> SeqNum;
+    fn set_msg_seq_num(&mut self, msg_seq_num: SeqNum);
+    fn poss_dup_flag(&self) -> Option<Boolean>;
+    fn set_poss_dup_flag(&mut self, poss_dup_flag: Option<Boolean>);
+    fn sending_time(&self) -> UtcTimestamp;
+    fn set_sending_time(&mut self, sending_time: UtcTimestamp);
+    fn orig_sending_time(&self) -> Option<UtcTimestamp>;
+    fn set_orig_sending_time(&mut self, orig_sending_time: Option<UtcTimestamp>);
+}
+
+/// Complete message (header, body and trailer) of one FIX version,
+/// the codec used by a session.
+pub trait SessionMessage: Clone + Debug + Send + 'static {
+    type Header: SessionHeader + Clone + Debug + Send;
+    type Body: Debug + Send + 'static;
+    type MsgType: Copy + Debug + Eq + Hash + Send + Sync + 'static;
+
+    /// Check if BeginString<8> is handled by this codec.
+    fn is_supported_begin_string(begin_string: &FixStr) -> bool;
+
+    fn deserialize(deserializer: Deserializer) -> Result<Box<Self>, DeserializeError>;
+
+    fn from_bytes(input: &[u8]) -> Result<Box<Self>, DeserializeError> {
+        let (_, raw_msg) = raw_message(input)?;
+        Self::deserialize(Deserializer::from_raw_message(raw_msg))
+    }
+
+    /// Serialize message with preconfigured `serializer`.
+    fn serialize_with(&self, serializer: Serializer) -> Vec<u8>;
+
+    /// Wrap `body` with default header (MsgType<35> set, SendingTime<52>
+    /// set to `UtcTimestamp::MIN_UTC`) and trailer.
+    fn from_body(body: Box<Self::Body>) -> Box<Self>;
+
+    fn into_body(self: Box<Self>) -> Box<Self::Body>;
+
+    fn header(&self) -> &Self::Header;
+
+    fn header_mut(&mut self) -> &mut Self::Header;
+
+    /// MsgType<35> of the body.
+    fn msg_type(&self) -> Self::MsgType;
+
+    fn msg_cat(&self) -> MsgCat;
+
+    /// Set MsgType<35> in header to match the body.
+    fn update_msg_type(&mut self);
+
+    fn msg_type_as_fix_str(msg_type: Self::MsgType) -> &'static FixStr;
+
+    fn msg_type_from_fix_str(msg_type: &FixStr) -> Option<Self::MsgType>;
+
+    fn admin_msg_type(&self) -> Option<AdminMsgType> {
+        AdminMsgType::from_fix_str(Self::msg_type_as_fix_str(self.msg_type()))
+    }
+
+    /// Session level view of the body, `None` for application messages.
+    fn to_admin(&self) -> Option<AdminMessage>;
+
+    /// Build session level message, see [`SessionMessage::from_body`].
+    fn from_admin(admin: AdminMessage) -> Box<Self>;
+}
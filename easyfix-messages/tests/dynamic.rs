@@ -0,0 +1,170 @@
+use std::fs;
+
+use assert_matches::assert_matches;
+use easyfix_messages::{
+    deserializer::ParseRejectReason,
+    dynamic::{Dictionary, DynamicError, DynamicMessage, FieldMap, Schema},
+    fields::{FixStr, FixString, Utc, UtcTimestamp},
+    messages::{FixtMessage, BEGIN_STRING},
+};
+
+fn schema() -> Schema {
+    let dir = env!("CARGO_MANIFEST_DIR");
+    let mut dictionary = Dictionary::new(None);
+    dictionary
+        .process_fixt_xml(&fs::read_to_string(format!("{dir}/xml/FIXT11.xml")).unwrap())
+        .unwrap();
+    dictionary
+        .process_fix_xml(&fs::read_to_string(format!("{dir}/xml/FIX50SP2.xml")).unwrap())
+        .unwrap();
+    Schema::new(&dictionary).expect("Schema creation failed")
+}
+
+fn fix_str(s: &str) -> &FixStr {
+    FixStr::from_ascii(s.as_bytes()).unwrap()
+}
+
+fn logon() -> DynamicMessage {
+    let mut msg = DynamicMessage::new(BEGIN_STRING.to_owned(), fix_str("A"));
+    msg.header.set_str(49, fix_str("test_sender"));
+    msg.header.set_str(56, fix_str("test_target"));
+    msg.header.set_seq_num(34, 1);
+    msg.header
+        .set_utc_timestamp(52, &UtcTimestamp::with_nanos(Utc::now()));
+    msg.body.set_int(98, 0);
+    msg.body.set_int(108, 30);
+    // RawData<96> with SOH inside
+    msg.body.set_int(95, 5);
+    msg.body.set_raw(96, b"ab\x01cd".to_vec());
+    msg.body.set_str(1137, fix_str("9"));
+    let mut msg_type = FieldMap::new();
+    msg_type.set_str(372, fix_str("D"));
+    msg_type.set_char(385, b'S');
+    msg.body.set_group(384, vec![msg_type.clone(), msg_type]);
+    msg
+}
+
+#[test]
+fn unknown_fields_preserved() {
+    let schema = schema();
+    let mut msg = logon();
+    // Unknown field following the group stays in the body
+    msg.body.set_str(5001, fix_str("venue specific"));
+    msg.body
+        .group_mut(384)
+        .unwrap()
+        .last_mut()
+        .unwrap()
+        .set_char(385, b'R');
+
+    let parsed = DynamicMessage::from_bytes(&schema, &msg.serialize()).expect("Parsing failed");
+    assert_eq!(parsed, msg);
+    assert_eq!(parsed.msg_type().unwrap(), "A");
+    assert_eq!(parsed.body.get_int(108).unwrap(), 30);
+    assert_eq!(parsed.body.get_raw(96).unwrap(), b"ab\x01cd");
+    assert_eq!(parsed.body.get_str(5001).unwrap(), "venue specific");
+    let msg_types = parsed.body.group(384).unwrap();
+    assert_eq!(msg_types.len(), 2);
+    assert_eq!(msg_types[1].get_char(385).unwrap(), b'R');
+    assert_matches!(
+        parsed.body.get_int(109),
+        Err(DynamicError::FieldNotFound(109))
+    );
+    assert_matches!(
+        parsed.validate(&schema),
+        Err(DynamicError::Reject {
+            tag: Some(5001),
+            reason: ParseRejectReason::UndefinedTag
+        })
+    );
+}
+
+#[test]
+fn unknown_fields_in_group_entries_preserved() {
+    let schema = schema();
+    let mut msg = logon();
+    // NoMsgTypes<384>=2, both entries 372=D|5002=x|385=S, the first one
+    // ends with unknown 5003=y, followed by the next entry
+    let mut msg_type = FieldMap::new();
+    msg_type.set_str(372, fix_str("D"));
+    msg_type.set_str(5002, fix_str("x"));
+    msg_type.set_char(385, b'S');
+    let mut first_msg_type = msg_type.clone();
+    first_msg_type.set_str(5003, fix_str("y"));
+    msg.body.set_group(384, vec![first_msg_type, msg_type]);
+
+    let serialized = msg.serialize();
+    let parsed = DynamicMessage::from_bytes(&schema, &serialized).expect("Parsing failed");
+    assert_eq!(parsed, msg);
+    assert_eq!(parsed.serialize(), serialized);
+    let msg_types = parsed.body.group(384).unwrap();
+    assert_eq!(msg_types.len(), 2);
+    for msg_type in msg_types {
+        assert_eq!(msg_type.get_str(5002).unwrap(), "x");
+        assert_eq!(msg_type.get_char(385).unwrap(), b'S');
+    }
+    assert_eq!(msg_types[0].get_str(5003).unwrap(), "y");
+}
+
+#[test]
+fn validation() {
+    let schema = schema();
+    let msg = logon();
+    msg.validate(&schema).expect("Validation failed");
+
+    let mut missing_field = msg.clone();
+    missing_field.body.remove(108);
+    assert_matches!(
+        missing_field.validate(&schema),
+        Err(DynamicError::Reject {
+            tag: Some(108),
+            reason: ParseRejectReason::RequiredTagMissing
+        })
+    );
+
+    let mut incorrect_value = msg.clone();
+    incorrect_value.body.set_int(98, 42);
+    assert_matches!(
+        incorrect_value.validate(&schema),
+        Err(DynamicError::Reject {
+            tag: Some(98),
+            reason: ParseRejectReason::ValueIsIncorrect
+        })
+    );
+
+    let mut incorrect_format = msg;
+    incorrect_format.body.set_raw(108, b"3O".to_vec());
+    assert_matches!(
+        incorrect_format.body.get_int(108),
+        Err(DynamicError::Reject {
+            tag: Some(108),
+            reason: ParseRejectReason::IncorrectDataFormatForValue
+        })
+    );
+    assert_matches!(
+        incorrect_format.validate(&schema),
+        Err(DynamicError::Reject {
+            tag: Some(108),
+            reason: ParseRejectReason::IncorrectDataFormatForValue
+        })
+    );
+}
+
+#[test]
+fn fixt_message_conversion() {
+    let schema = schema();
+    let msg = logon();
+    let fixt_msg = msg.to_fixt_message().expect("Conversion failed");
+    assert_eq!(
+        fixt_msg.header.sender_comp_id,
+        FixString::from_ascii_lossy(b"test_sender".to_vec())
+    );
+    let converted = DynamicMessage::from_fixt_message(&schema, &fixt_msg).unwrap();
+    assert_eq!(converted.serialize(), fixt_msg.serialize());
+    assert_eq!(
+        FixtMessage::from_bytes(&converted.serialize())
+            .unwrap()
+            .serialize(),
+        msg.serialize()
+    );
+}
@@ -1,6 +1,6 @@
 use assert_matches::assert_matches;
 use easyfix_messages::{
-    deserializer::{DeserializeError, ParseRejectReason},
+    deserializer::{raw_message, DeserializeError, Deserializer, ParseRejectReason},
     fields::{
         DefaultApplVerId, EncryptMethod, FixString, MsgDirection, MsgType, ToFixString, Utc,
         UtcTimestamp,
@@ -42,6 +42,7 @@ fn heartbeat_ok() {
     // Simple test with simple message.
     let msg = fixt_message(Box::new(Message::Heartbeat(Heartbeat {
         test_req_id: None,
+        unknown_fields: Vec::new(),
     })));
     let serialized = msg.serialize();
     FixtMessage::from_bytes(&serialized).expect("Deserialization failed");
@@ -61,6 +62,7 @@ fn logon_msg_type_grp_no_present() {
         password: None,
         default_appl_ver_id: DefaultApplVerId::Fix50Sp2,
         msg_type_grp: None,
+        unknown_fields: Vec::new(),
     })));
     let serialized = msg.serialize();
     FixtMessage::from_bytes(&serialized).expect("Deserialization failed");
@@ -87,6 +89,7 @@ fn logon_msg_type_grp_present_with_two_entries_1() {
                 ref_appl_ext_id: None,
                 ref_cstm_appl_ver_id: None,
                 default_ver_indicator: None,
+                        unknown_fields: Vec::new(),
             },
             MsgTypeGrp {
                 ref_msg_type: Some(MsgType::NewOrderSingle.to_fix_string()),
@@ -95,8 +98,10 @@ fn logon_msg_type_grp_present_with_two_entries_1() {
                 ref_appl_ext_id: None,
                 ref_cstm_appl_ver_id: None,
                 default_ver_indicator: None,
+                        unknown_fields: Vec::new(),
             },
         ]),
+        unknown_fields: Vec::new(),
     })));
     let serialized = msg.serialize();
     FixtMessage::from_bytes(&serialized).expect("Deserialization failed");
@@ -123,6 +128,7 @@ fn logon_msg_type_grp_present_with_two_entries_2() {
                 ref_appl_ext_id: None,
                 ref_cstm_appl_ver_id: None,
                 default_ver_indicator: Some(true),
+                        unknown_fields: Vec::new(),
             },
             MsgTypeGrp {
                 ref_msg_type: Some(MsgType::NewOrderSingle.to_fix_string()),
@@ -131,8 +137,10 @@ fn logon_msg_type_grp_present_with_two_entries_2() {
                 ref_appl_ext_id: None,
                 ref_cstm_appl_ver_id: None,
                 default_ver_indicator: Some(false),
+                        unknown_fields: Vec::new(),
             },
         ]),
+        unknown_fields: Vec::new(),
     })));
     let serialized = msg.serialize();
     FixtMessage::from_bytes(&serialized).expect("Deserialization failed");
@@ -159,3 +167,116 @@ fn known_msg_type() {
     let msg = FixtMessage::from_bytes(msg_str.replace("|", "\x01").as_bytes()).unwrap();
     assert_eq!(msg.header.msg_type, MsgType::Heartbeat);
 }
+
+#[test]
+fn begin_string_mismatch() {
+    // FIX.4.x messages are handled by codecs generated from FIX.4.x
+    // dictionaries, not by FIXT one
+    let mut msg = fixt_message(Box::new(Message::Logon(Logon {
+        encrypt_method: EncryptMethod::NoneOther,
+        heart_bt_int: 30,
+        ..Default::default()
+    })));
+    msg.header.begin_string = FixString::from_ascii_lossy(b"FIX.4.4".to_vec());
+    assert_matches!(
+        FixtMessage::from_bytes(&msg.serialize()),
+        Err(DeserializeError::GarbledMessage(_))
+    );
+}
+
+// Fields kept in message headers, bodies and group entries
+mod unknown_fields {
+    use easyfix_messages::deserializer::UnknownFields;
+
+    use super::*;
+
+    #[test]
+    fn unknown_fields() {
+        let msg = fixt_message(Box::new(Message::Logon(Logon {
+            encrypt_method: EncryptMethod::NoneOther,
+            heart_bt_int: 30,
+            msg_type_grp: Some(vec![MsgTypeGrp {
+                ref_msg_type: Some(MsgType::NewOrderSingle.to_fix_string()),
+                msg_direction: Some(MsgDirection::Send),
+                unknown_fields: vec![(5002, FixString::from_ascii_lossy(b"grp".to_vec()))],
+                ..Default::default()
+            }]),
+            unknown_fields: vec![(5001, FixString::from_ascii_lossy(b"msg".to_vec()))],
+            ..Default::default()
+        })));
+        let serialized = msg.serialize();
+
+        let deserialize = |unknown_fields| {
+            let (_, raw_msg) = raw_message(&serialized).unwrap();
+            let mut deserializer = Deserializer::from_raw_message(raw_msg);
+            deserializer.set_unknown_fields(unknown_fields);
+            FixtMessage::deserialize(deserializer)
+        };
+
+        assert_matches!(
+            deserialize(UnknownFields::Reject),
+            Err(DeserializeError::Reject {
+                tag: Some(5002),
+                reason: ParseRejectReason::UndefinedTag,
+                ..
+            })
+        );
+
+        let kept = deserialize(UnknownFields::Keep).expect("Deserialization failed");
+        assert_eq!(kept.serialize(), serialized);
+
+        let ignored = deserialize(UnknownFields::Ignore).expect("Deserialization failed");
+        let Message::Logon(logon) = *ignored.body else {
+            panic!("Logon expected");
+        };
+        assert!(logon.unknown_fields.is_empty());
+        let msg_type_grp = logon.msg_type_grp.expect("MsgTypeGrp missing");
+        assert_eq!(msg_type_grp.len(), 1);
+        assert!(msg_type_grp[0].unknown_fields.is_empty());
+        assert_eq!(msg_type_grp[0].msg_direction, Some(MsgDirection::Send));
+    }
+
+    #[test]
+    fn unknown_header_fields() {
+        let mut msg = fixt_message(Box::new(Message::Heartbeat(Heartbeat::default())));
+        msg.header.unknown_fields = vec![(5000, FixString::from_ascii_lossy(b"hdr".to_vec()))];
+        let serialized = msg.serialize();
+
+        let (_, raw_msg) = raw_message(&serialized).unwrap();
+        let mut deserializer = Deserializer::from_raw_message(raw_msg);
+        deserializer.set_unknown_fields(UnknownFields::Keep);
+        let kept = FixtMessage::deserialize(deserializer).expect("Deserialization failed");
+        assert_eq!(kept.header.unknown_fields, msg.header.unknown_fields);
+        let Message::Heartbeat(heartbeat) = &*kept.body else {
+            panic!("Heartbeat expected");
+        };
+        assert!(heartbeat.unknown_fields.is_empty());
+        assert_eq!(kept.serialize(), serialized);
+    }
+
+    #[test]
+    fn known_field_not_defined_for_message_type() {
+        // Price<44> is defined in dictionary, but not for Logon<A>
+        let msg = fixt_message(Box::new(Message::Logon(Logon {
+            encrypt_method: EncryptMethod::NoneOther,
+            heart_bt_int: 30,
+            unknown_fields: vec![(44, FixString::from_ascii_lossy(b"1".to_vec()))],
+            ..Default::default()
+        })));
+        let serialized = msg.serialize();
+
+        for unknown_fields in [UnknownFields::Keep, UnknownFields::Ignore] {
+            let (_, raw_msg) = raw_message(&serialized).unwrap();
+            let mut deserializer = Deserializer::from_raw_message(raw_msg);
+            deserializer.set_unknown_fields(unknown_fields);
+            assert_matches!(
+                FixtMessage::deserialize(deserializer),
+                Err(DeserializeError::Reject {
+                    tag: Some(44),
+                    reason: ParseRejectReason::TagNotDefinedForThisMessageType,
+                    ..
+                })
+            );
+        }
+    }
+}
@@ -0,0 +1,38 @@
+use std::{fs, path::PathBuf, process::Command};
+
+/// Build script layers overlays from FIX_OVERLAY_XML on top of FIX XML.
+#[test]
+fn overlay_reaches_generated_code() {
+    let manifest_dir = env!("CARGO_MANIFEST_DIR");
+    // Separate target directory, so regular build is not affected
+    let target_dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("overlay");
+    let output = Command::new(env!("CARGO"))
+        .args(["check", "--quiet", "--manifest-path"])
+        .arg(format!("{manifest_dir}/Cargo.toml"))
+        .arg("--target-dir")
+        .arg(&target_dir)
+        .env_remove("FIXT_XML")
+        .env_remove("FIX_XML")
+        .env(
+            "FIX_OVERLAY_XML",
+            format!("{manifest_dir}/tests/xml/overlay.xml"),
+        )
+        .output()
+        .expect("failed to run cargo");
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let out_dir = fs::read_dir(target_dir.join("debug/build"))
+        .unwrap()
+        .map(|entry| entry.unwrap().path().join("out"))
+        .find(|out_dir| out_dir.join("generated_messages.rs").exists())
+        .expect("generated messages not found");
+    let messages = fs::read_to_string(out_dir.join("generated_messages.rs")).unwrap();
+    assert!(messages.contains("VenueSession = 5001u16"));
+    assert!(messages.contains("VenueStatusText = 5002u16"));
+    assert!(messages.contains("pub venue_session: Option<FixString>"));
+    assert!(messages.contains("pub struct VenueStatus {"));
+}
@@ -3,6 +3,7 @@ use std::{collections::HashMap, net::SocketAddr, time::Duration};
 use chrono::NaiveTime;
 use easyfix_macros::fix_str;
 use easyfix_messages::{
+    deserializer::UnknownFields,
     fields::{FixStr, FixString},
     messages::Header,
 };
@@ -15,13 +16,15 @@ use easyfix_session::{
 };
 use tokio::{runtime::Builder, task::LocalSet};
 use tokio_stream::StreamExt;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
 async fn acceptor() {
     let settings = Settings {
         sender_comp_id: "n8_fix_test_server".try_into().unwrap(), //: "easyfix-acceptor".try_into().unwrap(),
         sender_sub_id: None,
         heartbeat_interval: Duration::from_secs(10),
+        min_heartbeat_interval: None,
+        max_heartbeat_interval: None,
         auto_disconnect_after_no_logon_received: Duration::from_secs(3),
         auto_disconnect_after_no_heartbeat: 3,
     };
@@ -45,8 +48,11 @@ async fn acceptor() {
                 session_id,
                 // session_time: UtcTimeOnly::from_hms(8, 0, 0)..=UtcTimeOnly::from_hms(16, 0, 0),
                 //logon_time: UtcTimeOnly::from_hms(7, 30, 0)..=UtcTimeOnly::from_hms(16, 30, 0),
-                session_time: NaiveTime::from_hms_opt(0, 0, 0).unwrap()
-                    ..=NaiveTime::from_hms_opt(23, 59, 59).unwrap(),
+                session_time: Some(
+                    (NaiveTime::from_hms_opt(0, 0, 0).unwrap()
+                        ..=NaiveTime::from_hms_opt(23, 59, 59).unwrap())
+                        .into(),
+                ),
                 logon_time: NaiveTime::from_hms_opt(0, 0, 0).unwrap()
                     ..=NaiveTime::from_hms_opt(23, 59, 59).unwrap(),
 
@@ -61,10 +67,15 @@ async fn acceptor() {
                 sender_default_appl_ver_id: fix_str!("9").to_owned(),
                 target_default_appl_ver_id: fix_str!("9").to_owned(),
                 persist: false,
+                persist_inbound: false,
                 refresh_on_logon: false,
                 enable_next_expected_msg_seq_num: true,
 
                 verify_logout: true,
+                tls_client_identity: None,
+                outbound_queue_limit: Some(1024),
+                throttle: None,
+                unknown_fields: UnknownFields::Reject,
             },
         );
     };
@@ -81,6 +92,7 @@ async fn acceptor() {
     acceptor.start(connection);
     while let Some(mut entry) = acceptor.next().await {
         match entry.as_event() {
+            FixEvent::Connecting(..) | FixEvent::ConnectionFailed(..) => {}
             FixEvent::Created(session_id) => info!("Session created: {}", session_id),
             FixEvent::Logon(session_id, sender) => {
                 info!("Logon: {session_id}");
@@ -92,7 +104,7 @@ async fn acceptor() {
             }
             FixEvent::AppMsgIn(mut msg, _responder) => {
                 info!("App input msg: {:?}", msg.msg_type());
-                let session_id = SessionId::from_input_msg(&msg);
+                let session_id = SessionId::from_input_msg(&*msg);
                 reverse_route(&mut msg.header);
                 let _ = senders.get(&session_id).unwrap().send_raw(msg);
             }
@@ -102,6 +114,22 @@ async fn acceptor() {
                 _responder.do_not_send();
             }
             FixEvent::AdmMsgOut(msg) => info!("Adm output msg: {:?}", msg.msg_type()),
+            FixEvent::AppMsgResend(session_id, msg, _responder) => info!(
+                "{session_id}: {:?}/{} resend",
+                msg.msg_type(),
+                msg.header.msg_seq_num
+            ),
+            FixEvent::AppMsgGapFilled(session_id, msg) => info!(
+                "{session_id}: {:?}/{} changed to gap fill",
+                msg.msg_type(),
+                msg.header.msg_seq_num
+            ),
+            FixEvent::MsgThrottled(session_id, msg_type, delay) => {
+                info!("{session_id}: {msg_type:?} throttled for {delay:?}")
+            }
+            FixEvent::MsgThrottleRejected(session_id, msg) => {
+                warn!("{session_id}: {:?} rejected by throttle", msg.msg_type())
+            }
             FixEvent::DeserializeError(session_id, error) => {
                 error!("{session_id}: {error}");
             }
@@ -1,35 +1,63 @@
 use std::{
     cell::RefCell,
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     future::Future,
     io,
     net::SocketAddr,
     pin::Pin,
     rc::Rc,
     task::{Context, Poll},
+    time::Duration,
 };
 
-use easyfix_messages::fields::{FixString, SeqNum, SessionStatus};
-use futures::{self, Stream};
+use easyfix_messages::{
+    fields::{FixString, SeqNum, SessionStatus},
+    messages::FixtMessage,
+    session::SessionMessage,
+};
+use futures::{
+    self,
+    future::{self, LocalBoxFuture},
+    FutureExt, Stream,
+};
 use pin_project::pin_project;
 use tokio::{
     io::{AsyncRead, AsyncWrite},
     net::TcpListener,
     task::JoinHandle,
 };
+use tokio_util::{sync::CancellationToken, task::TaskTracker};
 use tracing::{error, info, info_span, instrument, warn, Instrument};
 
 use crate::{
     application::{events_channel, AsEvent, Emitter, EventStream},
-    io::acceptor_connection,
+    io::{acceptor_connection, time::timeout},
     messages_storage::MessagesStorage,
     session::Session,
     session_id::SessionId,
     session_state::State as SessionState,
     settings::SessionSettings,
-    DisconnectReason, Settings,
+    DisconnectReason, Sender, Settings,
 };
 
+/// Connected peer details.
+#[derive(Clone, Debug)]
+pub struct PeerInfo {
+    pub addr: SocketAddr,
+    /// Identity (certificate Common Name) of TLS client, available only
+    /// when client certificate was verified.
+    pub tls_identity: Option<String>,
+}
+
+impl From<SocketAddr> for PeerInfo {
+    fn from(addr: SocketAddr) -> PeerInfo {
+        PeerInfo {
+            addr,
+            tls_identity: None,
+        }
+    }
+}
+
 #[allow(async_fn_in_trait)]
 pub trait Connection {
     async fn accept(
@@ -38,7 +66,7 @@ pub trait Connection {
         (
             impl AsyncRead + Unpin + 'static,
             impl AsyncWrite + Unpin + 'static,
-            SocketAddr,
+            PeerInfo,
         ),
         io::Error,
     >;
@@ -54,6 +82,11 @@ impl TcpConnection {
         let listener = TcpListener::bind(&socket_addr).await?;
         Ok(TcpConnection { listener })
     }
+
+    /// Address the listener is bound to, e.g. to learn port assigned by OS.
+    pub fn local_addr(&self) -> Result<SocketAddr, io::Error> {
+        self.listener.local_addr()
+    }
 }
 
 impl Connection for TcpConnection {
@@ -63,34 +96,77 @@ impl Connection for TcpConnection {
         (
             impl AsyncRead + Unpin + 'static,
             impl AsyncWrite + Unpin + 'static,
-            SocketAddr,
+            PeerInfo,
         ),
         io::Error,
     > {
         let (tcp_stream, peer_addr) = self.listener.accept().await?;
         tcp_stream.set_nodelay(true)?;
         let (reader, writer) = tcp_stream.into_split();
-        Ok((reader, writer, peer_addr))
+        Ok((reader, writer, peer_addr.into()))
     }
 }
 
-type SessionMapInternal<S> = HashMap<SessionId, (SessionSettings, Rc<RefCell<SessionState<S>>>)>;
+type SessionEntry<S, M> = (SessionSettings, Rc<RefCell<SessionState<S, M>>>);
+
+type SessionMapInternal<S, M> = HashMap<SessionId, SessionEntry<S, M>>;
+
+/// Builds settings for session which is not registered, `None` rejects
+/// the session.
+pub type DynamicSessionBuilder = Box<dyn Fn(&SessionId) -> Option<SessionSettings>>;
+
+/// Logon<A> details passed to [Authenticator].
+#[derive(Clone, Debug)]
+pub struct LogonRequest {
+    pub session_id: SessionId,
+    /// Username<553>
+    pub username: Option<FixString>,
+    /// Password<554>
+    pub password: Option<FixString>,
+    pub peer_info: PeerInfo,
+}
+
+/// Reason of Logon<A> rejection, sent to counterparty in Logout<5>.
+#[derive(Clone, Debug, Default)]
+pub struct LogonRejection {
+    /// SessionStatus<1409>, i.e. `SessionStatus::InvalidUsernameOrPassword`
+    pub session_status: Option<SessionStatus>,
+    /// Text<58>
+    pub text: Option<FixString>,
+}
+
+/// Verifies Logon<A> before it's processed by the session.
+pub type Authenticator =
+    Box<dyn Fn(LogonRequest) -> LocalBoxFuture<'static, Result<(), LogonRejection>>>;
 
-pub struct SessionsMap<S> {
-    map: SessionMapInternal<S>,
+pub struct SessionsMap<S, M: SessionMessage = FixtMessage> {
+    map: SessionMapInternal<S, M>,
     message_storage_builder: Box<dyn Fn(&SessionId) -> S>,
+    dynamic_session_builder: Option<DynamicSessionBuilder>,
+    // Sessions registered by `dynamic_session_builder`
+    dynamic_sessions: HashSet<SessionId>,
+    unregister_dynamic_sessions: bool,
+    authenticator: Option<Authenticator>,
 }
 
-impl<S: MessagesStorage> SessionsMap<S> {
-    fn new(message_storage_builder: Box<dyn Fn(&SessionId) -> S>) -> SessionsMap<S> {
+impl<S: MessagesStorage, M: SessionMessage> SessionsMap<S, M> {
+    fn new(message_storage_builder: Box<dyn Fn(&SessionId) -> S>) -> SessionsMap<S, M> {
         SessionsMap {
             map: HashMap::new(),
             message_storage_builder,
+            dynamic_session_builder: None,
+            dynamic_sessions: HashSet::new(),
+            unregister_dynamic_sessions: false,
+            authenticator: None,
         }
     }
 
     #[rustfmt::skip]
     pub fn register_session(&mut self, session_id: SessionId, session_settings: SessionSettings) {
+        if !M::is_supported_begin_string(session_id.begin_string()) {
+            warn!("session {session_id}: BeginString<8> not supported by acceptor codec");
+        }
+        self.dynamic_sessions.remove(&session_id);
         self.map.insert(
             session_id.clone(),
             (
@@ -102,69 +178,202 @@ impl<S: MessagesStorage> SessionsMap<S> {
         );
     }
 
-    pub(crate) fn get_session(
-        &self,
+    /// Remove session, returns its settings if it was registered.
+    pub fn unregister_session(&mut self, session_id: &SessionId) -> Option<SessionSettings> {
+        self.dynamic_sessions.remove(session_id);
+        self.map
+            .remove(session_id)
+            .map(|(session_settings, _)| session_settings)
+    }
+
+    /// Replace settings of registered session, session state (sequence
+    /// numbers, stored messages) is preserved. Returns previous settings,
+    /// `None` (and nothing is changed) if session is not registered.
+    pub fn update_session(
+        &mut self,
         session_id: &SessionId,
-    ) -> Option<(SessionSettings, Rc<RefCell<SessionState<S>>>)> {
+        session_settings: SessionSettings,
+    ) -> Option<SessionSettings> {
+        self.map
+            .get_mut(session_id)
+            .map(|(settings, _)| std::mem::replace(settings, session_settings))
+    }
+
+    pub fn session_ids(&self) -> impl Iterator<Item = &SessionId> {
+        self.map.keys()
+    }
+
+    fn flush(&self) -> impl Future<Output = ()> {
+        future::join_all(
+            self.map
+                .values()
+                .map(|(_, state)| state.borrow_mut().flush())
+                .collect::<Vec<_>>(),
+        )
+        .map(|_| ())
+    }
+
+    /// Accept sessions which are not registered when `builder` returns
+    /// settings for them. Such session is registered on first logon,
+    /// with messages storage created by storage builder, and by default
+    /// remains registered after disconnection.
+    ///
+    /// `SessionSettings::session_id` returned by `builder` is replaced
+    /// with id of the session being registered.
+    pub fn set_dynamic_session_builder(&mut self, builder: DynamicSessionBuilder) {
+        self.dynamic_session_builder = Some(builder);
+    }
+
+    /// Unregister sessions created by dynamic session builder when they
+    /// disconnect or their Logon<A> is rejected, so they don't accumulate.
+    /// Session state is then built again, with new messages storage,
+    /// on next logon.
+    pub fn set_unregister_dynamic_sessions(&mut self, unregister: bool) {
+        self.unregister_dynamic_sessions = unregister;
+    }
+
+    /// Verify every Logon<A> received by acceptor with `authenticator`.
+    ///
+    /// When Logon<A> is rejected, Logout<5> with given SessionStatus<1409>
+    /// and Text<58> is sent and connection is closed.
+    pub fn set_authenticator(&mut self, authenticator: Authenticator) {
+        self.authenticator = Some(authenticator);
+    }
+
+    /// Start Logon<A> verification, `None` when there is no authenticator.
+    pub(crate) fn authenticate(
+        &self,
+        logon_request: LogonRequest,
+    ) -> Option<LocalBoxFuture<'static, Result<(), LogonRejection>>> {
+        self.authenticator
+            .as_ref()
+            .map(|authenticator| authenticator(logon_request))
+    }
+
+    pub(crate) fn get_session(&self, session_id: &SessionId) -> Option<SessionEntry<S, M>> {
         self.map.get(session_id).cloned()
     }
+
+    /// Get registered session or, if dynamic sessions are enabled,
+    /// try to register new one.
+    pub(crate) fn get_or_create_session(
+        &mut self,
+        session_id: &SessionId,
+    ) -> Option<SessionEntry<S, M>> {
+        if !self.map.contains_key(session_id) {
+            let mut session_settings = (self.dynamic_session_builder.as_ref()?)(session_id)?;
+            if session_settings.session_id != *session_id {
+                warn!(
+                    "dynamic session builder returned settings of {}, using {session_id}",
+                    session_settings.session_id
+                );
+                session_settings.session_id = session_id.clone();
+            }
+            info!("registering dynamic session {session_id}");
+            self.register_session(session_id.clone(), session_settings);
+            self.dynamic_sessions.insert(session_id.clone());
+        }
+        self.get_session(session_id)
+    }
+
+    /// Called when connection bound to the session ends, unregisters
+    /// dynamic session if it's enabled and session is not connected.
+    pub(crate) fn release_session(&mut self, session_id: &SessionId) {
+        if !self.unregister_dynamic_sessions || !self.dynamic_sessions.contains(session_id) {
+            return;
+        }
+        let disconnected = self
+            .map
+            .get(session_id)
+            .is_none_or(|(_, state)| state.borrow().disconnected());
+        if disconnected {
+            info!("unregistering dynamic session {session_id}");
+            self.unregister_session(session_id);
+        }
+    }
+}
+
+/// Registered session details, see [Acceptor::sessions].
+#[derive(Clone, Debug)]
+pub struct SessionInfo {
+    pub session_id: SessionId,
+    pub session_settings: SessionSettings,
+    pub connected: bool,
+    pub logged_on: bool,
+    pub next_sender_msg_seq_num: SeqNum,
+    pub next_target_msg_seq_num: SeqNum,
 }
 
-pub struct SessionTask<S> {
+pub struct SessionTask<S, M: SessionMessage = FixtMessage> {
     settings: Settings,
-    sessions: Rc<RefCell<SessionsMap<S>>>,
-    active_sessions: Rc<RefCell<ActiveSessionsMap<S>>>,
-    emitter: Emitter,
+    sessions: Rc<RefCell<SessionsMap<S, M>>>,
+    active_sessions: Rc<RefCell<ActiveSessionsMap<S, M>>>,
+    emitter: Emitter<M>,
+    // Connections in progress, awaited on shutdown
+    tracker: TaskTracker,
 }
 
-impl<S> Clone for SessionTask<S> {
+impl<S, M: SessionMessage> Clone for SessionTask<S, M> {
     fn clone(&self) -> Self {
         Self {
             settings: self.settings.clone(),
             sessions: self.sessions.clone(),
             active_sessions: self.active_sessions.clone(),
             emitter: self.emitter.clone(),
+            tracker: self.tracker.clone(),
         }
     }
 }
 
-impl<S: MessagesStorage + 'static> SessionTask<S> {
+impl<S: MessagesStorage + 'static, M: SessionMessage> SessionTask<S, M> {
     fn new(
         settings: Settings,
-        sessions: Rc<RefCell<SessionsMap<S>>>,
-        active_sessions: Rc<RefCell<ActiveSessionsMap<S>>>,
-        emitter: Emitter,
-    ) -> SessionTask<S> {
+        sessions: Rc<RefCell<SessionsMap<S, M>>>,
+        active_sessions: Rc<RefCell<ActiveSessionsMap<S, M>>>,
+        emitter: Emitter<M>,
+    ) -> SessionTask<S, M> {
         SessionTask {
             settings,
             sessions,
             active_sessions,
             emitter,
+            tracker: TaskTracker::new(),
         }
     }
 
     pub async fn run(
         self,
-        peer_addr: SocketAddr,
+        peer_info: impl Into<PeerInfo>,
         reader: impl AsyncRead + Unpin + 'static,
         writer: impl AsyncWrite + Unpin + 'static,
     ) {
-        let span = info_span!("connection", %peer_addr);
+        let peer_info = peer_info.into();
+        let span = info_span!("connection", peer_addr = %peer_info.addr);
+
+        // Tracker is closed by `Acceptor::shutdown`
+        if self.tracker.is_closed() {
+            span.in_scope(|| {
+                warn!("Acceptor is shut down, connection refused");
+            });
+            return;
+        }
 
         span.in_scope(|| {
             info!("New connection");
         });
 
-        acceptor_connection(
-            reader,
-            writer,
-            self.settings,
-            self.sessions,
-            self.active_sessions,
-            self.emitter,
-        )
-        .instrument(span.clone())
-        .await;
+        self.tracker
+            .track_future(acceptor_connection(
+                reader,
+                writer,
+                peer_info,
+                self.settings,
+                self.sessions,
+                self.active_sessions,
+                self.emitter,
+            ))
+            .instrument(span.clone())
+            .await;
 
         span.in_scope(|| {
             info!("Connection closed");
@@ -172,15 +381,16 @@ impl<S: MessagesStorage + 'static> SessionTask<S> {
     }
 }
 
-pub(crate) type ActiveSessionsMap<S> = HashMap<SessionId, Rc<Session<S>>>;
+pub(crate) type ActiveSessionsMap<S, M = FixtMessage> = HashMap<SessionId, Rc<Session<S, M>>>;
 
 #[pin_project]
-pub struct Acceptor<S> {
-    sessions: Rc<RefCell<SessionsMap<S>>>,
-    active_sessions: Rc<RefCell<ActiveSessionsMap<S>>>,
-    session_task: SessionTask<S>,
+pub struct Acceptor<S, M: SessionMessage = FixtMessage> {
+    sessions: Rc<RefCell<SessionsMap<S, M>>>,
+    active_sessions: Rc<RefCell<ActiveSessionsMap<S, M>>>,
+    session_task: SessionTask<S, M>,
+    shutdown_token: CancellationToken,
     #[pin]
-    event_stream: EventStream,
+    event_stream: EventStream<M>,
 }
 
 impl<S: MessagesStorage + 'static> Acceptor<S> {
@@ -188,7 +398,32 @@ impl<S: MessagesStorage + 'static> Acceptor<S> {
         settings: Settings,
         message_storage_builder: Box<dyn Fn(&SessionId) -> S>,
     ) -> Acceptor<S> {
+        Acceptor::with_codec(settings, message_storage_builder)
+    }
+}
+
+impl<S: MessagesStorage + 'static, M: SessionMessage> Acceptor<S, M> {
+    /// Create acceptor running its sessions on codec `M`, i.e.
+    /// `Acceptor::<_, fix42::messages::FixtMessage>::with_codec(..)`
+    /// for sessions with BeginString<8> `FIX.4.2`.
+    ///
+    /// Sessions of different FIX versions are run by separate acceptors.
+    pub fn with_codec(
+        settings: Settings,
+        message_storage_builder: Box<dyn Fn(&SessionId) -> S>,
+    ) -> Acceptor<S, M> {
         let (emitter, event_stream) = events_channel();
+        Acceptor::with_events_channel(settings, message_storage_builder, emitter, event_stream)
+    }
+
+    /// Create acceptor emitting events to given channel, which may be
+    /// shared with other acceptors.
+    pub(crate) fn with_events_channel(
+        settings: Settings,
+        message_storage_builder: Box<dyn Fn(&SessionId) -> S>,
+        emitter: Emitter<M>,
+        event_stream: EventStream<M>,
+    ) -> Acceptor<S, M> {
         let sessions = Rc::new(RefCell::new(SessionsMap::new(message_storage_builder)));
         let active_sessions = Rc::new(RefCell::new(HashMap::new()));
         let session_task_builder =
@@ -198,6 +433,7 @@ impl<S: MessagesStorage + 'static> Acceptor<S> {
             sessions,
             active_sessions,
             session_task: session_task_builder,
+            shutdown_token: CancellationToken::new(),
             event_stream,
         }
     }
@@ -208,12 +444,200 @@ impl<S: MessagesStorage + 'static> Acceptor<S> {
             .register_session(session_id, session_settings);
     }
 
-    pub fn sessions_map(&self) -> Rc<RefCell<SessionsMap<S>>> {
+    /// Accept sessions which are not registered up front,
+    /// see [SessionsMap::set_dynamic_session_builder].
+    ///
+    /// Builder may i.e. accept every TargetCompID<56> with given prefix:
+    /// `Box::new(move |session_id| session_id.target_comp_id().as_bytes()
+    /// .starts_with(b"CLIENT_").then(|| template.clone()))`.
+    pub fn set_dynamic_session_builder(&mut self, builder: DynamicSessionBuilder) {
+        self.sessions
+            .borrow_mut()
+            .set_dynamic_session_builder(builder);
+    }
+
+    /// Unregister dynamic sessions on disconnection,
+    /// see [SessionsMap::set_unregister_dynamic_sessions].
+    pub fn set_unregister_dynamic_sessions(&mut self, unregister: bool) {
+        self.sessions
+            .borrow_mut()
+            .set_unregister_dynamic_sessions(unregister);
+    }
+
+    /// Verify every Logon<A> with `authenticator`,
+    /// see [SessionsMap::set_authenticator].
+    pub fn set_authenticator(&mut self, authenticator: Authenticator) {
+        self.sessions.borrow_mut().set_authenticator(authenticator);
+    }
+
+    /// Remove session at runtime.
+    ///
+    /// When the session is connected, Logout<5> is sent and connection
+    /// is closed. Returns session settings if it was registered.
+    pub fn unregister_session(&mut self, session_id: &SessionId) -> Option<SessionSettings> {
+        let session_settings = self.sessions.borrow_mut().unregister_session(session_id);
+        if session_settings.is_some() {
+            self.logout_and_disconnect(session_id, b"Session removed");
+        }
+        session_settings
+    }
+
+    /// Replace settings of registered session at runtime.
+    ///
+    /// Session state (sequence numbers, stored messages) is preserved.
+    /// Connected session keeps settings from the moment it logged on,
+    /// new settings apply on next logon. With `disconnect` set, Logout<5>
+    /// is sent to connected session and connection is closed, so new
+    /// settings apply when counterparty reconnects. Returns previous
+    /// settings, `None` (and nothing is changed) if session is not
+    /// registered.
+    pub fn update_session(
+        &mut self,
+        session_id: &SessionId,
+        session_settings: SessionSettings,
+        disconnect: bool,
+    ) -> Option<SessionSettings> {
+        let prev_settings = self
+            .sessions
+            .borrow_mut()
+            .update_session(session_id, session_settings);
+        if prev_settings.is_some() && disconnect {
+            self.logout_and_disconnect(session_id, b"Session settings changed");
+        }
+        prev_settings
+    }
+
+    /// List registered sessions with their current state.
+    pub fn sessions(&self) -> Vec<SessionInfo> {
+        self.sessions
+            .borrow()
+            .map
+            .iter()
+            .map(|(session_id, (session_settings, state))| {
+                self.session_info(session_id, session_settings, &state.borrow())
+            })
+            .collect()
+    }
+
+    /// Details of registered session, `None` when session is not registered.
+    pub fn session(&self, session_id: &SessionId) -> Option<SessionInfo> {
+        let sessions = self.sessions.borrow();
+        let (session_settings, state) = sessions.map.get(session_id)?;
+        let session_info = self.session_info(session_id, session_settings, &state.borrow());
+        Some(session_info)
+    }
+
+    fn session_info(
+        &self,
+        session_id: &SessionId,
+        session_settings: &SessionSettings,
+        state: &SessionState<S, M>,
+    ) -> SessionInfo {
+        let connected = self.active_sessions.borrow().contains_key(session_id);
+        SessionInfo {
+            session_id: session_id.clone(),
+            session_settings: session_settings.clone(),
+            connected,
+            logged_on: connected && Session::<S, M>::is_logged_on(state),
+            next_sender_msg_seq_num: state.next_sender_msg_seq_num(),
+            next_target_msg_seq_num: state.next_target_msg_seq_num(),
+        }
+    }
+
+    /// Sender of connected session, `None` when session is not connected.
+    pub fn sender(&self, session_id: &SessionId) -> Option<Sender<M>> {
+        self.active_sessions
+            .borrow()
+            .get(session_id)
+            .map(|session| session.sender().clone())
+    }
+
+    fn logout_and_disconnect(&self, session_id: &SessionId, text: &[u8]) {
+        let Some(session) = self.active_sessions.borrow().get(session_id).cloned() else {
+            return;
+        };
+        info!("session {session_id} changed, disconnecting");
+        let mut state = session.state().borrow_mut();
+        if !state.logout_sent() {
+            session.send_logout(
+                &mut state,
+                None,
+                Some(FixString::from_ascii_lossy(text.to_vec())),
+            );
+        }
+        session.disconnect(&mut state, DisconnectReason::LocalRequestedLogout);
+    }
+
+    pub fn sessions_map(&self) -> Rc<RefCell<SessionsMap<S, M>>> {
         self.sessions.clone()
     }
 
     pub fn start(&self, connection: impl Connection + 'static) -> JoinHandle<()> {
-        tokio::task::spawn_local(Self::server_task(connection, self.session_task.clone()))
+        tokio::task::spawn_local(Self::server_task(
+            connection,
+            self.session_task.clone(),
+            self.shutdown_token.clone(),
+        ))
+    }
+
+    /// Gracefully shut down the acceptor.
+    ///
+    /// New connections are no longer accepted and Logout<5> (with
+    /// optional `reason`) is sent to all active sessions. Sessions which
+    /// don't finish Logout<5> exchange in `logout_timeout` are
+    /// disconnected. Returned future completes when all connections
+    /// are closed (with output queues flushed) and messages storages
+    /// are flushed. It doesn't borrow the acceptor, so it may be
+    /// spawned as separate task.
+    pub fn shutdown(
+        &self,
+        reason: Option<FixString>,
+        logout_timeout: Duration,
+    ) -> impl Future<Output = ()> + 'static {
+        info!("Acceptor shutdown");
+        self.shutdown_token.cancel();
+        let tracker = self.session_task.tracker.clone();
+        tracker.close();
+
+        for session in self.active_sessions() {
+            let mut state = session.state().borrow_mut();
+            if !state.logout_sent() {
+                session.send_logout(&mut state, None, reason.clone());
+            }
+        }
+
+        let active_sessions = self.active_sessions.clone();
+        let sessions = self.sessions.clone();
+        async move {
+            if timeout(logout_timeout, tracker.wait()).await.is_err() {
+                warn!("Logout not confirmed, disconnecting remaining sessions");
+                let active_sessions = active_sessions
+                    .borrow()
+                    .values()
+                    .cloned()
+                    .collect::<Vec<_>>();
+                for session in active_sessions {
+                    session.disconnect(
+                        &mut session.state().borrow_mut(),
+                        DisconnectReason::LocalRequestedLogout,
+                    );
+                }
+                tracker.wait().await;
+            }
+
+            let flush = sessions.borrow().flush();
+            flush.await;
+            info!("Acceptor shut down");
+        }
+    }
+
+    /// Whether `shutdown` was called.
+    pub(crate) fn is_shut_down(&self) -> bool {
+        self.shutdown_token.is_cancelled()
+    }
+
+    fn active_sessions(&self) -> Vec<Rc<Session<S, M>>> {
+        self.active_sessions.borrow().values().cloned().collect()
     }
 
     pub fn logout(
@@ -288,34 +712,57 @@ impl<S: MessagesStorage + 'static> Acceptor<S> {
             .set_next_sender_msg_seq_num(seq_num);
     }
 
-    async fn server_task(mut connection: impl Connection, session_task: SessionTask<S>) {
+    /// Round-trip time measured with the last answered TestRequest<1>,
+    /// `None` when there was no answered TestRequest yet.
+    #[instrument(skip(self))]
+    pub fn round_trip_time(&self, session_id: &SessionId) -> Option<Duration> {
+        let active_sessions = self.active_sessions.borrow();
+        let Some(session) = active_sessions.get(session_id) else {
+            warn!("session not found");
+            return None;
+        };
+
+        let state = session.state().borrow();
+        state.round_trip_time()
+    }
+
+    async fn server_task(
+        mut connection: impl Connection,
+        session_task: SessionTask<S, M>,
+        shutdown_token: CancellationToken,
+    ) {
         info!("Acceptor started");
         loop {
-            match connection.accept().await {
-                Ok((reader, writer, peer_addr)) => {
-                    tokio::task::spawn_local(session_task.clone().run(peer_addr, reader, writer));
+            let result = tokio::select! {
+                result = connection.accept() => result,
+                _ = shutdown_token.cancelled() => break,
+            };
+            match result {
+                Ok((reader, writer, peer_info)) => {
+                    tokio::task::spawn_local(session_task.clone().run(peer_info, reader, writer));
                 }
                 Err(err) => error!("server task failed to accept incoming connection: {err}"),
             }
         }
+        info!("Acceptor stopped");
     }
 
-    pub fn session_task(&self) -> SessionTask<S> {
+    pub fn session_task(&self) -> SessionTask<S, M> {
         self.session_task.clone()
     }
 
     pub fn run_session_task(
         &self,
-        peer_addr: SocketAddr,
+        peer_info: impl Into<PeerInfo>,
         reader: impl AsyncRead + Unpin + 'static,
         writer: impl AsyncWrite + Unpin + 'static,
     ) -> impl Future<Output = ()> {
-        self.session_task.clone().run(peer_addr, reader, writer)
+        self.session_task.clone().run(peer_info, reader, writer)
     }
 }
 
-impl<S: MessagesStorage> Stream for Acceptor<S> {
-    type Item = impl AsEvent;
+impl<S: MessagesStorage, M: SessionMessage> Stream for Acceptor<S, M> {
+    type Item = impl AsEvent<M>;
 
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
         Pin::new(&mut self.event_stream).poll_next(cx)
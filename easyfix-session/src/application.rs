@@ -1,8 +1,10 @@
 use std::{
-    fmt,
+    fmt, io,
     marker::PhantomData,
+    net::SocketAddr,
     pin::Pin,
     task::{Context, Poll},
+    time::Duration,
 };
 
 use easyfix_messages::{
@@ -12,6 +14,7 @@ use easyfix_messages::{
         SessionStatus, TagNum,
     },
     messages::FixtMessage,
+    session::SessionMessage,
 };
 use futures::Stream;
 use tokio::sync::{mpsc, oneshot};
@@ -155,14 +158,21 @@ impl<'a> InputResponder<'a> {
     }
 }
 
+/// Decision made about outgoing message, see `Responder`.
 #[derive(Debug)]
-pub struct Responder {
-    sender: Option<oneshot::Sender<Box<FixtMessage>>>,
+pub(crate) enum ResponderMsg<M = FixtMessage> {
+    Send(Box<M>),
+    GapFill(Box<M>),
+}
+
+#[derive(Debug)]
+pub struct Responder<M = FixtMessage> {
+    sender: Option<oneshot::Sender<ResponderMsg<M>>>,
     change_to_gap_fill: bool,
 }
 
-impl Responder {
-    pub(crate) fn new(sender: oneshot::Sender<Box<FixtMessage>>) -> Responder {
+impl<M> Responder<M> {
+    pub(crate) fn new(sender: oneshot::Sender<ResponderMsg<M>>) -> Responder<M> {
         Responder {
             sender: Some(sender),
             change_to_gap_fill: false,
@@ -174,56 +184,65 @@ impl Responder {
         self.sender.take();
     }
 
+    /// Send SequenceReset-GapFill<4> with the same MsgSeqNum<34> instead
+    /// of the message. `FixEvent::AppMsgGapFilled` is emitted then.
     pub fn change_to_gap_fill(&mut self) {
         self.change_to_gap_fill = true;
     }
 }
 
 #[derive(Debug)]
-pub(crate) enum FixEventInternal {
+pub(crate) enum FixEventInternal<M: SessionMessage = FixtMessage> {
+    Connecting(SessionId, SocketAddr),
+    ConnectionFailed(SessionId, SocketAddr, io::Error),
     Created(SessionId),
-    Logon(SessionId, Option<Sender>),
+    Logon(SessionId, Option<Sender<M>>),
     Logout(SessionId, DisconnectReason),
-    AppMsgIn(
-        Option<Box<FixtMessage>>,
-        Option<oneshot::Sender<InputResponderMsg>>,
-    ),
-    AdmMsgIn(
-        Option<Box<FixtMessage>>,
-        Option<oneshot::Sender<InputResponderMsg>>,
-    ),
-    AppMsgOut(Option<Box<FixtMessage>>, Responder),
-    AdmMsgOut(Option<Box<FixtMessage>>, Responder),
+    AppMsgIn(Option<Box<M>>, Option<oneshot::Sender<InputResponderMsg>>),
+    AdmMsgIn(Option<Box<M>>, Option<oneshot::Sender<InputResponderMsg>>),
+    AppMsgOut(Option<Box<M>>, Responder<M>),
+    AdmMsgOut(Option<Box<M>>, Responder<M>),
+    AppMsgGapFilled(SessionId, Box<M>),
+    AppMsgResend(SessionId, Option<Box<M>>, Responder<M>),
+    MsgThrottled(SessionId, M::MsgType, Duration),
+    MsgThrottleRejected(SessionId, Box<M>),
     DeserializeError(SessionId, DeserializeError),
 }
 
-impl Drop for FixEventInternal {
+impl<M: SessionMessage> Drop for FixEventInternal<M> {
     fn drop(&mut self) {
         if let FixEventInternal::AppMsgOut(ref mut msg, ref mut responder)
-        | FixEventInternal::AdmMsgOut(ref mut msg, ref mut responder) = self
+        | FixEventInternal::AdmMsgOut(ref mut msg, ref mut responder)
+        | FixEventInternal::AppMsgResend(_, ref mut msg, ref mut responder) = self
         {
             if let Some(sender) = responder.sender.take() {
+                let msg = msg.take().unwrap();
                 if responder.change_to_gap_fill {
-                    // TODO: GapFill HERE!
-                    sender.send(msg.take().unwrap()).unwrap();
+                    sender.send(ResponderMsg::GapFill(msg)).unwrap();
                 } else {
-                    sender.send(msg.take().unwrap()).unwrap();
+                    sender.send(ResponderMsg::Send(msg)).unwrap();
                 }
             }
         }
     }
 }
 
-/// FIX protolol events.
+/// FIX protolol events, `M` is the codec of the session.
 #[derive(Debug)]
-pub enum FixEvent<'a> {
+pub enum FixEvent<'a, M: SessionMessage = FixtMessage> {
+    /// Initiator is connecting to given address.
+    Connecting(&'a SessionId, SocketAddr),
+
+    /// Initiator failed to connect to given address.
+    ConnectionFailed(&'a SessionId, SocketAddr, &'a io::Error),
+
     /// Session created.
     Created(&'a SessionId),
 
     /// Successfull Logon<A> messages exchange.
     ///
     /// Use `Sender` to send messages to connected peer.
-    Logon(&'a SessionId, Sender),
+    Logon(&'a SessionId, Sender<M>),
 
     /// Session disconnected.
     Logout(&'a SessionId, DisconnectReason),
@@ -232,13 +251,13 @@ pub enum FixEvent<'a> {
     ///
     /// Use `InputResponder` to reject the message or to force logut or
     /// disconnection.
-    AppMsgIn(Box<FixtMessage>, InputResponder<'a>),
+    AppMsgIn(Box<M>, InputResponder<'a>),
 
     /// New administration message received.
     ///
     /// Use `InputResponder` to reject the message or to force logut or
     /// disconnection.
-    AdmMsgIn(Box<FixtMessage>, InputResponder<'a>),
+    AdmMsgIn(Box<M>, InputResponder<'a>),
 
     /// Application message is ready to be send.
     ///
@@ -247,7 +266,7 @@ pub enum FixEvent<'a> {
     /// This event may happen after session disconnection when output queue
     /// still has messages to send. In such case all messages will be stored
     /// and will be available thorough ResendRequest<2>.
-    AppMsgOut(&'a mut FixtMessage, &'a mut Responder), // TODO: Try pass by value but bind named
+    AppMsgOut(&'a mut M, &'a mut Responder<M>), // TODO: Try pass by value but bind named
 
     /// Administration message is ready to be send.
     ///
@@ -256,23 +275,46 @@ pub enum FixEvent<'a> {
     /// This event may happen after session disconnection when output queue
     /// still has messages to send. In such case all messages will be stored
     /// and will be available thorough ResendRequest<2>.
-    AdmMsgOut(&'a mut FixtMessage),
+    AdmMsgOut(&'a mut M),
+
+    /// Application message was replaced with SequenceReset-GapFill<4>,
+    /// as requested with `Responder::change_to_gap_fill`.
+    AppMsgGapFilled(&'a SessionId, &'a M),
+
+    /// Stored application message is about to be resent, as requested
+    /// by ResendRequest<2>. PossDupFlag<43> and OrigSendingTime<122>
+    /// are already set.
+    ///
+    /// Message may be updated (i.e. refreshed prices), except
+    /// MsgSeqNum<34>. Use `Responder` to change the message to GapFill
+    /// or to discard it - discarded message is replaced with gap fill
+    /// too, as counterparty expects all requested sequence numbers.
+    /// Message which is going to be sent is reported by `AppMsgOut` then.
+    AppMsgResend(&'a SessionId, &'a mut M, &'a mut Responder<M>),
+
+    /// Outbound message exceeded rate limit and is delayed by given time.
+    MsgThrottled(&'a SessionId, M::MsgType, Duration),
+
+    /// Outbound application message exceeded rate limit and was dropped,
+    /// as configured with `ThrottleAction::Reject`. Message has no MsgSeqNum<34>
+    /// assigned yet, so it may be sent again.
+    MsgThrottleRejected(&'a SessionId, &'a M),
 
     /// Failed to deserialize input message.
     DeserializeError(&'a SessionId, &'a DeserializeError),
 }
 
 #[derive(Debug)]
-pub struct EventStream {
-    receiver: ReceiverStream<FixEventInternal>,
+pub struct EventStream<M: SessionMessage = FixtMessage> {
+    receiver: ReceiverStream<FixEventInternal<M>>,
 }
 
 #[derive(Debug)]
-pub struct Emitter {
-    inner: mpsc::Sender<FixEventInternal>,
+pub struct Emitter<M: SessionMessage = FixtMessage> {
+    inner: mpsc::Sender<FixEventInternal<M>>,
 }
 
-impl Clone for Emitter {
+impl<M: SessionMessage> Clone for Emitter<M> {
     fn clone(&self) -> Self {
         Self {
             inner: self.inner.clone(),
@@ -280,15 +322,15 @@ impl Clone for Emitter {
     }
 }
 
-impl Emitter {
-    pub(crate) async fn send(&self, event: FixEventInternal) {
+impl<M: SessionMessage> Emitter<M> {
+    pub(crate) async fn send(&self, event: FixEventInternal<M>) {
         if let Err(_e) = self.inner.send(event).await {
             error!("Failed to send msg")
         }
     }
 }
 
-pub(crate) fn events_channel() -> (Emitter, EventStream) {
+pub(crate) fn events_channel<M: SessionMessage>() -> (Emitter<M>, EventStream<M>) {
     let (sender, receiver) = mpsc::channel(16);
 
     (
@@ -300,19 +342,25 @@ pub(crate) fn events_channel() -> (Emitter, EventStream) {
 }
 
 mod private {
+    use easyfix_messages::session::SessionMessage;
+
     pub trait Sealed {}
 
-    impl Sealed for super::FixEventInternal {}
+    impl<M: SessionMessage> Sealed for super::FixEventInternal<M> {}
 }
 
 /// This trait is sealed and not meant to be implemented outside of the current crate.
-pub trait AsEvent: private::Sealed {
-    fn as_event(&mut self) -> FixEvent<'_>;
+pub trait AsEvent<M: SessionMessage = FixtMessage>: private::Sealed {
+    fn as_event(&mut self) -> FixEvent<'_, M>;
 }
 
-impl AsEvent for FixEventInternal {
-    fn as_event(&mut self) -> FixEvent<'_> {
+impl<M: SessionMessage> AsEvent<M> for FixEventInternal<M> {
+    fn as_event(&mut self) -> FixEvent<'_, M> {
         match self {
+            FixEventInternal::Connecting(id, addr) => FixEvent::Connecting(id, *addr),
+            FixEventInternal::ConnectionFailed(id, addr, error) => {
+                FixEvent::ConnectionFailed(id, *addr, error)
+            }
             FixEventInternal::Created(id) => FixEvent::Created(id),
             FixEventInternal::Logon(id, sender) => FixEvent::Logon(id, sender.take().unwrap()),
             FixEventInternal::Logout(id, reason) => FixEvent::Logout(id, *reason),
@@ -328,6 +376,18 @@ impl AsEvent for FixEventInternal {
                 FixEvent::AppMsgOut(msg.as_mut().unwrap(), resp)
             }
             FixEventInternal::AdmMsgOut(msg, _) => FixEvent::AdmMsgOut(msg.as_mut().unwrap()),
+            FixEventInternal::AppMsgGapFilled(session_id, msg) => {
+                FixEvent::AppMsgGapFilled(session_id, msg)
+            }
+            FixEventInternal::AppMsgResend(session_id, msg, resp) => {
+                FixEvent::AppMsgResend(session_id, msg.as_mut().unwrap(), resp)
+            }
+            FixEventInternal::MsgThrottled(session_id, msg_type, delay) => {
+                FixEvent::MsgThrottled(session_id, *msg_type, *delay)
+            }
+            FixEventInternal::MsgThrottleRejected(session_id, msg) => {
+                FixEvent::MsgThrottleRejected(session_id, msg)
+            }
             FixEventInternal::DeserializeError(session_id, deserialize_error) => {
                 FixEvent::DeserializeError(session_id, deserialize_error)
             }
@@ -335,8 +395,8 @@ impl AsEvent for FixEventInternal {
     }
 }
 
-impl Stream for EventStream {
-    type Item = impl AsEvent;
+impl<M: SessionMessage> Stream for EventStream<M> {
+    type Item = impl AsEvent<M>;
 
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
         Pin::new(&mut self.receiver).poll_next(cx)
@@ -1,33 +1,234 @@
-use std::{cell::RefCell, collections::HashMap, net::SocketAddr, rc::Rc};
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    io,
+    net::SocketAddr,
+    pin::Pin,
+    rc::Rc,
+    task::{Context, Poll},
+    time::Duration,
+};
 
+use chrono::{DateTime, Days, Utc};
+use easyfix_messages::{messages::FixtMessage, session::SessionMessage};
+use futures::Stream;
 use pin_project::pin_project;
-use tokio::net::TcpStream;
-use tracing::{info, info_span, Instrument};
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::TcpStream,
+    task::JoinHandle,
+    time::{sleep, timeout},
+};
+use tracing::{error, info, info_span, warn, Instrument};
 
+#[cfg(feature = "tls")]
+use crate::tls::TlsConnector;
 use crate::{
-    application::{events_channel, Emitter, EventStream},
+    acceptor::SessionInfo,
+    application::{events_channel, AsEvent, Emitter, EventStream, FixEventInternal},
     io::initiator_connection,
     messages_storage::MessagesStorage,
     session::Session,
     session_id::SessionId,
     session_state::State,
-    settings::{SessionSettings, Settings},
-    Error,
+    settings::{ReconnectSettings, SessionSettings, Settings},
+    Error, Sender,
 };
 
 // TODO: Same as in Acceptor, not need for duplicate
-pub(crate) type ActiveSessionsMap<S> = HashMap<SessionId, Rc<Session<S>>>;
+pub(crate) type ActiveSessionsMap<S, M = FixtMessage> = HashMap<SessionId, Rc<Session<S, M>>>;
+
+type BoxedReader = Box<dyn AsyncRead + Unpin>;
+type BoxedWriter = Box<dyn AsyncWrite + Unpin>;
+
+/// How many days ahead logon window is searched for.
+const MAX_LOGON_WINDOW_SEARCH_DAYS: usize = 14;
 
 #[pin_project]
-pub struct Initiator<S: MessagesStorage> {
+pub struct Initiator<S: MessagesStorage, M: SessionMessage = FixtMessage> {
     id: SessionId,
     settings: Settings,
     session_settings: SessionSettings,
-    state: Rc<RefCell<State<S>>>,
-    active_sessions: Rc<RefCell<ActiveSessionsMap<S>>>,
-    emitter: Emitter,
+    state: Rc<RefCell<State<S, M>>>,
+    active_sessions: Rc<RefCell<ActiveSessionsMap<S, M>>>,
+    emitter: Emitter<M>,
     #[pin]
-    event_stream: EventStream,
+    event_stream: EventStream<M>,
+    #[cfg(feature = "tls")]
+    tls_connector: Option<TlsConnector>,
+}
+
+/// Everything needed to run a connection, detached from `Initiator`
+/// so it can be moved to the connection task.
+struct Connector<S, M: SessionMessage> {
+    settings: Settings,
+    session_settings: SessionSettings,
+    state: Rc<RefCell<State<S, M>>>,
+    active_sessions: Rc<RefCell<ActiveSessionsMap<S, M>>>,
+    emitter: Emitter<M>,
+    #[cfg(feature = "tls")]
+    tls_connector: Option<TlsConnector>,
+}
+
+/// Find the first moment, not earlier than `now`, when logon is possible
+/// (i.e. it's both inside session period and logon time).
+fn next_logon_time(session_settings: &SessionSettings, now: DateTime<Utc>) -> DateTime<Utc> {
+    let logon_time = &session_settings.logon_time;
+    let mut time = now;
+    for _ in 0..MAX_LOGON_WINDOW_SEARCH_DAYS {
+        if let Some(session_time) = &session_settings.session_time {
+            time = session_time.next_session_start(time);
+        }
+        if !logon_time.contains(&time.time()) {
+            let mut logon_start = time.date_naive().and_time(*logon_time.start()).and_utc();
+            if logon_start < time {
+                logon_start = logon_start + Days::new(1);
+            }
+            time = logon_start;
+        }
+        if session_settings
+            .session_time
+            .as_ref()
+            .is_none_or(|session_time| session_time.is_session_time(time))
+        {
+            return time;
+        }
+    }
+    warn!("Logon time doesn't overlap with session time");
+    now
+}
+
+impl<S: MessagesStorage + 'static, M: SessionMessage> Connector<S, M> {
+    async fn connect(
+        &self,
+        addr: SocketAddr,
+        connect_timeout: Option<Duration>,
+    ) -> Result<(BoxedReader, BoxedWriter), io::Error> {
+        let tcp_stream = match connect_timeout {
+            Some(connect_timeout) => timeout(connect_timeout, TcpStream::connect(addr))
+                .await
+                .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "connect timed out"))??,
+            None => TcpStream::connect(addr).await?,
+        };
+        tcp_stream.set_nodelay(true)?;
+        #[cfg(feature = "tls")]
+        if let Some(tls_connector) = &self.tls_connector {
+            let (reader, writer) = tokio::io::split(tls_connector.connect(tcp_stream).await?);
+            return Ok((Box::new(reader), Box::new(writer)));
+        }
+        let (reader, writer) = tcp_stream.into_split();
+        Ok((Box::new(reader), Box::new(writer)))
+    }
+
+    /// Run connection until it's closed, returns `true` when session was
+    /// logged on.
+    async fn run(&self, (reader, writer): (BoxedReader, BoxedWriter), addr: SocketAddr) -> bool {
+        let connection_span = info_span!("connection", %addr);
+        let logged_on = initiator_connection(
+            reader,
+            writer,
+            self.settings.clone(),
+            self.session_settings.clone(),
+            self.state.clone(),
+            self.active_sessions.clone(),
+            self.emitter.clone(),
+        )
+        .instrument(connection_span.clone())
+        .await;
+        connection_span.in_scope(|| {
+            info!("Connection closed");
+        });
+        logged_on
+    }
+
+    async fn run_managed(self, addrs: Vec<SocketAddr>, reconnect_settings: ReconnectSettings) {
+        if addrs.is_empty() {
+            error!("Initiator not started, no addresses given");
+            return;
+        }
+        if let Err(err) = reconnect_settings.validate() {
+            error!("Initiator not started, invalid reconnect settings: {err}");
+            return;
+        }
+        info!("Initiator started");
+
+        let session_id = &self.session_settings.session_id;
+        let connect_timeout = reconnect_settings.connect_timeout;
+        let mut backoff = Backoff::new(reconnect_settings);
+        loop {
+            let mut logged_on = false;
+            for addr in addrs.iter().copied() {
+                let logon_delay = (next_logon_time(&self.session_settings, Utc::now())
+                    - Utc::now())
+                .to_std()
+                .unwrap_or_default();
+                if !logon_delay.is_zero() {
+                    info!("Waiting {logon_delay:?} for logon time");
+                    sleep(logon_delay).await;
+                }
+
+                self.emitter
+                    .send(FixEventInternal::Connecting(session_id.clone(), addr))
+                    .await;
+                match self.connect(addr, Some(connect_timeout)).await {
+                    Ok(connection) => {
+                        // Connection closed before logon counts as failed
+                        // attempt, next address is tried
+                        if self.run(connection, addr).await {
+                            logged_on = true;
+                            break;
+                        }
+                        warn!("Connection to {addr} closed before logon");
+                    }
+                    Err(err) => {
+                        warn!("Failed to connect to {addr}: {err}");
+                        self.emitter
+                            .send(FixEventInternal::ConnectionFailed(
+                                session_id.clone(),
+                                addr,
+                                err,
+                            ))
+                            .await;
+                    }
+                }
+            }
+
+            let delay = backoff.next_delay(logged_on);
+            info!("Reconnecting in {delay:?}");
+            sleep(delay).await;
+        }
+    }
+}
+
+/// Delay between reconnection rounds.
+struct Backoff {
+    reconnect_settings: ReconnectSettings,
+    delay: Duration,
+}
+
+impl Backoff {
+    fn new(reconnect_settings: ReconnectSettings) -> Backoff {
+        Backoff {
+            delay: reconnect_settings.initial_delay,
+            reconnect_settings,
+        }
+    }
+
+    /// Delay before next round. It's reset to initial delay after
+    /// successful logon and grows after every round without logon.
+    fn next_delay(&mut self, logged_on: bool) -> Duration {
+        if logged_on {
+            self.delay = self.reconnect_settings.initial_delay;
+        }
+        let delay = self.delay;
+        if !logged_on {
+            self.delay = self
+                .delay
+                .saturating_mul(self.reconnect_settings.multiplier)
+                .min(self.reconnect_settings.max_delay);
+        }
+        delay
+    }
 }
 
 impl<S: MessagesStorage + 'static> Initiator<S> {
@@ -36,7 +237,44 @@ impl<S: MessagesStorage + 'static> Initiator<S> {
         session_settings: SessionSettings,
         messages_storage: S,
     ) -> Initiator<S> {
+        Initiator::with_codec(settings, session_settings, messages_storage)
+    }
+}
+
+impl<S: MessagesStorage + 'static, M: SessionMessage> Initiator<S, M> {
+    /// Create initiator running the session on codec `M`, i.e.
+    /// `Initiator::<_, fix42::messages::FixtMessage>::with_codec(..)`
+    /// for session with BeginString<8> `FIX.4.2`.
+    pub fn with_codec(
+        settings: Settings,
+        session_settings: SessionSettings,
+        messages_storage: S,
+    ) -> Initiator<S, M> {
         let (emitter, event_stream) = events_channel();
+        Initiator::with_events_channel(
+            settings,
+            session_settings,
+            messages_storage,
+            emitter,
+            event_stream,
+        )
+    }
+
+    /// Create initiator emitting events to given channel, which may be
+    /// shared with other initiators.
+    pub(crate) fn with_events_channel(
+        settings: Settings,
+        session_settings: SessionSettings,
+        messages_storage: S,
+        emitter: Emitter<M>,
+        event_stream: EventStream<M>,
+    ) -> Initiator<S, M> {
+        if !M::is_supported_begin_string(session_settings.session_id.begin_string()) {
+            warn!(
+                "session {}: BeginString<8> not supported by initiator codec",
+                session_settings.session_id
+            );
+        }
         Initiator {
             id: session_settings.session_id.clone(),
             settings,
@@ -45,6 +283,26 @@ impl<S: MessagesStorage + 'static> Initiator<S> {
             active_sessions: Rc::new(RefCell::new(HashMap::new())),
             emitter,
             event_stream,
+            #[cfg(feature = "tls")]
+            tls_connector: None,
+        }
+    }
+
+    /// Use TLS for all following connections.
+    #[cfg(feature = "tls")]
+    pub fn set_tls_connector(&mut self, tls_connector: TlsConnector) {
+        self.tls_connector = Some(tls_connector);
+    }
+
+    fn connector(&self) -> Connector<S, M> {
+        Connector {
+            settings: self.settings.clone(),
+            session_settings: self.session_settings.clone(),
+            state: self.state.clone(),
+            active_sessions: self.active_sessions.clone(),
+            emitter: self.emitter.clone(),
+            #[cfg(feature = "tls")]
+            tls_connector: self.tls_connector.clone(),
         }
     }
 
@@ -52,31 +310,190 @@ impl<S: MessagesStorage + 'static> Initiator<S> {
         info!("Initiator started");
 
         let addr = socket_addr.into();
-        let tcp_stream = TcpStream::connect(addr).await?;
-        tcp_stream.set_nodelay(true)?;
-        let emitter = self.emitter.clone();
-        let settings = self.settings.clone();
-        let session_settings = self.session_settings.clone();
-        let active_sessions = self.active_sessions.clone();
-        let state = self.state.clone();
+        let connector = self.connector();
+        let connection = connector.connect(addr, None).await?;
 
-        let connection_span = info_span!("connection", %addr);
+        tokio::task::spawn_local(async move { connector.run(connection, addr).await });
+        Ok(())
+    }
+
+    /// Start managed connection, re-established whenever it's closed.
+    ///
+    /// Addresses are tried in the given order (primary first, then backups),
+    /// every attempt is reported with `FixEvent::Connecting` and, when failed,
+    /// with `FixEvent::ConnectionFailed`. Connection not established within
+    /// `ReconnectSettings::connect_timeout` fails, connection closed before
+    /// logon counts as failed attempt too. When all addresses fail, next
+    /// round starts after exponentially growing delay, which is reset after
+    /// successful logon. Connection is attempted only inside logon time and
+    /// session period. Invalid `reconnect_settings` are logged and initiator
+    /// is not started.
+    ///
+    /// Managed connection is stopped with `JoinHandle::abort`, current
+    /// connection (if any) is dropped without Logout<5>.
+    pub fn start(
+        &self,
+        addrs: impl IntoIterator<Item = SocketAddr>,
+        reconnect_settings: ReconnectSettings,
+    ) -> JoinHandle<()> {
+        let addrs = addrs.into_iter().collect::<Vec<_>>();
+        let connector = self.connector();
+        tokio::task::spawn_local(
+            connector
+                .run_managed(addrs, reconnect_settings)
+                .instrument(info_span!("initiator", id = %self.id)),
+        )
+    }
+}
+
+impl<S: MessagesStorage, M: SessionMessage> Initiator<S, M> {
+    /// Session details.
+    pub fn session(&self) -> SessionInfo {
+        let state = self.state.borrow();
+        let connected = self.active_sessions.borrow().contains_key(&self.id);
+        SessionInfo {
+            session_id: self.id.clone(),
+            session_settings: self.session_settings.clone(),
+            connected,
+            logged_on: connected && Session::<S, M>::is_logged_on(&state),
+            next_sender_msg_seq_num: state.next_sender_msg_seq_num(),
+            next_target_msg_seq_num: state.next_target_msg_seq_num(),
+        }
+    }
+
+    /// Sender of the session, `None` when session is not connected.
+    pub fn sender(&self) -> Option<Sender<M>> {
+        self.active_sessions
+            .borrow()
+            .get(&self.id)
+            .map(|session| session.sender().clone())
+    }
+
+    /// Round-trip time measured with the last answered TestRequest<1>,
+    /// `None` when there was no answered TestRequest yet.
+    pub fn round_trip_time(&self) -> Option<Duration> {
+        self.state.borrow().round_trip_time()
+    }
+}
+
+impl<S: MessagesStorage, M: SessionMessage> Stream for Initiator<S, M> {
+    type Item = impl AsEvent<M>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.event_stream).poll_next(cx)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.event_stream.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::RangeInclusive;
+
+    use chrono::{NaiveDate, NaiveTime, TimeZone, Weekday};
+    use easyfix_messages::{deserializer::UnknownFields, fields::FixString};
+
+    use super::*;
+    use crate::session_time::SessionTime;
+
+    fn fix_string(s: &str) -> FixString {
+        FixString::from_ascii_lossy(s.as_bytes().to_vec())
+    }
 
-        tokio::task::spawn_local(async move {
-            initiator_connection(
-                tcp_stream,
-                settings,
-                session_settings,
-                state,
-                active_sessions,
-                emitter,
-            )
-            .instrument(connection_span.clone())
-            .await;
-            connection_span.in_scope(|| {
-                info!("Connection closed");
-            });
+    fn time(s: &str) -> NaiveTime {
+        s.parse().unwrap()
+    }
+
+    fn utc(date: &str, time: &str) -> DateTime<Utc> {
+        Utc.from_utc_datetime(
+            &date
+                .parse::<NaiveDate>()
+                .unwrap()
+                .and_time(time.parse().unwrap()),
+        )
+    }
+
+    fn session_settings(
+        session_time: Option<SessionTime>,
+        logon_time: RangeInclusive<NaiveTime>,
+    ) -> SessionSettings {
+        SessionSettings {
+            session_id: SessionId::new(
+                fix_string("FIXT.1.1"),
+                fix_string("initiator"),
+                fix_string("acceptor"),
+            ),
+            session_time,
+            logon_time,
+            send_redundant_resend_requests: false,
+            check_comp_id: true,
+            check_latency: true,
+            max_latency: Duration::from_secs(120),
+            reset_on_logon: false,
+            reset_on_logout: false,
+            reset_on_disconnect: false,
+            refresh_on_logon: false,
+            sender_default_appl_ver_id: fix_string("9"),
+            target_default_appl_ver_id: fix_string("9"),
+            enable_next_expected_msg_seq_num: false,
+            persist: true,
+            persist_inbound: false,
+            verify_logout: true,
+            tls_client_identity: None,
+            outbound_queue_limit: None,
+            throttle: None,
+            unknown_fields: UnknownFields::Reject,
+        }
+    }
+
+    #[test]
+    fn backoff() {
+        let mut backoff = Backoff::new(ReconnectSettings {
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(5),
+            multiplier: 2,
+            connect_timeout: Duration::from_secs(1),
         });
-        Ok(())
+        let delays = [false, false, false, false, true, false]
+            .map(|logged_on| backoff.next_delay(logged_on).as_secs());
+        assert_eq!(delays, [1, 2, 4, 5, 1, 1]);
+        assert_eq!(backoff.next_delay(false), Duration::from_secs(2));
+    }
+
+    #[test]
+    fn next_logon_time_in_logon_window() {
+        let session_settings = session_settings(None, time("08:00:00")..=time("17:00:00"));
+        let now = utc("2024-01-03", "12:00:00");
+        assert_eq!(next_logon_time(&session_settings, now), now);
+        assert_eq!(
+            next_logon_time(&session_settings, utc("2024-01-03", "06:00:00")),
+            utc("2024-01-03", "08:00:00")
+        );
+        assert_eq!(
+            next_logon_time(&session_settings, utc("2024-01-03", "18:00:00")),
+            utc("2024-01-04", "08:00:00")
+        );
+    }
+
+    #[test]
+    fn next_logon_time_in_session_period() {
+        // 2024-01-06 is Saturday
+        let session_time = SessionTime {
+            start_time: time("17:00:00"),
+            end_time: time("17:00:00"),
+            start_day: Some(Weekday::Sun),
+            end_day: Some(Weekday::Fri),
+            time_zone: chrono_tz::UTC,
+        };
+        let session_settings =
+            session_settings(Some(session_time), NaiveTime::MIN..=time("23:59:59"));
+        assert_eq!(
+            next_logon_time(&session_settings, utc("2024-01-06", "12:00:00")),
+            utc("2024-01-07", "17:00:00")
+        );
+        let now = utc("2024-01-08", "12:00:00");
+        assert_eq!(next_logon_time(&session_settings, now), now);
     }
 }
@@ -1,159 +1,338 @@
 use std::{
+    any::Any,
     cell::RefCell,
     collections::{hash_map::Entry, HashMap},
     rc::Rc,
     sync::Mutex,
 };
 
+use bytes::BytesMut;
 use easyfix_messages::{
-    fields::{FixString, SessionStatus},
+    deserializer::{raw_message, RawMessageError},
+    fields::{FixString, SessionStatus, Utc, UtcTimestamp},
     messages::{FixtMessage, Message},
+    serializer::Serializer,
+    session::{AdminMessage, SessionHeader, SessionMessage},
 };
 use futures_util::{pin_mut, Stream};
 use tokio::{
     self,
-    io::{AsyncRead, AsyncWrite, AsyncWriteExt},
-    net::TcpStream,
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
     sync::mpsc,
-    time::Duration,
+    time::{sleep_until, Instant},
 };
 use tokio_stream::StreamExt;
-use tracing::{debug, error, info, info_span, Instrument};
+use tracing::{debug, error, info, info_span, warn, Instrument};
 
 use crate::{
-    acceptor::{ActiveSessionsMap, SessionsMap},
+    acceptor::{ActiveSessionsMap, LogonRequest, PeerInfo, SessionsMap},
     application::{Emitter, FixEventInternal},
     messages_storage::MessagesStorage,
     session::Session,
     session_id::SessionId,
     session_state::State,
     settings::{SessionSettings, Settings},
-    DisconnectReason, Error, Sender, SessionError, NO_INBOUND_TIMEOUT_PADDING,
+    Delivery, DisconnectReason, Error, Sender, SessionError, NO_INBOUND_TIMEOUT_PADDING,
 };
 
 mod input_stream;
-pub use input_stream::{input_stream, InputEvent, InputStream};
+pub(crate) use input_stream::deserialize;
+use input_stream::{header_session_id, input_stream_with_buffer};
+pub use input_stream::{input_stream, input_stream_with_codec, InputEvent, InputStream};
 
 mod output_stream;
 use output_stream::{output_stream, OutputEvent};
 
+mod throttle;
+
 pub mod time;
 use time::{timeout, timeout_stream};
 
-static SENDERS: Mutex<Option<HashMap<SessionId, Sender>>> = Mutex::new(None);
+// Kept only for deprecated functions below, use `Acceptor::sender`
+// or `Initiator::sender` instead. When more engines connect sessions
+// with the same `SessionId`, the first connected one is registered.
+// Senders of all codecs are kept, `Sender<M>` is stored as `dyn Any`.
+static SENDERS: Mutex<Option<HashMap<SessionId, Box<dyn Any + Send>>>> = Mutex::new(None);
 
-pub fn register_sender(session_id: SessionId, sender: Sender) {
+fn register_global_sender<M: SessionMessage>(session_id: SessionId, sender: Sender<M>) {
     if let Entry::Vacant(entry) = SENDERS
         .lock()
         .unwrap()
         .get_or_insert_with(HashMap::new)
         .entry(session_id)
     {
-        entry.insert(sender);
+        entry.insert(Box::new(sender));
+    }
+}
+
+/// Unregister `sender`, sender of the same session registered
+/// by another engine is kept.
+fn unregister_global_sender<M: SessionMessage>(session_id: &SessionId, sender: &Sender<M>) {
+    let mut senders = SENDERS.lock().unwrap();
+    let senders = senders.get_or_insert_with(HashMap::new);
+    if let Entry::Occupied(entry) = senders.entry(session_id.clone()) {
+        if entry
+            .get()
+            .downcast_ref::<Sender<M>>()
+            .is_some_and(|registered| registered.same_channel(sender))
+        {
+            entry.remove();
+        }
     }
 }
 
+#[deprecated(note = "senders are registered by `Acceptor` and `Initiator`")]
+pub fn register_sender(session_id: SessionId, sender: Sender) {
+    register_global_sender(session_id, sender)
+}
+
+#[deprecated(note = "senders are unregistered by `Acceptor` and `Initiator`")]
 pub fn unregister_sender(session_id: &SessionId) {
-    if SENDERS
+    SENDERS
         .lock()
         .unwrap()
         .get_or_insert_with(HashMap::new)
-        .remove(session_id)
-        .is_none()
-    {
-        // TODO: ERROR?
-    }
+        .remove(session_id);
 }
 
+/// Sender of connected session. When more engines connect sessions
+/// with the same `SessionId`, it's sender of the first connected one.
+///
+/// Sessions running on other codecs than `easyfix_messages::messages`
+/// are not available here.
+#[deprecated(
+    note = "process-wide registry is ambiguous when engines share `SessionId`, \
+                     use `Acceptor::sender` or `Initiator::sender`"
+)]
 pub fn sender(session_id: &SessionId) -> Option<Sender> {
     SENDERS
         .lock()
         .unwrap()
         .get_or_insert_with(HashMap::new)
         .get(session_id)
+        .and_then(|sender| sender.downcast_ref::<Sender>())
         .cloned()
 }
 
-// TODO: Remove?
+#[deprecated(note = "use `Acceptor::sender` or `Initiator::sender`")]
+#[allow(deprecated)]
 pub fn send(session_id: &SessionId, msg: Box<Message>) -> Result<(), Box<Message>> {
     if let Some(sender) = sender(session_id) {
-        sender.send(msg).map_err(|msg| msg.body)
+        sender.send(msg).map(|_| ()).map_err(|msg| msg.body)
     } else {
         Err(msg)
     }
 }
 
+#[deprecated(note = "use `Acceptor::sender` or `Initiator::sender`")]
+#[allow(deprecated)]
 pub fn send_raw(msg: Box<FixtMessage>) -> Result<(), Box<FixtMessage>> {
-    if let Some(sender) = sender(&SessionId::from_input_msg(&msg)) {
-        sender.send_raw(msg)
+    if let Some(sender) = sender(&SessionId::from_input_msg(&*msg)) {
+        sender.send_raw(msg).map(|_| ())
     } else {
         Err(msg)
     }
 }
 
-async fn first_msg(
-    stream: &mut (impl Stream<Item = InputEvent> + Unpin),
-    logon_timeout: Duration,
-) -> Result<Box<FixtMessage>, Error> {
-    match timeout(logon_timeout, stream.next()).await {
-        Ok(Some(InputEvent::Message(msg))) => Ok(msg),
-        Ok(Some(InputEvent::IoError(error))) => Err(error.into()),
-        Ok(Some(InputEvent::DeserializeError(error))) => {
-            error!("failed to deserialize first message: {error}");
-            Err(Error::SessionError(SessionError::LogonNeverReceived))
+/// Read first message from new connection and find out which session
+/// it belongs to. All bytes read are left in `buffer`.
+pub(crate) async fn read_session_id(
+    reader: &mut (impl AsyncRead + Unpin),
+    buffer: &mut BytesMut,
+) -> Result<SessionId, Error> {
+    loop {
+        match raw_message(buffer) {
+            Ok((_, raw_msg)) => {
+                // Only header is read, message is deserialized once
+                // session (and its handling of unknown fields) is known.
+                return header_session_id(raw_msg).map_err(|err| {
+                    error!("failed to read session id of first message: {err:?}");
+                    Error::SessionError(SessionError::LogonNeverReceived)
+                });
+            }
+            Err(RawMessageError::Incomplete) => {
+                if reader.read_buf(buffer).await? == 0 {
+                    return Err(Error::SessionError(SessionError::LogonNeverReceived));
+                }
+            }
+            Err(err) => {
+                error!("garbled first message: {err:?}");
+                return Err(Error::SessionError(SessionError::LogonNeverReceived));
+            }
         }
-        _ => Err(Error::SessionError(SessionError::LogonNeverReceived)),
     }
 }
 
 #[derive(Debug)]
-struct Connection<S> {
-    session: Rc<Session<S>>,
+struct Connection<S, M: SessionMessage = FixtMessage> {
+    session: Rc<Session<S, M>>,
+}
+
+/// Send standalone Logout<5> to counterparty whose connection is
+/// rejected before it's bound to the session.
+async fn reject_logon<M: SessionMessage>(
+    writer: &mut (impl AsyncWrite + Unpin),
+    session_id: &SessionId,
+    session_status: Option<SessionStatus>,
+    text: Option<FixString>,
+) {
+    let mut logout = M::from_admin(AdminMessage::Logout {
+        session_status: session_status.map(|status| status.as_fix_str().to_owned()),
+        text,
+    });
+    let header = logout.header_mut();
+    header.set_begin_string(session_id.begin_string().to_owned());
+    header.set_sender_comp_id(session_id.sender_comp_id().to_owned());
+    header.set_target_comp_id(session_id.target_comp_id().to_owned());
+    header.set_msg_seq_num(1);
+    header.set_sending_time(UtcTimestamp::now());
+    if let Err(err) = writer
+        .write_all(&logout.serialize_with(Serializer::new()))
+        .await
+    {
+        error!("failed to send Logout: {err}");
+    } else if let Err(err) = writer.flush().await {
+        error!("failed to send Logout: {err}");
+    }
 }
 
-pub(crate) async fn acceptor_connection<S>(
-    reader: impl AsyncRead + Unpin,
-    writer: impl AsyncWrite + Unpin,
+pub(crate) async fn acceptor_connection<S, M>(
+    mut reader: impl AsyncRead + Unpin,
+    mut writer: impl AsyncWrite + Unpin,
+    peer_info: PeerInfo,
     settings: Settings,
-    sessions: Rc<RefCell<SessionsMap<S>>>,
-    active_sessions: Rc<RefCell<ActiveSessionsMap<S>>>,
-    emitter: Emitter,
+    sessions: Rc<RefCell<SessionsMap<S, M>>>,
+    active_sessions: Rc<RefCell<ActiveSessionsMap<S, M>>>,
+    emitter: Emitter<M>,
 ) where
     S: MessagesStorage,
+    M: SessionMessage,
 {
-    let stream = input_stream(reader);
     let logon_timeout =
         settings.auto_disconnect_after_no_logon_received + NO_INBOUND_TIMEOUT_PADDING;
-    pin_mut!(stream);
-    let msg = match first_msg(&mut stream, logon_timeout).await {
-        Ok(msg) => msg,
-        Err(err) => {
+    let mut buffer = BytesMut::new();
+    let session_id = match timeout(logon_timeout, read_session_id(&mut reader, &mut buffer)).await {
+        Ok(Ok(session_id)) => session_id,
+        Ok(Err(err)) => {
             error!("failed to establish new session: {err}");
             return;
         }
+        Err(_) => {
+            error!("failed to establish new session: logon timeout");
+            return;
+        }
     };
-    let session_id = SessionId::from_input_msg(&msg);
-    debug!("first_msg: {msg:?}");
 
-    let (sender, receiver) = mpsc::unbounded_channel();
-    let sender = Sender::new(sender);
-
-    let Some((session_settings, session_state)) = sessions.borrow().get_session(&session_id) else {
+    let session = sessions.borrow_mut().get_or_create_session(&session_id);
+    let Some((session_settings, session_state)) = session else {
         error!("failed to establish new session: unknown session id {session_id}");
+        reject_logon::<M>(
+            &mut writer,
+            &session_id,
+            None,
+            Some(FixString::from_ascii_lossy(b"Unknown session".to_vec())),
+        )
+        .await;
         return;
     };
+    if let Some(tls_client_identity) = &session_settings.tls_client_identity {
+        if peer_info.tls_identity.as_ref() != Some(tls_client_identity) {
+            error!(
+                "failed to establish new session {session_id}: TLS client identity {:?} \
+                 doesn't match {tls_client_identity:?}",
+                peer_info.tls_identity
+            );
+            reject_logon::<M>(
+                &mut writer,
+                &session_id,
+                None,
+                Some(FixString::from_ascii_lossy(
+                    b"TLS client identity mismatch".to_vec(),
+                )),
+            )
+            .await;
+            sessions.borrow_mut().release_session(&session_id);
+            return;
+        }
+    }
+    let first_msg = raw_message(&buffer)
+        .map_err(Into::into)
+        .and_then(|(leftover, raw_msg)| {
+            let msg_len = buffer.len() - leftover.len();
+            deserialize::<M>(raw_msg, session_settings.unknown_fields).map(|msg| (msg, msg_len))
+        });
+    let (msg, raw) = match first_msg {
+        Ok((msg, msg_len)) => (msg, buffer.split_to(msg_len).freeze()),
+        Err(err) => {
+            error!("failed to establish new session {session_id}: {err}");
+            reject_logon::<M>(
+                &mut writer,
+                &session_id,
+                None,
+                Some(FixString::from_ascii_lossy(err.to_string().into_bytes())),
+            )
+            .await;
+            sessions.borrow_mut().release_session(&session_id);
+            return;
+        }
+    };
+    debug!("first_msg: {msg:?}");
+    let mut stream = input_stream_with_buffer(reader, buffer);
+    stream.set_unknown_fields(session_settings.unknown_fields);
+    pin_mut!(stream);
+
+    // Logon<A> is verified before session state is touched, so rejected
+    // counterparty can't disturb session which is already connected.
+    let authentication = match msg.to_admin() {
+        Some(AdminMessage::Logon {
+            username, password, ..
+        }) => sessions.borrow().authenticate(LogonRequest {
+            session_id: session_id.clone(),
+            username,
+            password,
+            peer_info,
+        }),
+        _ => None,
+    };
+    if let Some(authentication) = authentication {
+        if let Err(rejection) = authentication.await {
+            warn!(
+                "failed to establish new session {session_id}: Logon rejected: {:?}",
+                rejection.text
+            );
+            reject_logon::<M>(
+                &mut writer,
+                &session_id,
+                rejection.session_status,
+                rejection.text,
+            )
+            .await;
+            sessions.borrow_mut().release_session(&session_id);
+            return;
+        }
+    }
+
     session_state.borrow_mut().set_disconnected(false);
-    register_sender(session_id.clone(), sender.clone());
+
+    let (sender, receiver) = mpsc::unbounded_channel();
+    let sender = Sender::new(sender, session_settings.outbound_queue_limit);
+
+    register_global_sender(session_id.clone(), sender.clone());
     let session = Rc::new(Session::new(
         settings,
         session_settings,
         session_state,
-        sender,
+        sender.clone(),
         emitter.clone(),
     ));
     active_sessions
         .borrow_mut()
         .insert(session_id.clone(), session.clone());
+    let active_session = ActiveSessionGuard {
+        active_sessions,
+        session_id: session_id.clone(),
+        sender,
+    };
 
     let session_span = info_span!(
         "session",
@@ -163,8 +342,9 @@ pub(crate) async fn acceptor_connection<S>(
     let input_loop_span = info_span!(parent: &session_span, "in");
     let output_loop_span = info_span!(parent: &session_span, "out");
 
+    session.reset_on_new_session_period(&mut session.state().borrow_mut());
     let force_disconnection_with_reason = session
-        .on_message_in(msg)
+        .on_message_in(msg, &raw)
         .instrument(input_loop_span.clone())
         .await;
 
@@ -173,6 +353,7 @@ pub(crate) async fn acceptor_connection<S>(
         .send(FixEventInternal::Created(session_id.clone()))
         .await;
 
+    // Logon<A> is already processed, so timers use negotiated heartbeat interval.
     let input_timeout_duration = session.heartbeat_interval() + NO_INBOUND_TIMEOUT_PADDING;
     let input_stream = timeout_stream(input_timeout_duration, stream)
         .map(|res| res.unwrap_or(InputEvent::Timeout));
@@ -183,54 +364,77 @@ pub(crate) async fn acceptor_connection<S>(
 
     let connection = Connection::new(session);
     let (input_closed_tx, input_closed_rx) = tokio::sync::oneshot::channel();
+    let (output_closed_tx, output_closed_rx) = tokio::sync::oneshot::channel();
 
     tokio::join!(
         connection
             .input_loop(
                 input_stream,
                 input_closed_tx,
+                output_closed_rx,
                 force_disconnection_with_reason
             )
             .instrument(input_loop_span.clone()),
         connection
-            .output_loop(writer, output_stream, input_closed_rx)
+            .output_loop(writer, output_stream, input_closed_rx, output_closed_tx)
             .instrument(output_loop_span),
     );
     session_span.in_scope(|| {
         info!("connection closed");
     });
-    unregister_sender(&session_id);
-    active_sessions.borrow_mut().remove(&session_id);
+    drop(active_session);
+    sessions.borrow_mut().release_session(&session_id);
 }
 
-pub(crate) async fn initiator_connection<S>(
-    tcp_stream: TcpStream,
+pub(crate) async fn initiator_connection<S, M>(
+    source: impl AsyncRead + Unpin,
+    sink: impl AsyncWrite + Unpin,
     settings: Settings,
     session_settings: SessionSettings,
-    state: Rc<RefCell<State<S>>>,
-    active_sessions: Rc<RefCell<ActiveSessionsMap<S>>>,
-    emitter: Emitter,
-) where
+    state: Rc<RefCell<State<S, M>>>,
+    active_sessions: Rc<RefCell<ActiveSessionsMap<S, M>>>,
+    emitter: Emitter<M>,
+) -> bool
+where
     S: MessagesStorage,
+    M: SessionMessage,
 {
-    let (source, sink) = tcp_stream.into_split();
-    state.borrow_mut().set_disconnected(false);
     let session_id = session_settings.session_id.clone();
+    if let Some(session_time) = &session_settings.session_time {
+        if !session_time.is_session_time(Utc::now()) {
+            warn!("failed to establish session {session_id}: outside of session time");
+            return false;
+        }
+    }
+
+    {
+        let mut state = state.borrow_mut();
+        // Logon<A> received by initiator is a response, not a request
+        state.set_initiate(true);
+        state.set_disconnected(false);
+        state.set_logon_completed(false);
+    }
 
     let (sender, receiver) = mpsc::unbounded_channel();
-    let sender = Sender::new(sender);
+    let sender = Sender::new(sender, session_settings.outbound_queue_limit);
 
-    register_sender(session_id.clone(), sender.clone());
+    register_global_sender(session_id.clone(), sender.clone());
     let session = Rc::new(Session::new(
         settings,
         session_settings,
         state,
-        sender,
+        sender.clone(),
         emitter.clone(),
     ));
     active_sessions
         .borrow_mut()
         .insert(session_id.clone(), session.clone());
+    // Connection future is dropped when initiator task is aborted
+    let _active_session = ActiveSessionGuard {
+        active_sessions,
+        session_id: session_id.clone(),
+        sender,
+    };
 
     let session_span = info_span!(
         "session",
@@ -246,42 +450,61 @@ pub(crate) async fn initiator_connection<S>(
         .await;
 
     let input_timeout_duration = session.heartbeat_interval() + NO_INBOUND_TIMEOUT_PADDING;
-    let input_stream = timeout_stream(input_timeout_duration, input_stream(source))
+    // TODO: Max MSG size
+    let mut source = input_stream_with_buffer(source, BytesMut::with_capacity(4096));
+    source.set_unknown_fields(session.unknown_fields());
+    let input_stream = timeout_stream(input_timeout_duration, source)
         .map(|res| res.unwrap_or(InputEvent::Timeout));
     pin_mut!(input_stream);
 
     let output_stream = output_stream(session.clone(), session.heartbeat_interval(), receiver);
     pin_mut!(output_stream);
 
-    // TODO: It's not so simple, add check if session time is within range,
-    //       if not schedule timer to send logon at proper time
+    session.reset_on_new_session_period(&mut session.state().borrow_mut());
     session.send_logon_request(&mut session.state().borrow_mut());
 
     let connection = Connection::new(session);
     let (input_closed_tx, input_closed_rx) = tokio::sync::oneshot::channel();
+    let (output_closed_tx, output_closed_rx) = tokio::sync::oneshot::channel();
 
     tokio::join!(
         connection
-            .input_loop(input_stream, input_closed_tx, None)
+            .input_loop(input_stream, input_closed_tx, output_closed_rx, None)
             .instrument(input_loop_span),
         connection
-            .output_loop(sink, output_stream, input_closed_rx)
+            .output_loop(sink, output_stream, input_closed_rx, output_closed_tx)
             .instrument(output_loop_span),
     );
     info!("connection closed");
-    unregister_sender(&session_id);
-    active_sessions.borrow_mut().remove(&session_id);
+    let logon_completed = connection.session.state().borrow().logon_completed();
+    logon_completed
+}
+
+/// Removes session from active sessions (and its sender from global
+/// registry) when dropped.
+struct ActiveSessionGuard<S: MessagesStorage, M: SessionMessage> {
+    active_sessions: Rc<RefCell<ActiveSessionsMap<S, M>>>,
+    session_id: SessionId,
+    sender: Sender<M>,
+}
+
+impl<S: MessagesStorage, M: SessionMessage> Drop for ActiveSessionGuard<S, M> {
+    fn drop(&mut self) {
+        self.active_sessions.borrow_mut().remove(&self.session_id);
+        unregister_global_sender(&self.session_id, &self.sender);
+    }
 }
 
-impl<S: MessagesStorage> Connection<S> {
-    fn new(session: Rc<Session<S>>) -> Connection<S> {
+impl<S: MessagesStorage, M: SessionMessage> Connection<S, M> {
+    fn new(session: Rc<Session<S, M>>) -> Connection<S, M> {
         Connection { session }
     }
 
     async fn input_loop(
         &self,
-        mut input_stream: impl Stream<Item = InputEvent> + Unpin,
+        mut input_stream: impl Stream<Item = InputEvent<M>> + Unpin,
         input_closed_tx: tokio::sync::oneshot::Sender<()>,
+        mut output_closed_rx: tokio::sync::oneshot::Receiver<()>,
         force_disconnection_with_reason: Option<DisconnectReason>,
     ) {
         if let Some(disconnect_reason) = force_disconnection_with_reason {
@@ -300,7 +523,47 @@ impl<S: MessagesStorage> Connection<S> {
 
         let mut disconnect_reason = DisconnectReason::Disconnected;
 
-        while let Some(event) = input_stream.next().await {
+        // When session period ends, Logout<5> is sent and connection
+        // is dropped if counterparty doesn't respond in heartbeat interval.
+        let session_end = self.session.session_end();
+        let mut session_ended = false;
+        let session_end_timer = sleep_until(session_end.map_or_else(Instant::now, |session_end| {
+            Instant::now() + (session_end - Utc::now()).to_std().unwrap_or_default()
+        }));
+        pin_mut!(session_end_timer);
+
+        loop {
+            let event = tokio::select! {
+                event = input_stream.next() => match event {
+                    Some(event) => event,
+                    None => break,
+                },
+                // Session was disconnected locally (e.g. by
+                // `Acceptor::disconnect`), don't wait for next input
+                _ = &mut output_closed_rx => {
+                    info!("output closed, exit input processing");
+                    break;
+                }
+                _ = &mut session_end_timer, if session_end.is_some() => {
+                    if session_ended {
+                        warn!("Logout not confirmed");
+                        disconnect_reason = DisconnectReason::SessionTimeEnded;
+                        break;
+                    }
+                    info!("Session time ended");
+                    session_ended = true;
+                    self.session.send_logout(
+                        &mut self.session.state().borrow_mut(),
+                        Some(SessionStatus::SessionLogoutComplete),
+                        Some(FixString::from_ascii_lossy(b"Session time ended".to_vec())),
+                    );
+                    session_end_timer
+                        .as_mut()
+                        .reset(Instant::now() + self.session.heartbeat_interval());
+                    continue;
+                }
+            };
+
             // Don't accept new messages if session is disconnected.
             if self.session.state().borrow().disconnected() {
                 info!("session disconnected, exit input processing");
@@ -313,13 +576,18 @@ impl<S: MessagesStorage> Connection<S> {
                 return;
             }
             match event {
-                InputEvent::Message(msg) => {
-                    if let Some(dr) = self.session.on_message_in(msg).await {
+                InputEvent::Message(msg, raw) => {
+                    if let Some(dr) = self.session.on_message_in(msg, &raw).await {
                         info!("disconnect ({dr:?}), exit input processing");
                         disconnect_reason = dr;
                         break;
                     }
-                    self.session.state().borrow_mut().set_input_timoeut_cnt(0);
+                    let mut state = self.session.state().borrow_mut();
+                    // While TestRequest is outstanding, only matching
+                    // Heartbeat clears the counter (see `Session::on_heartbeat`)
+                    if state.test_request().is_none() {
+                        state.set_input_timoeut_cnt(0);
+                    }
                 }
                 InputEvent::DeserializeError(error) => {
                     if let Some(dr) = self.session.on_deserialize_error(error).await {
@@ -363,20 +631,23 @@ impl<S: MessagesStorage> Connection<S> {
         mut sink: impl AsyncWrite + Unpin,
         mut output_stream: impl Stream<Item = OutputEvent> + Unpin,
         input_closed_rx: tokio::sync::oneshot::Receiver<()>,
+        output_closed_tx: tokio::sync::oneshot::Sender<()>,
     ) {
         let mut sink_closed = false;
         let mut disconnect_reason = DisconnectReason::Disconnected;
         while let Some(event) = output_stream.next().await {
             match event {
-                OutputEvent::Message(msg) => {
+                OutputEvent::Message(msg, delivery, token) => {
                     if sink_closed {
                         // Sink is closed - ignore message, but do not break
                         // the loop. Output stream has to process all enqueued
                         // messages to made them available
                         // for ResendRequest<2>.
                         info!("Client disconnected, message will be stored for further resend");
+                        token.confirm(Delivery::Disconnected(delivery.seq_num()));
                     } else if let Err(error) = sink.write_all(&msg).await {
                         sink_closed = true;
+                        token.confirm(Delivery::Disconnected(delivery.seq_num()));
                         error!("Output write error: {error:?}");
                         // XXX: Don't disconnect now. If IO error happened
                         //      here, it will aslo happen in input loop
@@ -389,6 +660,8 @@ impl<S: MessagesStorage> Connection<S> {
                         //     &mut self.session.state().borrow_mut(),
                         //     DisconnectReason::IoError,
                         // );
+                    } else {
+                        token.confirm(delivery);
                     }
                 }
                 OutputEvent::Timeout => self.session.on_out_timeout().await,
@@ -411,6 +684,9 @@ impl<S: MessagesStorage> Connection<S> {
         //      randomly before or after.
         self.session.emit_logout(disconnect_reason).await;
 
+        // Wake up input loop, it may be still waiting for input
+        let _ = output_closed_tx.send(());
+
         // Don't wait for any specific value it's just notification that
         // input_loop finished, so no more messages can be added to output
         // queue.
@@ -1,13 +1,18 @@
 use std::{
     io,
+    marker::PhantomData,
     pin::Pin,
     task::{ready, Context, Poll},
 };
 
-use bytes::BytesMut;
+use bytes::{Bytes, BytesMut};
 use easyfix_messages::{
-    deserializer::{self, raw_message, RawMessageError},
+    deserializer::{
+        self, raw_message, Deserializer, ParseRejectReason, RawMessage, RawMessageError,
+        UnknownFields,
+    },
     messages::FixtMessage,
+    session::SessionMessage,
 };
 use futures_util::Stream;
 use pin_project::pin_project;
@@ -15,11 +20,12 @@ use tokio::io::AsyncRead;
 use tokio_util::io::poll_read_buf;
 use tracing::{debug, info, warn};
 
-use crate::application::DeserializeError;
+use crate::{application::DeserializeError, session_id::SessionId};
 
 #[derive(Debug)]
-pub enum InputEvent {
-    Message(Box<FixtMessage>),
+pub enum InputEvent<M = FixtMessage> {
+    /// Deserialized message with its bytes, as received.
+    Message(Box<M>, Bytes),
     DeserializeError(DeserializeError),
     IoError(io::Error),
     Timeout,
@@ -38,9 +44,55 @@ fn process_garbled_data(buf: &mut BytesMut) {
     info!("dropped {len} bytes of garbled message");
 }
 
-fn parse_message(
+pub(crate) fn deserialize<M: SessionMessage>(
+    raw_msg: RawMessage,
+    unknown_fields: UnknownFields,
+) -> Result<Box<M>, deserializer::DeserializeError> {
+    let mut deserializer = Deserializer::from_raw_message(raw_msg);
+    deserializer.set_unknown_fields(unknown_fields);
+    M::deserialize(deserializer)
+}
+
+/// Read id of session message belongs to from its header, without
+/// deserializing whole message.
+pub(crate) fn header_session_id(
+    raw_msg: RawMessage,
+) -> Result<SessionId, deserializer::DeserializeError> {
+    let begin_string = raw_msg.begin_string.to_owned();
+    let mut deserializer = Deserializer::from_raw_message(raw_msg);
+    let mut sender_comp_id = None;
+    let mut target_comp_id = None;
+    while let Some(tag) = deserializer.deserialize_tag_num()? {
+        match tag {
+            49 => sender_comp_id = Some(deserializer.deserialize_string()?),
+            56 => target_comp_id = Some(deserializer.deserialize_string()?),
+            // SecureDataLen<90> and XmlDataLen<212>, data that follows
+            // may contain SOH
+            90 | 212 => {
+                let len = deserializer.deserialize_length()?;
+                deserializer.deserialize_tag_num()?;
+                deserializer.deserialize_data(len as usize)?;
+            }
+            _ => {
+                deserializer.deserialize_str()?;
+            }
+        }
+        if let (Some(sender_comp_id), Some(target_comp_id)) = (&sender_comp_id, &target_comp_id) {
+            return Ok(SessionId::new(
+                begin_string,
+                target_comp_id.clone(),
+                sender_comp_id.clone(),
+            ));
+        }
+    }
+    let missing_tag = if sender_comp_id.is_none() { 49 } else { 56 };
+    Err(deserializer.reject(Some(missing_tag), ParseRejectReason::RequiredTagMissing))
+}
+
+fn parse_message<M: SessionMessage>(
     bytes: &mut BytesMut,
-) -> Result<Option<Box<FixtMessage>>, deserializer::DeserializeError> {
+    unknown_fields: UnknownFields,
+) -> Result<Option<(Box<M>, Bytes)>, deserializer::DeserializeError> {
     if bytes.is_empty() {
         return Ok(None);
     }
@@ -53,10 +105,10 @@ fn parse_message(
 
     match raw_message(bytes) {
         Ok((leftover, raw_msg)) => {
-            let result = FixtMessage::from_raw_message(raw_msg).map(Some);
+            let result = deserialize(raw_msg, unknown_fields);
             let leftover_len = leftover.len();
-            bytes.split_to(src_len - leftover_len).freeze();
-            result
+            let raw = bytes.split_to(src_len - leftover_len).freeze();
+            result.map(|msg| Some((msg, raw)))
         }
         Err(RawMessageError::Incomplete) => Ok(None),
         Err(err) => {
@@ -67,17 +119,28 @@ fn parse_message(
 }
 
 #[pin_project]
-pub struct InputStream<S> {
+pub struct InputStream<S, M = FixtMessage> {
     buffer: BytesMut,
     #[pin]
     source: S,
+    unknown_fields: UnknownFields,
+    phantom: PhantomData<M>,
+}
+
+impl<S, M> InputStream<S, M> {
+    /// Change handling of fields not defined for the message,
+    /// applies to messages read from now on.
+    pub fn set_unknown_fields(&mut self, unknown_fields: UnknownFields) {
+        self.unknown_fields = unknown_fields;
+    }
 }
 
-impl<S> Stream for InputStream<S>
+impl<S, M> Stream for InputStream<S, M>
 where
     S: AsyncRead + Unpin,
+    M: SessionMessage,
 {
-    type Item = InputEvent;
+    type Item = InputEvent<M>;
 
     fn poll_next(self: std::pin::Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
         let mut this = self.project();
@@ -85,9 +148,9 @@ where
         loop {
             // Attempt to parse a message from the buffered data.
             // If enough data has been buffered, the message is returned.
-            match parse_message(this.buffer) {
-                Ok(Some(msg)) => {
-                    return Poll::Ready(Some(InputEvent::Message(msg)));
+            match parse_message(this.buffer, *this.unknown_fields) {
+                Ok(Some((msg, raw))) => {
+                    return Poll::Ready(Some(InputEvent::Message(msg, raw)));
                 }
                 Ok(None) => {}
                 // Convert `deserializer::DeserializeError` to `application::DeserializeError`
@@ -127,10 +190,30 @@ where
 pub fn input_stream<S>(source: S) -> InputStream<S>
 where
     S: AsyncRead + Unpin,
+{
+    input_stream_with_codec(source)
+}
+
+/// Like `input_stream`, with messages deserialized by codec `M`.
+pub fn input_stream_with_codec<S, M>(source: S) -> InputStream<S, M>
+where
+    S: AsyncRead + Unpin,
+    M: SessionMessage,
+{
+    // TODO: Max MSG size
+    input_stream_with_buffer(source, BytesMut::with_capacity(4096))
+}
+
+/// Input stream processing data already read from `source` first.
+pub(crate) fn input_stream_with_buffer<S, M>(source: S, buffer: BytesMut) -> InputStream<S, M>
+where
+    S: AsyncRead + Unpin,
+    M: SessionMessage,
 {
     InputStream {
-        // TODO: Max MSG size
-        buffer: BytesMut::with_capacity(4096),
+        buffer,
         source,
+        unknown_fields: UnknownFields::Reject,
+        phantom: PhantomData,
     }
 }
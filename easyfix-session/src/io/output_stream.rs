@@ -3,7 +3,8 @@ use std::rc::Rc;
 use async_stream::stream;
 use easyfix_messages::{
     fields::UtcTimestamp,
-    messages::{FixtMessage, BEGIN_STRING},
+    serializer::Serializer,
+    session::{SessionHeader, SessionMessage},
 };
 use futures_util::Stream;
 use tokio::{
@@ -13,37 +14,39 @@ use tokio::{
 use tokio_stream::StreamExt;
 use tracing::{debug, instrument};
 
-use super::time::timeout_stream;
-use crate::{messages_storage::MessagesStorage, session::Session, DisconnectReason, SenderMsg};
+use super::{throttle::Throttle, time::timeout_stream};
+use crate::{
+    application::ResponderMsg, messages_storage::MessagesStorage, session::Session, Delivery,
+    DisconnectReason, QueueToken, SenderMsg,
+};
 
 pub(crate) enum OutputEvent {
-    Message(Vec<u8>),
+    Message(Vec<u8>, Delivery, QueueToken),
     Timeout,
     Disconnect(DisconnectReason),
 }
 
-fn fill_header<S: MessagesStorage>(message: &mut FixtMessage, session: &Session<S>) {
+fn fill_header<S: MessagesStorage, M: SessionMessage>(message: &mut M, session: &Session<S, M>) {
     let mut state = session.state().borrow_mut();
 
-    let header = &mut message.header;
-    if header.begin_string.is_empty() {
-        header.begin_string = BEGIN_STRING.to_owned();
-    }
-
-    header.msg_type = message.body.msg_type();
+    message.update_msg_type();
 
-    if header.sender_comp_id.is_empty() {
-        header.sender_comp_id = session.session_id().sender_comp_id().to_owned();
+    let header = message.header_mut();
+    if header.begin_string().is_empty() {
+        header.set_begin_string(session.session_id().begin_string().to_owned());
+    }
+    if header.sender_comp_id().is_empty() {
+        header.set_sender_comp_id(session.session_id().sender_comp_id().to_owned());
     }
-    if header.target_comp_id.is_empty() {
-        header.target_comp_id = session.session_id().target_comp_id().to_owned();
+    if header.target_comp_id().is_empty() {
+        header.set_target_comp_id(session.session_id().target_comp_id().to_owned());
     }
-    if header.sending_time == UtcTimestamp::MIN_UTC {
-        header.sending_time = UtcTimestamp::now();
+    if header.sending_time() == UtcTimestamp::MIN_UTC {
+        header.set_sending_time(UtcTimestamp::now());
     }
 
-    if header.msg_seq_num == 0 {
-        header.msg_seq_num = state.next_sender_msg_seq_num();
+    if header.msg_seq_num() == 0 {
+        header.set_msg_seq_num(state.next_sender_msg_seq_num());
         state.incr_next_sender_msg_seq_num();
     }
 
@@ -55,18 +58,21 @@ fn fill_header<S: MessagesStorage>(message: &mut FixtMessage, session: &Session<
     level = "trace",
     skip_all,
     fields(
-        msg_seq_num = message.header.msg_seq_num,
+        msg_seq_num = message.header().msg_seq_num(),
         msg_type = ?message.msg_type()
     )
 )]
-fn output_handler<S: MessagesStorage>(message: &FixtMessage, session: &Session<S>) -> Vec<u8> {
+fn output_handler<S: MessagesStorage, M: SessionMessage>(
+    message: &M,
+    session: &Session<S, M>,
+) -> Vec<u8> {
     // TODO: fn serialize_to(&mut buf) / fn serialize_to_buf(&mut buf)
-    let buffer = message.serialize();
-    if !message.header.poss_dup_flag.unwrap_or(false) {
+    let buffer = message.serialize_with(Serializer::new());
+    if !message.header().poss_dup_flag().unwrap_or(false) {
         session
             .state()
             .borrow_mut()
-            .store(message.header.msg_seq_num, &buffer);
+            .store(message.header().msg_seq_num(), &buffer);
     }
 
     debug!(
@@ -76,19 +82,49 @@ fn output_handler<S: MessagesStorage>(message: &FixtMessage, session: &Session<S
     buffer
 }
 
-pub(crate) fn output_stream<S: MessagesStorage>(
-    session: Rc<Session<S>>,
+pub(crate) fn output_stream<S: MessagesStorage, M: SessionMessage>(
+    session: Rc<Session<S, M>>,
     timeout_duration: Duration,
-    mut receiver: UnboundedReceiver<SenderMsg>,
+    mut receiver: UnboundedReceiver<SenderMsg<M>>,
 ) -> impl Stream<Item = OutputEvent> {
+    let mut throttle = session.throttle_settings().map(Throttle::new);
     let stream = stream! {
         while let Some(sender_msg) = receiver.recv().await {
             match sender_msg {
-                SenderMsg::Msg(mut msg) => {
-                    fill_header(&mut msg, &session);
-                    if let Some(msg) = session.on_message_out(msg).await {
-                        yield OutputEvent::Message(output_handler(&msg, &session));
+                // Token is kept (with queue capacity) until message
+                // is written or dropped
+                SenderMsg::Msg(mut msg, token) => {
+                    // Throttle before MsgSeqNum<34> is assigned, so rejected
+                    // message doesn't make a gap
+                    if let Some(throttle) = throttle.as_mut() {
+                        let delay = throttle.delay(&*msg, Instant::now());
+                        if !delay.is_zero() {
+                            if throttle.rejects(&*msg) {
+                                session.on_message_throttle_rejected(msg).await;
+                                token.confirm(Delivery::ThrottleRejected);
+                                continue;
+                            }
+                            session.on_message_throttled(&msg, delay).await;
+                        }
+                        throttle.acquire(&*msg).await;
                     }
+                    fill_header(&mut *msg, &session);
+                    let seq_num = msg.header().msg_seq_num();
+                    let (msg, delivery) = match session.on_message_out(msg).await {
+                        Some(ResponderMsg::Send(msg)) => {
+                            let seq_num = msg.header().msg_seq_num();
+                            (msg, Delivery::Written(seq_num))
+                        }
+                        Some(ResponderMsg::GapFill(msg)) => {
+                            let seq_num = msg.header().msg_seq_num();
+                            (msg, Delivery::GapFilled(seq_num))
+                        }
+                        None => {
+                            token.confirm(Delivery::Discarded(seq_num));
+                            continue;
+                        }
+                    };
+                    yield OutputEvent::Message(output_handler(&*msg, &session), delivery, token);
                 }
                 SenderMsg::Disconnect(reason) => {
                     // Close stream, but don't break the loop now.
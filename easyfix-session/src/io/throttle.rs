@@ -0,0 +1,225 @@
+use std::collections::HashMap;
+
+use easyfix_messages::{
+    messages::{FixtMessage, MsgCat},
+    session::{SessionHeader, SessionMessage},
+};
+use tokio::time::{sleep, Duration, Instant};
+use tracing::warn;
+
+use crate::settings::{RateLimit, ThrottleAction, ThrottleSettings};
+
+#[derive(Debug)]
+struct TokenBucket {
+    // Tokens per second
+    rate: f64,
+    capacity: f64,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    fn new(rate_limit: &RateLimit, now: Instant) -> TokenBucket {
+        let rate = rate_limit.messages_per_second;
+        let capacity = rate_limit.burst.unwrap_or(rate);
+        TokenBucket {
+            rate: f64::from(rate.get()),
+            capacity: f64::from(capacity.get()),
+            tokens: f64::from(capacity.get()),
+            updated: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.capacity);
+        self.updated = now;
+    }
+
+    /// Time left until next token is available.
+    fn delay(&mut self, now: Instant) -> Duration {
+        self.refill(now);
+        if self.tokens >= 1.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64((1.0 - self.tokens) / self.rate)
+        }
+    }
+
+    fn take(&mut self) {
+        self.tokens -= 1.0;
+    }
+}
+
+/// Outbound messages throttle, built from `ThrottleSettings`.
+///
+/// Message is sent when there is a token in every bucket it belongs to,
+/// i.e. application/administrative one and bucket of its MsgType<35>.
+#[derive(Debug)]
+pub(crate) struct Throttle<M: SessionMessage = FixtMessage> {
+    app: Option<TokenBucket>,
+    admin: Option<TokenBucket>,
+    msg_types: HashMap<M::MsgType, TokenBucket>,
+    action: ThrottleAction,
+}
+
+impl<M: SessionMessage> Throttle<M> {
+    pub(crate) fn new(settings: &ThrottleSettings) -> Throttle<M> {
+        let now = Instant::now();
+        let mut msg_types = HashMap::new();
+        for (msg_type, rate_limit) in &settings.msg_types {
+            match M::msg_type_from_fix_str(msg_type) {
+                Some(msg_type) => {
+                    msg_types.insert(msg_type, TokenBucket::new(rate_limit, now));
+                }
+                None => warn!("Throttle for unknown MsgType<35> {msg_type} ignored"),
+            }
+        }
+        Throttle {
+            app: settings
+                .app
+                .as_ref()
+                .map(|limit| TokenBucket::new(limit, now)),
+            admin: settings
+                .admin
+                .as_ref()
+                .map(|limit| TokenBucket::new(limit, now)),
+            msg_types,
+            action: settings.action,
+        }
+    }
+
+    fn buckets(&mut self, msg: &M) -> impl Iterator<Item = &mut TokenBucket> {
+        let category = match msg.msg_cat() {
+            MsgCat::App => self.app.as_mut(),
+            MsgCat::Admin => self.admin.as_mut(),
+        };
+        category
+            .into_iter()
+            .chain(self.msg_types.get_mut(&msg.msg_type()))
+    }
+
+    /// Time left until message can be sent, zero when it can be sent now.
+    pub(crate) fn delay(&mut self, msg: &M, now: Instant) -> Duration {
+        self.buckets(msg)
+            .map(|bucket| bucket.delay(now))
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// Check if message exceeding the limit should be dropped.
+    ///
+    /// Administrative and resent messages are never rejected, as skipping
+    /// them would break the session.
+    pub(crate) fn rejects(&self, msg: &M) -> bool {
+        self.action == ThrottleAction::Reject
+            && matches!(msg.msg_cat(), MsgCat::App)
+            && !msg.header().poss_dup_flag().unwrap_or(false)
+    }
+
+    /// Wait until message can be sent and take its tokens.
+    pub(crate) async fn acquire(&mut self, msg: &M) {
+        loop {
+            let delay = self.delay(msg, Instant::now());
+            if delay.is_zero() {
+                break;
+            }
+            sleep(delay).await;
+        }
+        for bucket in self.buckets(msg) {
+            bucket.take();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroU32;
+
+    use easyfix_messages::{
+        fields::MsgType,
+        messages::{Heartbeat, Message},
+    };
+
+    use super::*;
+    use crate::{new_header, new_trailer};
+
+    fn heartbeat() -> FixtMessage {
+        FixtMessage {
+            header: Box::new(new_header(MsgType::Heartbeat)),
+            body: Box::new(Message::Heartbeat(Heartbeat {
+                test_req_id: None,
+                unknown_fields: Vec::new(),
+            })),
+            trailer: Box::new(new_trailer()),
+        }
+    }
+
+    #[test]
+    fn token_bucket_refills_with_rate() {
+        let now = Instant::now();
+        let mut bucket = TokenBucket::new(
+            &RateLimit {
+                messages_per_second: NonZeroU32::new(10).unwrap(),
+                burst: NonZeroU32::new(2),
+            },
+            now,
+        );
+        for _ in 0..2 {
+            assert_eq!(bucket.delay(now), Duration::ZERO);
+            bucket.take();
+        }
+        assert_eq!(bucket.delay(now), Duration::from_millis(100));
+        assert_eq!(
+            bucket.delay(now + Duration::from_millis(50)),
+            Duration::from_millis(50)
+        );
+        assert_eq!(
+            bucket.delay(now + Duration::from_millis(100)),
+            Duration::ZERO
+        );
+        // Never more than burst
+        bucket.refill(now + Duration::from_secs(10));
+        assert_eq!(bucket.tokens, 2.0);
+    }
+
+    #[test]
+    fn most_restrictive_limit_applies() {
+        let settings = ThrottleSettings {
+            admin: Some(RateLimit {
+                messages_per_second: NonZeroU32::new(100).unwrap(),
+                burst: None,
+            }),
+            msg_types: HashMap::from([(
+                MsgType::Heartbeat.as_fix_str().to_owned(),
+                RateLimit {
+                    messages_per_second: NonZeroU32::MIN,
+                    burst: None,
+                },
+            )]),
+            action: ThrottleAction::Reject,
+            ..Default::default()
+        };
+        let mut throttle = Throttle::new(&settings);
+        let msg = heartbeat();
+        let now = Instant::now();
+
+        assert_eq!(throttle.delay(&msg, now), Duration::ZERO);
+        for bucket in throttle.buckets(&msg) {
+            bucket.take();
+        }
+        assert!(throttle.delay(&msg, now) > Duration::from_millis(900));
+        assert!(!throttle.rejects(&msg));
+    }
+
+    #[test]
+    fn zero_rate_is_rejected() {
+        let rate_limit: RateLimit =
+            serde_json::from_str(r#"{"messages_per_second": 5, "burst": 10}"#).unwrap();
+        assert_eq!(rate_limit.messages_per_second.get(), 5);
+        assert!(serde_json::from_str::<RateLimit>(r#"{"messages_per_second": 0}"#).is_err());
+        assert!(
+            serde_json::from_str::<RateLimit>(r#"{"messages_per_second": 5, "burst": 0}"#).is_err()
+        );
+    }
+}
@@ -8,20 +8,35 @@ pub mod messages_storage;
 mod session;
 pub mod session_id;
 mod session_state;
+pub mod session_time;
 pub mod settings;
+pub mod threaded_acceptor;
+pub mod threaded_initiator;
+#[cfg(feature = "tls")]
+pub mod tls;
 
-use std::time::Duration;
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+    time::Duration,
+};
 
 use easyfix_messages::{
-    fields::{FixString, MsgType, UtcTimestamp},
-    messages::{FixtMessage, Header, Message, Trailer},
+    fields::{FixString, MsgType, SeqNum, UtcTimestamp},
+    messages::{FixtMessage, Header, Trailer},
+    session::SessionMessage,
 };
 use settings::Settings;
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, oneshot, Notify};
 
 const NO_INBOUND_TIMEOUT_PADDING: Duration = Duration::from_millis(250);
 
-use tracing::error;
+use tracing::{error, warn};
 
 #[derive(Debug, thiserror::Error)]
 pub enum SessionError {
@@ -54,27 +69,230 @@ pub enum DisconnectReason {
     MsgSeqNumTooLow,
     /// Invalid logon state
     InvalidLogonState,
+    /// Session period is over
+    SessionTimeEnded,
     /// Remote side disconnected
     Disconnected,
     /// I/O Error
     IoError,
 }
 
+/// Outbound messages queue, shared by all `Sender` clones of a session.
 #[derive(Debug)]
-pub(crate) enum SenderMsg {
-    Msg(Box<FixtMessage>),
+struct OutboundQueue {
+    len: AtomicUsize,
+    limit: Option<usize>,
+    // Wakes up `Sender::reserve` callers waiting for room in the queue.
+    released: Notify,
+}
+
+impl OutboundQueue {
+    /// Count message in queue length if it's below the limit.
+    fn try_reserve(&self) -> bool {
+        let Some(limit) = self.limit else {
+            self.len.fetch_add(1, Ordering::Relaxed);
+            return true;
+        };
+        self.len
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |len| {
+                (len < limit).then_some(len + 1)
+            })
+            .is_ok()
+    }
+
+    /// Count message in queue length even if the limit is reached.
+    fn reserve(&self) -> usize {
+        self.len.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    async fn reserve_async(&self) {
+        loop {
+            let released = self.released.notified();
+            tokio::pin!(released);
+            // Register before checking, so release in between is not missed
+            released.as_mut().enable();
+            if self.try_reserve() {
+                return;
+            }
+            released.await;
+        }
+    }
+}
+
+/// Keeps message counted in queue length until it's written or dropped
+/// by output stream.
+#[derive(Debug)]
+pub(crate) struct QueueToken {
+    queue: Arc<OutboundQueue>,
+    delivery: Option<oneshot::Sender<Delivery>>,
+}
+
+impl QueueToken {
+    /// Report message outcome to `DeliveryHandle`, if there is any.
+    pub(crate) fn confirm(mut self, delivery: Delivery) {
+        if let Some(sender) = self.delivery.take() {
+            // Handle may be already dropped, it's fine
+            let _ = sender.send(delivery);
+        }
+    }
+}
+
+impl Drop for QueueToken {
+    fn drop(&mut self) {
+        self.queue.len.fetch_sub(1, Ordering::Relaxed);
+        self.queue.released.notify_waiters();
+    }
+}
+
+#[derive(Debug)]
+pub(crate) enum SenderMsg<M = FixtMessage> {
+    Msg(Box<M>, QueueToken),
     Disconnect(DisconnectReason),
 }
 
-#[derive(Clone, Debug)]
-pub struct Sender {
-    inner: mpsc::UnboundedSender<SenderMsg>,
+/// Outcome of sent message, see [DeliveryHandle].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Delivery {
+    /// Message was written to the socket with given MsgSeqNum<34>.
+    Written(SeqNum),
+    /// Message was changed to SequenceReset-GapFill<4> with `Responder`,
+    /// gap fill was written with given MsgSeqNum<34>.
+    GapFilled(SeqNum),
+    /// Message with given MsgSeqNum<34> was discarded with `Responder`.
+    Discarded(SeqNum),
+    /// Message was rejected by outbound throttle, MsgSeqNum<34> was not
+    /// assigned.
+    ThrottleRejected,
+    /// Session disconnected before message was written. When MsgSeqNum<34>
+    /// was already assigned, message is stored and will be available
+    /// for ResendRequest<2>.
+    Disconnected(Option<SeqNum>),
+}
+
+impl Delivery {
+    /// MsgSeqNum<34> assigned to the message, if any.
+    pub fn seq_num(&self) -> Option<SeqNum> {
+        match self {
+            Delivery::Written(seq_num)
+            | Delivery::GapFilled(seq_num)
+            | Delivery::Discarded(seq_num) => Some(*seq_num),
+            Delivery::ThrottleRejected => None,
+            Delivery::Disconnected(seq_num) => *seq_num,
+        }
+    }
+}
+
+/// Future resolved with [Delivery] of sent message. It may be dropped
+/// when the outcome is not needed.
+#[derive(Debug)]
+pub struct DeliveryHandle {
+    receiver: oneshot::Receiver<Delivery>,
+}
+
+impl Future for DeliveryHandle {
+    type Output = Delivery;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Delivery> {
+        Pin::new(&mut self.receiver)
+            .poll(cx)
+            // Message dropped before MsgSeqNum<34> was assigned
+            .map(|result| result.unwrap_or(Delivery::Disconnected(None)))
+    }
+}
+
+/// Room in outbound queue for one message, reserved with
+/// [Sender::reserve] or [Sender::try_reserve]. It's released when permit
+/// is dropped without sending.
+#[derive(Debug)]
+pub struct Permit<'a, M: SessionMessage = FixtMessage> {
+    sender: &'a Sender<M>,
+    token: QueueToken,
 }
 
-impl Sender {
+impl<M: SessionMessage> Permit<'_, M> {
+    /// Send FIXT message in reserved room, see [Sender::send_raw].
+    pub fn send_raw(self, msg: Box<M>) -> Result<DeliveryHandle, Box<M>> {
+        self.sender.enqueue_confirmed(msg, self.token)
+    }
+
+    /// Send FIX message in reserved room, see [Sender::send].
+    pub fn send(self, msg: Box<M::Body>) -> Result<DeliveryHandle, Box<M>> {
+        self.send_raw(M::from_body(msg))
+    }
+}
+
+/// Sends messages to a session, `M` is the codec of the session
+/// (`FixtMessage` of a generated messages module).
+#[derive(Debug)]
+pub struct Sender<M: SessionMessage = FixtMessage> {
+    inner: mpsc::UnboundedSender<SenderMsg<M>>,
+    queue: Arc<OutboundQueue>,
+}
+
+impl<M: SessionMessage> Clone for Sender<M> {
+    fn clone(&self) -> Sender<M> {
+        Sender {
+            inner: self.inner.clone(),
+            queue: self.queue.clone(),
+        }
+    }
+}
+
+impl<M: SessionMessage> Sender<M> {
     /// Create new `Sender` instance.
-    pub(crate) fn new(writer: mpsc::UnboundedSender<SenderMsg>) -> Sender {
-        Sender { inner: writer }
+    ///
+    /// `queue_limit` limits number of messages queued with [Permit]
+    /// of [Sender::reserve] and [Sender::try_reserve].
+    pub(crate) fn new(
+        writer: mpsc::UnboundedSender<SenderMsg<M>>,
+        queue_limit: Option<usize>,
+    ) -> Sender<M> {
+        Sender {
+            inner: writer,
+            queue: Arc::new(OutboundQueue {
+                len: AtomicUsize::new(0),
+                limit: queue_limit,
+                released: Notify::new(),
+            }),
+        }
+    }
+
+    /// Token of message already counted in queue length.
+    fn token(&self) -> QueueToken {
+        QueueToken {
+            queue: self.queue.clone(),
+            delivery: None,
+        }
+    }
+
+    /// Put message into the queue, `token` keeps it counted in queue length.
+    fn enqueue(&self, msg: Box<M>, token: QueueToken) -> Result<(), Box<M>> {
+        if let Err(msg) = self.inner.send(SenderMsg::Msg(msg, token)) {
+            match msg.0 {
+                SenderMsg::Msg(msg, _) => {
+                    error!(
+                        "failed to send {:?}<{}> message, receiver closed or dropped",
+                        msg.msg_type(),
+                        M::msg_type_as_fix_str(msg.msg_type())
+                    );
+                    Err(msg)
+                }
+                SenderMsg::Disconnect(_) => unreachable!(),
+            }
+        } else {
+            Ok(())
+        }
+    }
+
+    fn enqueue_confirmed(
+        &self,
+        msg: Box<M>,
+        mut token: QueueToken,
+    ) -> Result<DeliveryHandle, Box<M>> {
+        let (sender, receiver) = oneshot::channel();
+        token.delivery = Some(sender);
+        self.enqueue(msg, token)?;
+        Ok(DeliveryHandle { receiver })
     }
 
     /// Send FIXT message.
@@ -92,38 +310,78 @@ impl Sender {
     ///
     /// The checksum(10) field value is always ignored - it is computed and set
     /// after serialziation.
-    pub fn send_raw(&self, msg: Box<FixtMessage>) -> Result<(), Box<FixtMessage>> {
-        if let Err(msg) = self.inner.send(SenderMsg::Msg(msg)) {
-            match msg.0 {
-                SenderMsg::Msg(msg) => {
-                    error!(
-                        "failed to send {:?}<{}> message, receiver closed or dropped",
-                        msg.msg_type(),
-                        msg.msg_type().as_fix_str()
-                    );
-                    Err(msg)
-                }
-                SenderMsg::Disconnect(_) => unreachable!(),
-            }
-        } else {
-            Ok(())
+    ///
+    /// Returned handle is resolved with assigned MsgSeqNum<34> and message
+    /// outcome, it may be dropped when they are not needed.
+    ///
+    /// Message is queued even when `SessionSettings::outbound_queue_limit`
+    /// is reached (warning is logged then), send it with [Permit] of
+    /// [Sender::reserve] or [Sender::try_reserve] to respect the limit.
+    /// It's counted in queue length, so limited sends wait until it's
+    /// written.
+    pub fn send_raw(&self, msg: Box<M>) -> Result<DeliveryHandle, Box<M>> {
+        let len = self.queue.reserve();
+        if let Some(limit) = self.queue.limit.filter(|limit| len > *limit) {
+            warn!(
+                "outbound queue limit ({limit}) exceeded by {:?}<{}> message, {len} messages queued",
+                msg.msg_type(),
+                M::msg_type_as_fix_str(msg.msg_type())
+            );
         }
+        self.enqueue_confirmed(msg, self.token())
+    }
+
+    /// Send message generated by the session itself, it's counted
+    /// in queue length, but never limited.
+    pub(crate) fn send_session_msg(&self, msg: Box<M>) -> Result<(), Box<M>> {
+        self.queue.reserve();
+        self.enqueue(msg, self.token())
     }
 
     /// Send FIX message.
     ///
     /// FIXT message will be constructed internally using default values
-    /// for Header and Trailer.
+    /// for Header and Trailer, see [Sender::send_raw].
     ///
     /// All header and trailer fields can be also adjusted when handing
     /// `FixEvent::AppMsgOut` and `FixEvent::AdmMsgOut`.
-    pub fn send(&self, msg: Box<Message>) -> Result<(), Box<FixtMessage>> {
-        let msg = Box::new(FixtMessage {
-            header: Box::new(new_header(msg.msg_type())),
-            body: msg,
-            trailer: Box::new(new_trailer()),
-        });
-        self.send_raw(msg)
+    pub fn send(&self, msg: Box<M::Body>) -> Result<DeliveryHandle, Box<M>> {
+        self.send_raw(M::from_body(msg))
+    }
+
+    /// Wait until there is room in outbound queue and reserve it for
+    /// one message.
+    pub async fn reserve(&self) -> Permit<'_, M> {
+        self.queue.reserve_async().await;
+        Permit {
+            sender: self,
+            token: self.token(),
+        }
+    }
+
+    /// Reserve room in outbound queue for one message, `None` when
+    /// the queue is full.
+    pub fn try_reserve(&self) -> Option<Permit<'_, M>> {
+        self.queue.try_reserve().then(|| Permit {
+            sender: self,
+            token: self.token(),
+        })
+    }
+
+    /// Number of messages waiting in outbound queue, including messages
+    /// sent by the session itself and with [Sender::send_raw], which
+    /// are not limited by `SessionSettings::outbound_queue_limit`.
+    pub fn queue_len(&self) -> usize {
+        self.queue.len.load(Ordering::Relaxed)
+    }
+
+    pub fn queue_limit(&self) -> Option<usize> {
+        self.queue.limit
+    }
+
+    /// Whether both senders send to the same session connection.
+    pub(crate) fn same_channel(&self, other: &Sender<M>) -> bool {
+        self.inner.same_channel(&other.inner)
     }
 
     /// Send disconnect message.
@@ -151,3 +409,87 @@ pub fn new_trailer() -> Trailer {
     // XXX: all required fields overwritten before serialization
     Trailer::default()
 }
+
+#[cfg(test)]
+mod tests {
+    use easyfix_messages::messages::{Heartbeat, Message};
+
+    use super::*;
+
+    fn heartbeat() -> Box<Message> {
+        Box::new(Message::Heartbeat(Heartbeat {
+            test_req_id: None,
+            unknown_fields: Vec::new(),
+        }))
+    }
+
+    #[tokio::test]
+    async fn try_reserve_respects_queue_limit() {
+        let (writer, mut receiver) = mpsc::unbounded_channel();
+        let sender: Sender = Sender::new(writer, Some(1));
+
+        let permit = sender.try_reserve().unwrap();
+        assert!(sender.try_reserve().is_none());
+        // Dropped permit releases the room
+        drop(permit);
+        sender.try_reserve().unwrap().send(heartbeat()).unwrap();
+        assert!(sender.try_reserve().is_none());
+        // Not limited, but still counted
+        sender.send(heartbeat()).unwrap();
+        assert_eq!(sender.queue_len(), 2);
+
+        // Message over the limit takes room of limited ones
+        drop(receiver.recv().await);
+        assert_eq!(sender.queue_len(), 1);
+        assert!(sender.try_reserve().is_none());
+        drop(receiver.recv().await);
+        assert_eq!(sender.queue_len(), 0);
+        sender.reserve().await.send(heartbeat()).unwrap();
+        assert_eq!(sender.queue_len(), 1);
+
+        drop(receiver);
+        assert!(sender.try_reserve().unwrap().send(heartbeat()).is_err());
+        assert_eq!(sender.queue_len(), 0);
+    }
+
+    #[tokio::test]
+    async fn reserve_waits_for_room_in_queue() {
+        let (writer, mut receiver) = mpsc::unbounded_channel();
+        let sender: Sender = Sender::new(writer, Some(1));
+
+        sender.send(heartbeat()).unwrap();
+        sender.send(heartbeat()).unwrap();
+        let reserve = sender.reserve();
+        tokio::pin!(reserve);
+        assert!(futures::poll!(reserve.as_mut()).is_pending());
+
+        drop(receiver.recv().await);
+        assert!(futures::poll!(reserve.as_mut()).is_pending());
+        drop(receiver.recv().await);
+        let handle = reserve.await.send(heartbeat()).unwrap();
+        assert_eq!(sender.queue_len(), 1);
+
+        let Some(SenderMsg::Msg(_, token)) = receiver.recv().await else {
+            panic!("message expected");
+        };
+        token.confirm(Delivery::Written(3));
+        assert_eq!(handle.await, Delivery::Written(3));
+    }
+
+    #[tokio::test]
+    async fn delivery_handle_resolves_with_outcome() {
+        let (writer, mut receiver) = mpsc::unbounded_channel();
+        let sender: Sender = Sender::new(writer, None);
+
+        let handle = sender.send(heartbeat()).unwrap();
+        let Some(SenderMsg::Msg(_, token)) = receiver.recv().await else {
+            panic!("message expected");
+        };
+        token.confirm(Delivery::Written(7));
+        assert_eq!(handle.await, Delivery::Written(7));
+
+        let handle = sender.send(heartbeat()).unwrap();
+        drop(receiver);
+        assert_eq!(handle.await, Delivery::Disconnected(None));
+    }
+}
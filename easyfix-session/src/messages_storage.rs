@@ -1,11 +1,48 @@
-use std::{collections::BTreeMap, ops::RangeInclusive};
+use std::{cell::RefCell, collections::BTreeMap, iter, ops::RangeInclusive, rc::Rc};
 
+use chrono::{DateTime, Utc};
 use easyfix_messages::fields::SeqNum;
+use futures::{
+    future::{self, FutureExt, LocalBoxFuture},
+    stream::{self, LocalBoxStream, StreamExt},
+};
+
+mod async_storage;
+mod file_storage;
+pub use async_storage::{AsyncMessagesStorage, AsyncStorage};
+pub use file_storage::{FileStorage, FsyncPolicy};
 
 pub trait MessagesStorage {
-    fn fetch_range(&mut self, range: RangeInclusive<SeqNum>) -> Vec<Vec<u8>>;
+    /// Fetch stored messages from given range.
+    ///
+    /// Messages should be loaded lazily, while the stream is polled,
+    /// so big ranges don't have to be kept in memory at once.
+    fn fetch_range(&mut self, range: RangeInclusive<SeqNum>) -> LocalBoxStream<'static, Vec<u8>>;
     fn store(&mut self, seq_num: SeqNum, data: &[u8]);
 
+    /// Store message received from counterparty.
+    ///
+    /// Called only when inbound messages persistence is enabled
+    /// (see `SessionSettings::persist_inbound`), does nothing by default.
+    fn store_inbound(&mut self, _seq_num: SeqNum, _data: &[u8]) {}
+
+    /// Time of session creation (or last reset), used to decide
+    /// if session has to be reset after restart.
+    ///
+    /// `None` (default) when storage doesn't track it, session then uses
+    /// time when the storage was created (or reset) in this process.
+    fn creation_time(&self) -> Option<DateTime<Utc>> {
+        None
+    }
+
+    /// Make sure all stored data is persisted, i.e. before shutdown.
+    ///
+    /// Returned future completes when data is flushed, by default
+    /// it completes immediately.
+    fn flush(&mut self) -> LocalBoxFuture<'static, ()> {
+        future::ready(()).boxed_local()
+    }
+
     fn next_sender_msg_seq_num(&self) -> SeqNum;
     fn next_target_msg_seq_num(&self) -> SeqNum;
 
@@ -39,9 +76,8 @@ impl Default for NullStorage {
 }
 
 impl MessagesStorage for NullStorage {
-    // TODO: Iterator or Stream! Returned Vec can be extremly big (and slow to create)
-    fn fetch_range(&mut self, _range: RangeInclusive<SeqNum>) -> Vec<Vec<u8>> {
-        Vec::new()
+    fn fetch_range(&mut self, _range: RangeInclusive<SeqNum>) -> LocalBoxStream<'static, Vec<u8>> {
+        stream::empty().boxed_local()
     }
 
     fn store(&mut self, _seq_num: SeqNum, _data: &[u8]) {}
@@ -79,7 +115,8 @@ impl MessagesStorage for NullStorage {
 pub struct InMemoryStorage {
     next_sender_msg_seq_num: SeqNum,
     next_target_msg_seq_num: SeqNum,
-    mem: BTreeMap<SeqNum, Vec<u8>>,
+    // Shared with streams returned by `fetch_range`.
+    mem: Rc<RefCell<BTreeMap<SeqNum, Vec<u8>>>>,
 }
 
 impl InMemoryStorage {
@@ -87,7 +124,7 @@ impl InMemoryStorage {
         InMemoryStorage {
             next_sender_msg_seq_num: 1,
             next_target_msg_seq_num: 1,
-            mem: BTreeMap::new(),
+            mem: Rc::new(RefCell::new(BTreeMap::new())),
         }
     }
 }
@@ -99,13 +136,23 @@ impl Default for InMemoryStorage {
 }
 
 impl MessagesStorage for InMemoryStorage {
-    // TODO: Stream!
-    fn fetch_range(&mut self, _range: RangeInclusive<SeqNum>) -> Vec<Vec<u8>> {
-        Vec::new()
+    fn fetch_range(&mut self, range: RangeInclusive<SeqNum>) -> LocalBoxStream<'static, Vec<u8>> {
+        let mem = self.mem.clone();
+        let (mut next, end) = range.into_inner();
+        stream::iter(iter::from_fn(move || {
+            if next > end {
+                return None;
+            }
+            let mem = mem.borrow();
+            let (seq_num, msg) = mem.range(next..=end).next()?;
+            next = seq_num + 1;
+            Some(msg.clone())
+        }))
+        .boxed_local()
     }
 
     fn store(&mut self, seq_num: SeqNum, data: &[u8]) {
-        self.mem.insert(seq_num, data.to_vec());
+        self.mem.borrow_mut().insert(seq_num, data.to_vec());
     }
 
     fn next_sender_msg_seq_num(&self) -> SeqNum {
@@ -135,6 +182,38 @@ impl MessagesStorage for InMemoryStorage {
     fn reset(&mut self) {
         self.next_sender_msg_seq_num = 1;
         self.next_target_msg_seq_num = 1;
-        self.mem.clear();
+        self.mem.borrow_mut().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::executor::block_on;
+
+    use super::*;
+
+    #[test]
+    fn in_memory_fetch_range() {
+        let mut storage = InMemoryStorage::new();
+        for seq_num in [1, 2, 3, 5] {
+            storage.store(seq_num, format!("msg{seq_num}").as_bytes());
+        }
+
+        let messages = storage.fetch_range(2..=5);
+        // Messages stored after stream creation are visible too
+        storage.store(4, b"msg4");
+        let messages: Vec<_> = block_on(messages.collect());
+        assert_eq!(
+            messages,
+            vec![
+                b"msg2".to_vec(),
+                b"msg3".to_vec(),
+                b"msg4".to_vec(),
+                b"msg5".to_vec()
+            ]
+        );
+
+        let messages: Vec<_> = block_on(storage.fetch_range(6..=10).collect());
+        assert!(messages.is_empty());
     }
 }
@@ -0,0 +1,304 @@
+use std::{future::Future, marker::PhantomData, ops::RangeInclusive};
+
+use chrono::{DateTime, Utc};
+use easyfix_messages::fields::SeqNum;
+use futures::{
+    future::{self, FutureExt, LocalBoxFuture},
+    stream::{self, LocalBoxStream, StreamExt},
+};
+use tokio::sync::{mpsc, oneshot};
+
+use super::MessagesStorage;
+
+/// Messages storage with asynchronous API, i.e. for database backed stores.
+///
+/// Use [AsyncStorage] to plug it into session.
+pub trait AsyncMessagesStorage: 'static {
+    /// Load next sender and target sequence numbers.
+    fn seq_nums(&mut self) -> impl Future<Output = (SeqNum, SeqNum)>;
+
+    /// Store next sender and target sequence numbers.
+    fn store_seq_nums(
+        &mut self,
+        next_sender_msg_seq_num: SeqNum,
+        next_target_msg_seq_num: SeqNum,
+    ) -> impl Future<Output = ()>;
+
+    /// Fetch stored messages from given range.
+    fn fetch_range(
+        &mut self,
+        range: RangeInclusive<SeqNum>,
+    ) -> impl Future<Output = LocalBoxStream<'static, Vec<u8>>>;
+
+    fn store(&mut self, seq_num: SeqNum, data: Vec<u8>) -> impl Future<Output = ()>;
+
+    /// Store message received from counterparty, does nothing by default.
+    fn store_inbound(&mut self, _seq_num: SeqNum, _data: Vec<u8>) -> impl Future<Output = ()> {
+        future::ready(())
+    }
+
+    /// Load session creation time, `None` (default) when it's not tracked,
+    /// see [MessagesStorage::creation_time].
+    fn creation_time(&mut self) -> impl Future<Output = Option<DateTime<Utc>>> {
+        future::ready(None)
+    }
+
+    /// Make sure all stored data is persisted, does nothing by default.
+    fn flush(&mut self) -> impl Future<Output = ()> {
+        future::ready(())
+    }
+
+    /// Remove all stored messages and reset sequence numbers to 1.
+    fn reset(&mut self) -> impl Future<Output = ()>;
+}
+
+enum Op {
+    SeqNums(SeqNum, SeqNum),
+    Store(SeqNum, Vec<u8>),
+    StoreInbound(SeqNum, Vec<u8>),
+    Fetch(
+        RangeInclusive<SeqNum>,
+        oneshot::Sender<LocalBoxStream<'static, Vec<u8>>>,
+    ),
+    Flush(oneshot::Sender<()>),
+    Reset,
+}
+
+/// Adapter between [AsyncMessagesStorage] and [MessagesStorage].
+///
+/// Sequence numbers are cached in memory, all operations are queued
+/// and executed, in order, by a background task, so session never waits
+/// for the storage. Background task is spawned with
+/// `tokio::task::spawn_local` when the adapter is created, so it has to
+/// be created inside `tokio::task::LocalSet` (like the rest of the
+/// session), and it finishes after the adapter is dropped and all queued
+/// operations are done.
+pub struct AsyncStorage<A> {
+    next_sender_msg_seq_num: SeqNum,
+    next_target_msg_seq_num: SeqNum,
+    creation_time: Option<DateTime<Utc>>,
+    ops_tx: mpsc::UnboundedSender<Op>,
+    // Storage itself is owned by background task.
+    _storage: PhantomData<A>,
+}
+
+impl<A: AsyncMessagesStorage> AsyncStorage<A> {
+    /// Create adapter with sequence numbers and creation time loaded
+    /// from `storage`.
+    ///
+    /// Panics when called outside of `tokio::task::LocalSet`.
+    pub async fn new(mut storage: A) -> AsyncStorage<A> {
+        let (next_sender_msg_seq_num, next_target_msg_seq_num) = storage.seq_nums().await;
+        let creation_time = storage.creation_time().await;
+        AsyncStorage::with_state(
+            storage,
+            next_sender_msg_seq_num,
+            next_target_msg_seq_num,
+            creation_time,
+        )
+    }
+
+    /// Create adapter with already known sequence numbers and creation
+    /// time, i.e. loaded up front for all sessions. Unlike [Self::new]
+    /// it can be used in storage builder passed to `Acceptor::new`.
+    ///
+    /// Panics when called outside of `tokio::task::LocalSet`.
+    pub fn with_state(
+        storage: A,
+        next_sender_msg_seq_num: SeqNum,
+        next_target_msg_seq_num: SeqNum,
+        creation_time: Option<DateTime<Utc>>,
+    ) -> AsyncStorage<A> {
+        let (ops_tx, ops_rx) = mpsc::unbounded_channel();
+        tokio::task::spawn_local(Self::run(storage, ops_rx));
+        AsyncStorage {
+            next_sender_msg_seq_num,
+            next_target_msg_seq_num,
+            creation_time,
+            ops_tx,
+            _storage: PhantomData,
+        }
+    }
+
+    async fn run(mut storage: A, mut ops_rx: mpsc::UnboundedReceiver<Op>) {
+        while let Some(op) = ops_rx.recv().await {
+            match op {
+                Op::SeqNums(next_sender_msg_seq_num, next_target_msg_seq_num) => {
+                    storage
+                        .store_seq_nums(next_sender_msg_seq_num, next_target_msg_seq_num)
+                        .await
+                }
+                Op::Store(seq_num, data) => storage.store(seq_num, data).await,
+                Op::StoreInbound(seq_num, data) => storage.store_inbound(seq_num, data).await,
+                Op::Fetch(range, stream_tx) => {
+                    // Receiver dropped means nobody waits for messages anymore
+                    let _ = stream_tx.send(storage.fetch_range(range).await);
+                }
+                Op::Flush(done_tx) => {
+                    storage.flush().await;
+                    let _ = done_tx.send(());
+                }
+                Op::Reset => storage.reset().await,
+            }
+        }
+    }
+
+    fn push(&mut self, op: Op) {
+        // Worker exits only when sender is dropped, so it can't fail
+        let _ = self.ops_tx.send(op);
+    }
+
+    fn push_seq_nums(&mut self) {
+        self.push(Op::SeqNums(
+            self.next_sender_msg_seq_num,
+            self.next_target_msg_seq_num,
+        ));
+    }
+}
+
+impl<A: AsyncMessagesStorage> MessagesStorage for AsyncStorage<A> {
+    fn fetch_range(&mut self, range: RangeInclusive<SeqNum>) -> LocalBoxStream<'static, Vec<u8>> {
+        let (stream_tx, stream_rx) = oneshot::channel();
+        self.push(Op::Fetch(range, stream_tx));
+        stream_rx
+            .map(|result| result.unwrap_or_else(|_| stream::empty().boxed_local()))
+            .flatten_stream()
+            .boxed_local()
+    }
+
+    fn store(&mut self, seq_num: SeqNum, data: &[u8]) {
+        self.push(Op::Store(seq_num, data.to_vec()));
+    }
+
+    fn store_inbound(&mut self, seq_num: SeqNum, data: &[u8]) {
+        self.push(Op::StoreInbound(seq_num, data.to_vec()));
+    }
+
+    fn creation_time(&self) -> Option<DateTime<Utc>> {
+        self.creation_time
+    }
+
+    /// Completes when all operations queued before are done.
+    fn flush(&mut self) -> LocalBoxFuture<'static, ()> {
+        let (done_tx, done_rx) = oneshot::channel();
+        self.push(Op::Flush(done_tx));
+        done_rx.map(|_| ()).boxed_local()
+    }
+
+    fn next_sender_msg_seq_num(&self) -> SeqNum {
+        self.next_sender_msg_seq_num
+    }
+
+    fn next_target_msg_seq_num(&self) -> SeqNum {
+        self.next_target_msg_seq_num
+    }
+
+    fn set_next_sender_msg_seq_num(&mut self, seq_num: SeqNum) {
+        self.next_sender_msg_seq_num = seq_num;
+        self.push_seq_nums();
+    }
+
+    fn set_next_target_msg_seq_num(&mut self, seq_num: SeqNum) {
+        self.next_target_msg_seq_num = seq_num;
+        self.push_seq_nums();
+    }
+
+    fn incr_next_sender_msg_seq_num(&mut self) {
+        self.next_sender_msg_seq_num += 1;
+        self.push_seq_nums();
+    }
+
+    fn incr_next_target_msg_seq_num(&mut self) {
+        self.next_target_msg_seq_num += 1;
+        self.push_seq_nums();
+    }
+
+    fn reset(&mut self) {
+        self.next_sender_msg_seq_num = 1;
+        self.next_target_msg_seq_num = 1;
+        if self.creation_time.is_some() {
+            self.creation_time = Some(Utc::now());
+        }
+        self.push(Op::Reset);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use super::*;
+    use crate::messages_storage::InMemoryStorage;
+
+    // Shared, so test can inspect backend state
+    #[derive(Clone, Default)]
+    struct SlowStorage(Rc<RefCell<InMemoryStorage>>);
+
+    impl AsyncMessagesStorage for SlowStorage {
+        async fn seq_nums(&mut self) -> (SeqNum, SeqNum) {
+            let storage = self.0.borrow();
+            (
+                storage.next_sender_msg_seq_num(),
+                storage.next_target_msg_seq_num(),
+            )
+        }
+
+        async fn store_seq_nums(
+            &mut self,
+            next_sender_msg_seq_num: SeqNum,
+            next_target_msg_seq_num: SeqNum,
+        ) {
+            tokio::task::yield_now().await;
+            let mut storage = self.0.borrow_mut();
+            storage.set_next_sender_msg_seq_num(next_sender_msg_seq_num);
+            storage.set_next_target_msg_seq_num(next_target_msg_seq_num);
+        }
+
+        async fn fetch_range(
+            &mut self,
+            range: RangeInclusive<SeqNum>,
+        ) -> LocalBoxStream<'static, Vec<u8>> {
+            tokio::task::yield_now().await;
+            self.0.borrow_mut().fetch_range(range)
+        }
+
+        async fn store(&mut self, seq_num: SeqNum, data: Vec<u8>) {
+            tokio::task::yield_now().await;
+            self.0.borrow_mut().store(seq_num, &data);
+        }
+
+        async fn reset(&mut self) {
+            tokio::task::yield_now().await;
+            self.0.borrow_mut().reset();
+        }
+    }
+
+    #[tokio::test]
+    async fn operations_are_executed_in_order() {
+        tokio::task::LocalSet::new()
+            .run_until(async {
+                let backend = SlowStorage::default();
+                backend.0.borrow_mut().set_next_target_msg_seq_num(5);
+
+                let mut storage = AsyncStorage::new(backend.clone()).await;
+                assert_eq!(storage.next_target_msg_seq_num(), 5);
+
+                storage.store(1, b"first");
+                storage.incr_next_sender_msg_seq_num();
+                storage.store(2, b"second");
+                storage.incr_next_sender_msg_seq_num();
+                assert_eq!(storage.next_sender_msg_seq_num(), 3);
+                storage.flush().await;
+                assert_eq!(backend.0.borrow().next_sender_msg_seq_num(), 3);
+
+                let messages: Vec<_> = storage.fetch_range(1..=2).collect().await;
+                assert_eq!(messages, vec![b"first".to_vec(), b"second".to_vec()]);
+
+                storage.reset();
+                let messages: Vec<_> = storage.fetch_range(1..=2).collect().await;
+                assert!(messages.is_empty());
+                assert_eq!(backend.0.borrow().next_sender_msg_seq_num(), 1);
+            })
+            .await;
+    }
+}
@@ -0,0 +1,583 @@
+use std::{
+    collections::BTreeMap,
+    fs::{self, File, OpenOptions},
+    io::{self, Read, Seek, SeekFrom, Write},
+    iter,
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Utc};
+use easyfix_messages::fields::SeqNum;
+use futures::{
+    future::{self, FutureExt, LocalBoxFuture},
+    stream::{self, LocalBoxStream, StreamExt},
+};
+use serde::Deserialize;
+use tracing::error;
+
+use super::MessagesStorage;
+use crate::session_id::SessionId;
+
+/// Width of a single sequence number in `.seqnums` file.
+const SEQ_NUM_WIDTH: usize = 10;
+
+/// Defines when written data is synchronized with the storage device.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+pub enum FsyncPolicy {
+    /// Leave synchronization to the operating system.
+    #[default]
+    Never,
+    /// Synchronize sequence numbers after every update, messages are
+    /// synchronized by the operating system.
+    SeqNums,
+    /// Synchronize messages and sequence numbers after every write.
+    Always,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Location {
+    offset: u64,
+    size: usize,
+}
+
+/// QuickFIX compatible, file based messages storage.
+///
+/// Every session uses following files (prefixed with the session name):
+/// - `.body` - outgoing messages appended one after another,
+/// - `.header` - index of `.body` file, `seq_num,offset,size` entries,
+/// - `.seqnums` - next sender and target sequence numbers,
+/// - `.session` - session creation time,
+/// - `.inbound` and `.inbound.header` - incoming messages and their index,
+///   like `.body` and `.header` (only when inbound messages persistence
+///   is enabled).
+///
+/// On startup all files are loaded, so session can continue with sequence
+/// numbers from before the restart and stored messages remain available
+/// for `ResendRequest<2>`.
+#[derive(Debug)]
+pub struct FileStorage {
+    fsync_policy: FsyncPolicy,
+    next_sender_msg_seq_num: SeqNum,
+    next_target_msg_seq_num: SeqNum,
+    index: BTreeMap<SeqNum, Location>,
+    body_path: PathBuf,
+    body_file: File,
+    body_len: u64,
+    header_file: File,
+    seq_nums_file: File,
+    session_path: PathBuf,
+    inbound_path: PathBuf,
+    inbound_header_path: PathBuf,
+    inbound_files: Option<(File, File)>,
+    inbound_len: u64,
+    inbound_index: BTreeMap<SeqNum, Location>,
+    creation_time: DateTime<Utc>,
+}
+
+fn file_prefix(session_id: &SessionId) -> String {
+    let mut prefix = format!(
+        "{}-{}-{}",
+        session_id.begin_string(),
+        session_id.sender_comp_id(),
+        session_id.target_comp_id()
+    );
+    if !session_id.session_qualifier().is_empty() {
+        prefix.push('-');
+        prefix.push_str(session_id.session_qualifier());
+    }
+    prefix
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+fn open_file(path: &Path) -> Result<File, io::Error> {
+    OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)
+}
+
+fn parse_seq_nums(input: &str) -> Option<(SeqNum, SeqNum)> {
+    let (sender, target) = input.split_once(':')?;
+    Some((sender.trim().parse().ok()?, target.trim().parse().ok()?))
+}
+
+/// Parse `.header` file content.
+///
+/// Entries pointing outside of `.body` file (i.e. when process crashed
+/// in the middle of write) are skipped.
+fn parse_index(input: &str, body_len: u64) -> BTreeMap<SeqNum, Location> {
+    let mut index = BTreeMap::new();
+    for entry in input.split_ascii_whitespace() {
+        let mut parts = entry.split(',');
+        let (Some(seq_num), Some(offset), Some(size), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            continue;
+        };
+        let (Ok(seq_num), Ok(offset), Ok(size)) = (
+            seq_num.parse::<SeqNum>(),
+            offset.parse::<u64>(),
+            size.parse::<usize>(),
+        ) else {
+            continue;
+        };
+        if offset
+            .checked_add(size as u64)
+            .is_some_and(|end| end <= body_len)
+        {
+            index.insert(seq_num, Location { offset, size });
+        }
+    }
+    index
+}
+
+/// Read `path` file content, missing file is empty.
+fn read_optional(path: &Path) -> Result<String, io::Error> {
+    match fs::read_to_string(path) {
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(String::new()),
+        result => result,
+    }
+}
+
+fn read_message(file: &mut File, location: Location) -> Result<Vec<u8>, io::Error> {
+    let mut buffer = vec![0; location.size];
+    file.seek(SeekFrom::Start(location.offset))?;
+    file.read_exact(&mut buffer)?;
+    Ok(buffer)
+}
+
+/// Stream messages of `index` entries in `range` from `path` file.
+fn fetch_messages(
+    path: &Path,
+    index: &BTreeMap<SeqNum, Location>,
+    range: RangeInclusive<SeqNum>,
+) -> LocalBoxStream<'static, Vec<u8>> {
+    // `BTreeMap::range` panics on inverted range, which counterparty
+    // can request with ResendRequest<2>.
+    if range.start() > range.end() {
+        return stream::empty().boxed_local();
+    }
+    // Separate handle, so reads don't interfere with writes position.
+    let mut file = match File::open(path) {
+        Ok(file) => file,
+        Err(err) => {
+            error!("failed to open {}: {err}", path.display());
+            return stream::empty().boxed_local();
+        }
+    };
+    let path = path.to_owned();
+    let mut locations = index
+        .range(range)
+        .map(|(_, location)| *location)
+        .collect::<Vec<_>>()
+        .into_iter();
+    stream::iter(iter::from_fn(move || {
+        let location = locations.next()?;
+        match read_message(&mut file, location) {
+            Ok(msg) => Some(msg),
+            Err(err) => {
+                error!("failed to read message from {}: {err}", path.display());
+                None
+            }
+        }
+    }))
+    .boxed_local()
+}
+
+impl FileStorage {
+    /// Open (or create) storage files for given session in `dir` directory.
+    pub fn new(
+        dir: impl AsRef<Path>,
+        session_id: &SessionId,
+        fsync_policy: FsyncPolicy,
+    ) -> Result<FileStorage, io::Error> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir)?;
+        let prefix = file_prefix(session_id);
+
+        let body_path = dir.join(format!("{prefix}.body"));
+        let body_file = open_file(&body_path)?;
+        let body_len = body_file.metadata()?.len();
+
+        let mut header_file = open_file(&dir.join(format!("{prefix}.header")))?;
+        let mut header = String::new();
+        header_file.read_to_string(&mut header)?;
+        let index = parse_index(&header, body_len);
+
+        let mut seq_nums_file = open_file(&dir.join(format!("{prefix}.seqnums")))?;
+        let mut seq_nums = String::new();
+        seq_nums_file.read_to_string(&mut seq_nums)?;
+        let (next_sender_msg_seq_num, next_target_msg_seq_num) =
+            parse_seq_nums(&seq_nums).unwrap_or((1, 1));
+
+        let session_path = dir.join(format!("{prefix}.session"));
+        let creation_time = DateTime::parse_from_rfc3339(read_optional(&session_path)?.trim())
+            .map(|creation_time| creation_time.with_timezone(&Utc));
+
+        let inbound_path = dir.join(format!("{prefix}.inbound"));
+        let inbound_header_path = dir.join(format!("{prefix}.inbound.header"));
+        let inbound_len = match fs::metadata(&inbound_path) {
+            Ok(metadata) => metadata.len(),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => 0,
+            Err(err) => return Err(err),
+        };
+        let inbound_index = parse_index(&read_optional(&inbound_header_path)?, inbound_len);
+
+        let mut storage = FileStorage {
+            fsync_policy,
+            next_sender_msg_seq_num,
+            next_target_msg_seq_num,
+            index,
+            body_path,
+            body_file,
+            body_len,
+            header_file,
+            seq_nums_file,
+            session_path,
+            inbound_path,
+            inbound_header_path,
+            inbound_files: None,
+            inbound_len,
+            inbound_index,
+            creation_time: creation_time.unwrap_or_else(|_| Utc::now()),
+        };
+        storage.write_seq_nums()?;
+        if creation_time.is_err() {
+            storage.write_creation_time()?;
+        }
+        Ok(storage)
+    }
+
+    /// Stream incoming messages with MsgSeqNum<34> in `range`, stored
+    /// when inbound messages persistence is enabled.
+    pub fn fetch_inbound_range(
+        &self,
+        range: RangeInclusive<SeqNum>,
+    ) -> LocalBoxStream<'static, Vec<u8>> {
+        fetch_messages(&self.inbound_path, &self.inbound_index, range)
+    }
+
+    fn write_seq_nums(&mut self) -> Result<(), io::Error> {
+        // Fixed width entries, so file doesn't have to be truncated.
+        let seq_nums = format!(
+            "{:0>width$} : {:0>width$}",
+            self.next_sender_msg_seq_num,
+            self.next_target_msg_seq_num,
+            width = SEQ_NUM_WIDTH
+        );
+        self.seq_nums_file.seek(SeekFrom::Start(0))?;
+        self.seq_nums_file.write_all(seq_nums.as_bytes())?;
+        if self.fsync_policy != FsyncPolicy::Never {
+            self.seq_nums_file.sync_data()?;
+        }
+        Ok(())
+    }
+
+    fn write_creation_time(&mut self) -> Result<(), io::Error> {
+        // Written to temporary file first and renamed, so crash in the
+        // middle of write doesn't leave empty `.session` file.
+        let mut tmp_path = self.session_path.clone().into_os_string();
+        tmp_path.push(".tmp");
+        let mut tmp_file = File::create(&tmp_path)?;
+        tmp_file.write_all(self.creation_time.to_rfc3339().as_bytes())?;
+        if self.fsync_policy != FsyncPolicy::Never {
+            tmp_file.sync_data()?;
+        }
+        fs::rename(&tmp_path, &self.session_path)?;
+        if self.fsync_policy != FsyncPolicy::Never {
+            if let Some(dir) = self.session_path.parent() {
+                File::open(dir)?.sync_all()?;
+            }
+        }
+        Ok(())
+    }
+
+    fn store_seq_nums(&mut self) {
+        if let Err(err) = self.write_seq_nums() {
+            error!("failed to store sequence numbers: {err}");
+        }
+    }
+
+    fn write_message(&mut self, seq_num: SeqNum, data: &[u8]) -> Result<(), io::Error> {
+        let offset = self.body_len;
+        self.body_file.seek(SeekFrom::Start(offset))?;
+        self.body_file.write_all(data)?;
+        self.body_len += data.len() as u64;
+        self.header_file.seek(SeekFrom::End(0))?;
+        write!(self.header_file, "{seq_num},{offset},{} ", data.len())?;
+        if self.fsync_policy == FsyncPolicy::Always {
+            self.body_file.sync_data()?;
+            self.header_file.sync_data()?;
+        }
+        self.index.insert(
+            seq_num,
+            Location {
+                offset,
+                size: data.len(),
+            },
+        );
+        Ok(())
+    }
+
+    fn write_inbound_message(&mut self, seq_num: SeqNum, data: &[u8]) -> Result<(), io::Error> {
+        // Opened on first use, so files are not created when inbound
+        // messages persistence is disabled.
+        let (inbound_file, inbound_header_file) = match &mut self.inbound_files {
+            Some(inbound_files) => inbound_files,
+            inbound_files @ None => inbound_files.insert((
+                open_file(&self.inbound_path)?,
+                open_file(&self.inbound_header_path)?,
+            )),
+        };
+        let offset = self.inbound_len;
+        inbound_file.seek(SeekFrom::Start(offset))?;
+        inbound_file.write_all(data)?;
+        self.inbound_len += data.len() as u64;
+        inbound_header_file.seek(SeekFrom::End(0))?;
+        write!(inbound_header_file, "{seq_num},{offset},{} ", data.len())?;
+        if self.fsync_policy == FsyncPolicy::Always {
+            inbound_file.sync_data()?;
+            inbound_header_file.sync_data()?;
+        }
+        self.inbound_index.insert(
+            seq_num,
+            Location {
+                offset,
+                size: data.len(),
+            },
+        );
+        Ok(())
+    }
+
+    fn sync(&self) -> Result<(), io::Error> {
+        self.body_file.sync_all()?;
+        self.header_file.sync_all()?;
+        self.seq_nums_file.sync_all()?;
+        if let Some((inbound_file, inbound_header_file)) = &self.inbound_files {
+            inbound_file.sync_all()?;
+            inbound_header_file.sync_all()?;
+        }
+        Ok(())
+    }
+
+    fn clear(&mut self) -> Result<(), io::Error> {
+        self.body_file.set_len(0)?;
+        self.header_file.set_len(0)?;
+        self.inbound_files = None;
+        for path in [&self.inbound_path, &self.inbound_header_path] {
+            if path.exists() {
+                fs::remove_file(path)?;
+            }
+        }
+        self.body_len = 0;
+        self.index.clear();
+        self.inbound_len = 0;
+        self.inbound_index.clear();
+        self.creation_time = Utc::now();
+        self.write_creation_time()?;
+        if self.fsync_policy == FsyncPolicy::Always {
+            self.body_file.sync_all()?;
+            self.header_file.sync_all()?;
+        }
+        Ok(())
+    }
+}
+
+impl MessagesStorage for FileStorage {
+    fn fetch_range(&mut self, range: RangeInclusive<SeqNum>) -> LocalBoxStream<'static, Vec<u8>> {
+        fetch_messages(&self.body_path, &self.index, range)
+    }
+
+    fn store(&mut self, seq_num: SeqNum, data: &[u8]) {
+        if let Err(err) = self.write_message(seq_num, data) {
+            error!("failed to store message {seq_num}: {err}");
+        }
+    }
+
+    fn store_inbound(&mut self, seq_num: SeqNum, data: &[u8]) {
+        if let Err(err) = self.write_inbound_message(seq_num, data) {
+            error!("failed to store inbound message {seq_num}: {err}");
+        }
+    }
+
+    fn creation_time(&self) -> Option<DateTime<Utc>> {
+        Some(self.creation_time)
+    }
+
+    fn flush(&mut self) -> LocalBoxFuture<'static, ()> {
+        if let Err(err) = self.sync() {
+            error!("failed to flush messages storage: {err}");
+        }
+        future::ready(()).boxed_local()
+    }
+
+    fn next_sender_msg_seq_num(&self) -> SeqNum {
+        self.next_sender_msg_seq_num
+    }
+
+    fn next_target_msg_seq_num(&self) -> SeqNum {
+        self.next_target_msg_seq_num
+    }
+
+    fn set_next_sender_msg_seq_num(&mut self, seq_num: SeqNum) {
+        self.next_sender_msg_seq_num = seq_num;
+        self.store_seq_nums();
+    }
+
+    fn set_next_target_msg_seq_num(&mut self, seq_num: SeqNum) {
+        self.next_target_msg_seq_num = seq_num;
+        self.store_seq_nums();
+    }
+
+    fn incr_next_sender_msg_seq_num(&mut self) {
+        self.next_sender_msg_seq_num += 1;
+        self.store_seq_nums();
+    }
+
+    fn incr_next_target_msg_seq_num(&mut self) {
+        self.next_target_msg_seq_num += 1;
+        self.store_seq_nums();
+    }
+
+    fn reset(&mut self) {
+        // Clear messages first, so crash in between doesn't leave
+        // old messages with reset sequence numbers.
+        if let Err(err) = self.clear() {
+            error!("failed to clear messages storage: {err}");
+        }
+        self.next_sender_msg_seq_num = 1;
+        self.next_target_msg_seq_num = 1;
+        self.store_seq_nums();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use easyfix_messages::fields::FixString;
+    use futures::executor::block_on;
+
+    use super::*;
+
+    fn fetch(storage: &mut FileStorage, range: RangeInclusive<SeqNum>) -> Vec<Vec<u8>> {
+        block_on(storage.fetch_range(range).collect())
+    }
+
+    fn fetch_inbound(storage: &FileStorage, range: RangeInclusive<SeqNum>) -> Vec<Vec<u8>> {
+        block_on(storage.fetch_inbound_range(range).collect())
+    }
+
+    fn session_id() -> SessionId {
+        SessionId::new(
+            FixString::from_ascii_lossy(b"FIXT.1.1".to_vec()),
+            FixString::from_ascii_lossy(b"sender".to_vec()),
+            FixString::from_ascii_lossy(b"target".to_vec()),
+        )
+    }
+
+    #[test]
+    fn messages_and_seq_nums_survive_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        {
+            let mut storage = FileStorage::new(dir.path(), &session_id(), FsyncPolicy::Always)
+                .expect("failed to create storage");
+            storage.store(1, b"first");
+            storage.incr_next_sender_msg_seq_num();
+            storage.store(2, b"second");
+            storage.incr_next_sender_msg_seq_num();
+            storage.set_next_target_msg_seq_num(7);
+        }
+
+        let mut storage = FileStorage::new(dir.path(), &session_id(), FsyncPolicy::Always)
+            .expect("failed to open storage");
+        assert_eq!(storage.next_sender_msg_seq_num(), 3);
+        assert_eq!(storage.next_target_msg_seq_num(), 7);
+        assert_eq!(
+            fetch(&mut storage, 1..=2),
+            vec![b"first".to_vec(), b"second".to_vec()]
+        );
+        assert_eq!(fetch(&mut storage, 2..=5), vec![b"second".to_vec()]);
+    }
+
+    #[test]
+    #[allow(clippy::reversed_empty_ranges)]
+    fn inverted_range_is_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut storage = FileStorage::new(dir.path(), &session_id(), FsyncPolicy::Never)
+            .expect("failed to create storage");
+        storage.store(1, b"first");
+        storage.store(2, b"second");
+        assert!(fetch(&mut storage, 10..=4).is_empty());
+        assert!(fetch(&mut storage, 2..=1).is_empty());
+    }
+
+    #[test]
+    fn reset_clears_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut storage = FileStorage::new(dir.path(), &session_id(), FsyncPolicy::Never)
+            .expect("failed to create storage");
+        storage.store(1, b"first");
+        storage.incr_next_sender_msg_seq_num();
+        storage.reset();
+        storage.store(1, b"after reset");
+        drop(storage);
+
+        let mut storage = FileStorage::new(dir.path(), &session_id(), FsyncPolicy::Never)
+            .expect("failed to open storage");
+        assert_eq!(storage.next_sender_msg_seq_num(), 1);
+        assert_eq!(fetch(&mut storage, 1..=1), vec![b"after reset".to_vec()]);
+    }
+
+    #[test]
+    fn creation_time_and_inbound_messages() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut storage = FileStorage::new(dir.path(), &session_id(), FsyncPolicy::Never)
+            .expect("failed to create storage");
+        let creation_time = storage.creation_time();
+        storage.store_inbound(1, b"first|");
+        storage.store_inbound(2, b"second|");
+        drop(storage);
+
+        let inbound_path = dir.path().join("FIXT.1.1-sender-target.inbound");
+        assert_eq!(fs::read(&inbound_path).unwrap(), b"first|second|");
+
+        let mut storage = FileStorage::new(dir.path(), &session_id(), FsyncPolicy::Always)
+            .expect("failed to open storage");
+        assert_eq!(storage.creation_time(), creation_time);
+        assert_eq!(fetch_inbound(&storage, 2..=2), vec![b"second|".to_vec()]);
+        storage.store_inbound(3, b"third|");
+        assert_eq!(
+            fetch_inbound(&storage, 1..=3),
+            vec![b"first|".to_vec(), b"second|".to_vec(), b"third|".to_vec()]
+        );
+        storage.reset();
+        assert!(storage.creation_time() > creation_time);
+        assert!(!inbound_path.exists());
+        assert!(fetch_inbound(&storage, 1..=3).is_empty());
+        // Creation time is replaced, not rewritten in place
+        let session_path = dir.path().join("FIXT.1.1-sender-target.session");
+        assert!(!session_path.with_extension("session.tmp").exists());
+        let session = fs::read_to_string(&session_path).unwrap();
+        assert_eq!(session, storage.creation_time().unwrap().to_rfc3339());
+    }
+
+    #[test]
+    fn truncated_index_entry_is_skipped() {
+        let index = parse_index("1,0,5 2,5,6 3,11", 11);
+        assert_eq!(index.len(), 2);
+        let index = parse_index("1,0,5 2,5,6", 8);
+        assert_eq!(index.len(), 1);
+        let index = parse_index(&format!("1,0,5 2,{},1", u64::MAX), 11);
+        assert_eq!(index.len(), 1);
+    }
+}
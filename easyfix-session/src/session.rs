@@ -1,25 +1,26 @@
 use std::{cell::RefCell, rc::Rc};
 
 use easyfix_messages::{
+    deserializer::UnknownFields,
     fields::{
-        DefaultApplVerId, EncryptMethod, FixStr, FixString, Int, MsgType, SeqNum,
-        SessionRejectReason, SessionStatus, ToFixString, Utc, UtcTimestamp,
-    },
-    messages::{
-        FieldTag, FixtMessage, Heartbeat, Logon, Logout, Message, MsgCat, Reject, ResendRequest,
-        SequenceReset, TestRequest,
+        DateTime, DefaultApplVerId, EncryptMethod, FixStr, FixString, Int, SeqNum,
+        SessionRejectReason, SessionStatus, Utc, UtcTimestamp,
     },
+    messages::{FieldTag, FixtMessage, MsgCat},
+    session::{AdminMessage, AdminMsgType, SessionHeader, SessionMessage},
 };
+use futures::{pin_mut, StreamExt};
 use tokio::time::{Duration, Instant};
-use tracing::{error, info, instrument, trace, warn};
+use tracing::{debug, error, info, instrument, trace, warn};
 
 use crate::{
-    application::{DeserializeError, Emitter, FixEventInternal, InputResponderMsg, Responder},
+    application::{
+        DeserializeError, Emitter, FixEventInternal, InputResponderMsg, Responder, ResponderMsg,
+    },
     messages_storage::MessagesStorage,
-    new_header, new_trailer,
     session_id::SessionId,
     session_state::State,
-    settings::{SessionSettings, Settings},
+    settings::{SessionSettings, Settings, ThrottleSettings},
     DisconnectReason, Sender,
 };
 
@@ -102,31 +103,37 @@ trait MessageExt {
     fn resend_as_gap_fill(&self) -> bool;
 }
 
-impl MessageExt for FixtMessage {
+impl<M: SessionMessage> MessageExt for M {
     fn resend_as_gap_fill(&self) -> bool {
-        matches!(self.msg_cat(), MsgCat::Admin) && !matches!(self.msg_type(), MsgType::Reject)
+        matches!(self.msg_cat(), MsgCat::Admin)
+            && self.admin_msg_type() != Some(AdminMsgType::Reject)
     }
 }
 
 #[derive(Debug)]
-pub(crate) struct Session<S> {
+pub(crate) struct Session<S, M: SessionMessage = FixtMessage> {
     // XXX: To avoid borrow errors, borrow state only in async fn,
     //      and in regular fn pass it by ref as argument.
-    state: Rc<RefCell<State<S>>>,
-    sender: Sender,
+    state: Rc<RefCell<State<S, M>>>,
+    sender: Sender<M>,
     settings: Settings,
     session_settings: SessionSettings,
-    emitter: Emitter,
+    emitter: Emitter<M>,
 }
 
-impl<S: MessagesStorage> Session<S> {
+impl<S: MessagesStorage, M: SessionMessage> Session<S, M> {
     pub(crate) fn new(
         settings: Settings,
         session_settings: SessionSettings,
-        state: Rc<RefCell<State<S>>>,
-        sender: Sender,
-        emitter: Emitter,
-    ) -> Session<S> {
+        state: Rc<RefCell<State<S, M>>>,
+        sender: Sender<M>,
+        emitter: Emitter<M>,
+    ) -> Session<S, M> {
+        // Initiator proposes configured value, acceptor overrides it
+        // with value received in Logon<A>.
+        state
+            .borrow_mut()
+            .set_heart_bt_int(settings.heartbeat_interval.as_secs() as Int);
         Session {
             state,
             settings,
@@ -140,14 +147,55 @@ impl<S: MessagesStorage> Session<S> {
         &self.session_settings.session_id
     }
 
-    pub(crate) fn state(&self) -> &Rc<RefCell<State<S>>> {
+    pub(crate) fn state(&self) -> &Rc<RefCell<State<S, M>>> {
         &self.state
     }
 
-    pub fn is_logged_on(state: &State<S>) -> bool {
+    pub(crate) fn sender(&self) -> &Sender<M> {
+        &self.sender
+    }
+
+    pub(crate) fn throttle_settings(&self) -> Option<&ThrottleSettings> {
+        self.session_settings.throttle.as_ref()
+    }
+
+    pub(crate) fn unknown_fields(&self) -> UnknownFields {
+        self.session_settings.unknown_fields
+    }
+
+    pub fn is_logged_on(state: &State<S, M>) -> bool {
         state.logon_received() && state.logon_sent()
     }
 
+    pub fn is_session_time(&self, time: DateTime<Utc>) -> bool {
+        self.session_settings
+            .session_time
+            .as_ref()
+            .is_none_or(|session_time| session_time.is_session_time(time))
+    }
+
+    /// End of current session period, `None` if session has no schedule.
+    pub(crate) fn session_end(&self) -> Option<DateTime<Utc>> {
+        self.session_settings
+            .session_time
+            .as_ref()
+            .and_then(|session_time| session_time.session_end(Utc::now()))
+    }
+
+    /// Reset sequence numbers and stored messages when storage
+    /// was created in previous session period.
+    pub(crate) fn reset_on_new_session_period(&self, state: &mut State<S, M>) {
+        let Some(session_time) = &self.session_settings.session_time else {
+            return;
+        };
+        let now = Utc::now();
+        let creation_time = state.creation_time();
+        if session_time.is_session_time(now) && !session_time.is_same_session(creation_time, now) {
+            info!(%creation_time, "New session period, reseting sequence numbers");
+            state.reset();
+        }
+    }
+
     pub fn is_logon_time(&self, time: UtcTimestamp) -> bool {
         self.session_settings
             .logon_time
@@ -182,11 +230,11 @@ impl<S: MessagesStorage> Session<S> {
         }
     }
 
-    fn is_target_too_high(state: &State<S>, msg_seq_num: SeqNum) -> bool {
+    fn is_target_too_high(state: &State<S, M>, msg_seq_num: SeqNum) -> bool {
         msg_seq_num > state.next_target_msg_seq_num()
     }
 
-    fn is_target_too_low(state: &State<S>, msg_seq_num: SeqNum) -> bool {
+    fn is_target_too_low(state: &State<S, M>, msg_seq_num: SeqNum) -> bool {
         msg_seq_num < state.next_target_msg_seq_num()
     }
 
@@ -206,7 +254,7 @@ impl<S: MessagesStorage> Session<S> {
         }
     }
 
-    fn should_send_reset(&self, state: &State<S>) -> bool {
+    fn should_send_reset(&self, state: &State<S, M>) -> bool {
         (self.session_settings.reset_on_logon
             || self.session_settings.reset_on_logout
             || self.session_settings.reset_on_disconnect)
@@ -216,20 +264,23 @@ impl<S: MessagesStorage> Session<S> {
 
     // current implementation is more readable than clippy proposal
     #[allow(clippy::if_same_then_else)]
-    fn check_logon_state(state: &State<S>, msg_type: MsgType) -> Result<(), VerifyError> {
-        if (msg_type == MsgType::Logon && state.reset_sent()) || state.reset_received() {
+    fn check_logon_state(
+        state: &State<S, M>,
+        msg_type: Option<AdminMsgType>,
+    ) -> Result<(), VerifyError> {
+        let is_logon = msg_type == Some(AdminMsgType::Logon);
+        let is_logout = msg_type == Some(AdminMsgType::Logout);
+        if (is_logon && state.reset_sent()) || state.reset_received() {
             Ok(())
-        } else if (msg_type == MsgType::Logon && !state.logon_received())
-            || (msg_type != MsgType::Logon && state.logon_received())
-        {
+        } else if (is_logon && !state.logon_received()) || (!is_logon && state.logon_received()) {
             Ok(())
-        } else if msg_type == MsgType::Logout && state.logon_sent() {
+        } else if is_logout && state.logon_sent() {
             Ok(())
-        } else if msg_type != MsgType::Logout && state.logout_sent() {
+        } else if !is_logout && state.logout_sent() {
             Ok(())
-        } else if msg_type == MsgType::SequenceReset {
+        } else if msg_type == Some(AdminMsgType::SequenceReset) {
             Ok(())
-        } else if msg_type == MsgType::Reject {
+        } else if msg_type == Some(AdminMsgType::Reject) {
             Ok(())
         } else {
             Err(VerifyError::InvalidLogonState)
@@ -242,20 +293,20 @@ impl<S: MessagesStorage> Session<S> {
     // https://github.com/rust-lang/rust-clippy/issues/6353
     async fn verify(
         &self,
-        msg: Box<FixtMessage>,
+        msg: Box<M>,
         check_too_high: bool,
         check_too_low: bool,
     ) -> Result<(), VerifyError> {
-        let msg_type = msg.header.msg_type;
+        let msg_type = msg.admin_msg_type();
 
-        let sender_comp_id = &msg.header.sender_comp_id;
-        let target_comp_id = &msg.header.target_comp_id;
-        let sending_time = msg.header.sending_time;
-        let msg_seq_num = msg.header.msg_seq_num;
+        let sender_comp_id = msg.header().sender_comp_id();
+        let target_comp_id = msg.header().target_comp_id();
+        let sending_time = msg.header().sending_time();
+        let msg_seq_num = msg.header().msg_seq_num();
 
         let state = self.state.borrow();
 
-        Self::check_logon_state(&state, msg.header.msg_type)?;
+        Self::check_logon_state(&state, msg_type)?;
         self.check_sending_time(sending_time)?;
         self.check_comp_id(sender_comp_id, target_comp_id)?;
 
@@ -268,9 +319,9 @@ impl<S: MessagesStorage> Session<S> {
             self.state.borrow_mut().enqueue_msg(msg);
             Err(VerifyError::target_seq_num_too_high(msg_seq_num))
         } else if check_too_low && Self::is_target_too_low(&state, msg_seq_num) {
-            if msg.header.poss_dup_flag.unwrap_or(false) {
-                if msg_type != MsgType::SequenceReset {
-                    let Some(orig_sending_time) = msg.header.orig_sending_time else {
+            if msg.header().poss_dup_flag().unwrap_or(false) {
+                if msg_type != Some(AdminMsgType::SequenceReset) {
+                    let Some(orig_sending_time) = msg.header().orig_sending_time() else {
                         warn!("Target too low (orig sending time missing)");
                         return Err(VerifyError::missing_orig_time());
                     };
@@ -357,14 +408,23 @@ impl<S: MessagesStorage> Session<S> {
         }
     }
 
-    pub(crate) fn send_logon_request(&self, state: &mut State<S>) {
+    /// DefaultApplVerID<1137> of sent Logon<A>, ignored by codecs
+    /// of FIX 4.x, which don't define it.
+    fn default_appl_ver_id(&self) -> FixString {
+        DefaultApplVerId::from_fix_str(&self.session_settings.sender_default_appl_ver_id)
+            .unwrap_or(DefaultApplVerId::Fix50Sp2)
+            .as_fix_str()
+            .to_owned()
+    }
+
+    pub(crate) fn send_logon_request(&self, state: &mut State<S, M>) {
         if self.session_settings.reset_on_logon {
             state.reset();
         }
 
-        self.send(Box::new(Message::Logon(Logon {
+        self.send(AdminMessage::Logon {
             // encrypt_method: EncryptMethod::None,
-            encrypt_method: EncryptMethod::NoneOther,
+            encrypt_method: EncryptMethod::NoneOther.as_fix_str().to_owned(),
             heart_bt_int: state.heart_bt_int(),
             reset_seq_num_flag: self.should_send_reset(state).then_some(true),
             next_expected_msg_seq_num: if self.session_settings.enable_next_expected_msg_seq_num {
@@ -374,53 +434,54 @@ impl<S: MessagesStorage> Session<S> {
             } else {
                 None
             },
-            default_appl_ver_id: DefaultApplVerId::Fix50Sp2,
-            ..Default::default()
-        })));
+            default_appl_ver_id: Some(self.default_appl_ver_id()),
+            username: None,
+            password: None,
+        });
+        state.set_logon_sent(true);
     }
 
-    fn send_logon_response(&self, state: &mut State<S>, next_expected_msg_seq_num: Option<SeqNum>) {
+    fn send_logon_response(
+        &self,
+        state: &mut State<S, M>,
+        next_expected_msg_seq_num: Option<SeqNum>,
+    ) {
         if self.session_settings.reset_on_logon {
             state.reset();
         }
 
-        self.send(Box::new(Message::Logon(Logon {
-            encrypt_method: EncryptMethod::NoneOther,
+        self.send(AdminMessage::Logon {
+            encrypt_method: EncryptMethod::NoneOther.as_fix_str().to_owned(),
             // TODO: option to use predefined OR the value from Logon request
             heart_bt_int: state.heart_bt_int(),
             reset_seq_num_flag: self.should_send_reset(state).then_some(true),
             next_expected_msg_seq_num,
-            // TODO: if self.session_settings.session_id().is_fixt()
-            // default_appl_ver_id: self.sender_default_appl_ver_id().to_owned(),
-            default_appl_ver_id: DefaultApplVerId::Fix50Sp2,
-            ..Default::default()
-        })));
+            default_appl_ver_id: Some(self.default_appl_ver_id()),
+            username: None,
+            password: None,
+        });
 
         state.set_last_received_time(Instant::now());
-        state.set_test_request(0);
+        state.set_test_request(None);
         state.set_logon_sent(true);
     }
 
     pub(crate) fn send_logout(
         &self,
-        state: &mut State<S>,
+        state: &mut State<S, M>,
         session_status: Option<SessionStatus>,
         text: Option<FixString>,
     ) {
-        // User can add new fields to message definition, this way in most
-        // cases new field won't require changes here.
-        #[allow(clippy::needless_update)]
-        self.send(Box::new(Message::Logout(Logout {
-            session_status,
+        self.send(AdminMessage::Logout {
+            session_status: session_status.map(|status| status.as_fix_str().to_owned()),
             text,
-            ..Default::default()
-        })));
+        });
         state.set_logout_sent(true);
     }
 
     fn send_reject(
         &self,
-        state: &mut State<S>,
+        state: &mut State<S, M>,
         ref_msg_type: Option<FixString>,
         ref_seq_num: SeqNum,
         reason: SessionRejectReason,
@@ -428,8 +489,8 @@ impl<S: MessagesStorage> Session<S> {
         ref_tag_id: Option<i64>,
     ) {
         if !matches!(
-            ref_msg_type.as_deref().and_then(MsgType::from_fix_str),
-            Some(MsgType::Logon) | Some(MsgType::SequenceReset)
+            ref_msg_type.as_deref().and_then(AdminMsgType::from_fix_str),
+            Some(AdminMsgType::Logon) | Some(AdminMsgType::SequenceReset)
         ) && ref_seq_num == state.next_target_msg_seq_num()
         {
             state.incr_next_target_msg_seq_num();
@@ -441,51 +502,48 @@ impl<S: MessagesStorage> Session<S> {
             // TODO: Error
         }
 
-        self.send(Box::new(Message::Reject(Reject {
+        self.send(AdminMessage::Reject {
             ref_seq_num,
             ref_tag_id,
             ref_msg_type,
-            session_reject_reason: Some(reason),
+            session_reject_reason: Some(reason.as_fix_str().to_owned()),
             text: Some(text),
-            ..Default::default()
-        })));
+        });
     }
 
     fn send_sequence_reset(&self, seq_num: SeqNum, new_seq_num: SeqNum) {
-        let mut sequence_reset = Box::new(FixtMessage {
-            header: Box::new(new_header(MsgType::SequenceReset)),
-            body: Box::new(Message::SequenceReset(SequenceReset {
-                gap_fill_flag: Some(true),
-                new_seq_no: new_seq_num,
-            })),
-            trailer: Box::new(new_trailer()),
+        let mut sequence_reset = M::from_admin(AdminMessage::SequenceReset {
+            gap_fill_flag: Some(true),
+            new_seq_no: new_seq_num,
         });
 
-        sequence_reset.header.msg_seq_num = seq_num;
-        sequence_reset.header.poss_dup_flag = Some(true);
-        sequence_reset.header.sending_time = UtcTimestamp::now();
-        sequence_reset.header.orig_sending_time = Some(sequence_reset.header.sending_time);
+        let header = sequence_reset.header_mut();
+        let sending_time = UtcTimestamp::now();
+        header.set_msg_seq_num(seq_num);
+        header.set_poss_dup_flag(Some(true));
+        header.set_sending_time(sending_time);
+        header.set_orig_sending_time(Some(sending_time));
 
         info!(seq_num, new_seq_num, "SequenceReset sent (gap fill)");
         self.send_raw(sequence_reset);
     }
 
     #[instrument(level = "trace", skip_all)]
-    fn send_resend_request(&self, state: &mut State<S>, msg_seq_num: SeqNum) {
+    fn send_resend_request(&self, state: &mut State<S, M>, msg_seq_num: SeqNum) {
         let begin_seq_no = state.next_target_msg_seq_num();
         let end_seq_no = msg_seq_num - 1;
 
-        self.send(Box::new(Message::ResendRequest(ResendRequest {
+        self.send(AdminMessage::ResendRequest {
             begin_seq_no,
             end_seq_no,
-        })));
+        });
 
         state.set_resend_range(Some(begin_seq_no..=msg_seq_num - 1));
     }
 
-    /// Send FIX message.
-    fn send(&self, msg: Box<Message>) {
-        if let Err(msg) = self.sender.send(msg) {
+    /// Send session level message.
+    fn send(&self, msg: AdminMessage) {
+        if let Err(msg) = self.sender.send_session_msg(M::from_admin(msg)) {
             // This should never happen.
             // See `fn input_loop()` and `fn output_loop()` in connection.rs
             // Output loop always waits for input loop to finish, so it's not
@@ -494,14 +552,14 @@ impl<S: MessagesStorage> Session<S> {
             unreachable!(
                 "Can't send message {:?}/{} - output stream is closed",
                 msg.msg_type(),
-                msg.header.msg_seq_num
+                msg.header().msg_seq_num()
             );
         }
     }
 
     /// Send FIXT message.
-    fn send_raw(&self, msg: Box<FixtMessage>) {
-        if let Err(msg) = self.sender.send_raw(msg) {
+    fn send_raw(&self, msg: Box<M>) {
+        if let Err(msg) = self.sender.send_session_msg(msg) {
             // This should never happen.
             // See `fn input_loop()` and `fn output_loop()` in connection.rs
             // Output loop always waits for input loop to finish, so it's not
@@ -510,7 +568,7 @@ impl<S: MessagesStorage> Session<S> {
             unreachable!(
                 "Can't send message {:?}/{} - output stream is closed",
                 msg.msg_type(),
-                msg.header.msg_seq_num
+                msg.header().msg_seq_num()
             );
         }
     }
@@ -521,9 +579,12 @@ impl<S: MessagesStorage> Session<S> {
     pub(crate) async fn emit_logout(&self, reason: DisconnectReason) {
         let mut state = self.state.borrow_mut();
 
-        if state.logon_received() || state.logon_sent() {
-            state.set_logon_received(false);
-            state.set_logon_sent(false);
+        // Logon sent by initiator but not answered yet doesn't count,
+        // `Logon` event wasn't emitted either
+        let logged_on = state.logon_received();
+        state.set_logon_received(false);
+        state.set_logon_sent(false);
+        if logged_on {
             drop(state);
 
             self.emitter
@@ -535,7 +596,7 @@ impl<S: MessagesStorage> Session<S> {
         }
     }
 
-    pub(crate) fn disconnect(&self, state: &mut State<S>, reason: DisconnectReason) {
+    pub(crate) fn disconnect(&self, state: &mut State<S, M>, reason: DisconnectReason) {
         if state.disconnected() {
             info!("already disconnected");
             return;
@@ -557,121 +618,164 @@ impl<S: MessagesStorage> Session<S> {
         }
 
         state.set_resend_range(None);
+        state.set_test_request(None);
+        state.set_input_timoeut_cnt(0);
         state.clear_queue();
         self.sender.disconnect(reason);
     }
 
-    pub(crate) fn reset(&self, state: &mut State<S>) {
+    pub(crate) fn reset(&self, state: &mut State<S, M>) {
         state.reset();
     }
 
     #[instrument(level = "trace", skip_all)]
-    fn resend_range(&self, state: &mut State<S>, begin_seq_num: SeqNum, mut end_seq_num: SeqNum) {
+    async fn resend_range(&self, begin_seq_num: SeqNum, mut end_seq_num: SeqNum) {
         info!("resend range: ({begin_seq_num}, {end_seq_num})");
-        let next_sender_msg_seq_num = state.next_sender_msg_seq_num();
-        if end_seq_num == 0 || end_seq_num >= next_sender_msg_seq_num {
-            end_seq_num = next_sender_msg_seq_num - 1;
-            info!("adjust end_seq_num to {end_seq_num}");
-        }
-
-        // Just do a gap fill when messages aren't persisted
-        if !self.session_settings.persist {
+        let messages = {
+            let mut state = self.state.borrow_mut();
             let next_sender_msg_seq_num = state.next_sender_msg_seq_num();
-            end_seq_num += 1;
-            if end_seq_num > next_sender_msg_seq_num {
-                end_seq_num = next_sender_msg_seq_num;
+            if end_seq_num == 0 || end_seq_num >= next_sender_msg_seq_num {
+                end_seq_num = next_sender_msg_seq_num - 1;
+                info!("adjust end_seq_num to {end_seq_num}");
             }
-            self.send_sequence_reset(begin_seq_num, end_seq_num);
-            return;
-        }
+
+            // Just do a gap fill when messages aren't persisted
+            if !self.session_settings.persist {
+                end_seq_num += 1;
+                if end_seq_num > next_sender_msg_seq_num {
+                    end_seq_num = next_sender_msg_seq_num;
+                }
+                self.send_sequence_reset(begin_seq_num, end_seq_num);
+                return;
+            }
+
+            // Stream doesn't borrow the storage, so `state` can be released
+            // before messages are loaded.
+            state.fetch_range(begin_seq_num..=end_seq_num)
+        };
+        pin_mut!(messages);
 
         let mut gap_fill_range = None;
-        let messages = state.fetch_range(begin_seq_num..=end_seq_num);
-        info!(
-            "fetch messages range from {begin_seq_num} to {end_seq_num}, found {} messages",
-            messages.len()
-        );
-        for msg_str in messages {
+        let mut messages_cnt = 0;
+        while let Some(msg_str) = messages.next().await {
+            messages_cnt += 1;
             // TODO: log error! and resend as gap fill instead of unwrap
-            let mut msg = FixtMessage::from_bytes(&msg_str).unwrap();
-            if msg.resend_as_gap_fill() {
-                info!(
-                    "Message {:?}/{} changed to gap fill",
-                    msg.msg_type(),
-                    msg.header.msg_seq_num
-                );
-                gap_fill_range
-                    .get_or_insert((msg.header.msg_seq_num, msg.header.msg_seq_num - 1))
-                    .1 += 1;
+            let mut msg = M::from_bytes(&msg_str).unwrap();
+            let seq_num = msg.header().msg_seq_num();
+            let msg = if msg.resend_as_gap_fill() {
+                info!("Message {:?}/{seq_num} changed to gap fill", msg.msg_type());
+                None
             } else {
-                if let Some((begin_seq_num, end_seq_num)) = gap_fill_range.take() {
-                    info!("Resending messages from {begin_seq_num} to {end_seq_num} as gap fill");
-                    self.send_sequence_reset(begin_seq_num, end_seq_num + 1);
+                let header = msg.header_mut();
+                header.set_orig_sending_time(Some(header.sending_time()));
+                header.set_poss_dup_flag(Some(true));
+                self.on_message_resend(msg).await
+            };
+            match msg {
+                Some(msg) => {
+                    if let Some((begin_seq_num, end_seq_num)) = gap_fill_range.take() {
+                        info!(
+                            "Resending messages from {begin_seq_num} to {end_seq_num} as gap fill"
+                        );
+                        self.send_sequence_reset(begin_seq_num, end_seq_num + 1);
+                    }
+                    info!("Resending message {:?}/{seq_num}", msg.msg_type());
+                    self.send_raw(msg);
+                }
+                None => {
+                    gap_fill_range.get_or_insert((seq_num, seq_num - 1)).1 += 1;
                 }
-                info!(
-                    "Resending message {:?}/{}",
-                    msg.msg_type(),
-                    msg.header.msg_seq_num
-                );
-                msg.header.orig_sending_time = Some(msg.header.sending_time);
-                msg.header.poss_dup_flag = Some(true);
-                // TODO: emit event!
-                self.send_raw(msg);
             }
         }
         if let Some((begin_seq_num, end_seq_num)) = gap_fill_range {
             info!("Resending messages from {begin_seq_num} to {end_seq_num} as gap fill");
             self.send_sequence_reset(begin_seq_num, end_seq_num + 1);
         }
+        info!(
+            "fetch messages range from {begin_seq_num} to {end_seq_num}, found {messages_cnt} messages"
+        );
     }
 
-    async fn on_heartbeat(&self, message: Box<FixtMessage>) -> Result<(), VerifyError> {
-        // Got Heartbeat, nothing to do.
-        // If we would like to check if this is response for specific
-        // TestRequest, it should be done here.
-        // TODO Check it
+    /// Let application decide what to do with application message about
+    /// to be resent, `None` means it has to be replaced with gap fill.
+    async fn on_message_resend(&self, msg: Box<M>) -> Option<Box<M>> {
+        let (sender, receiver) = tokio::sync::oneshot::channel();
+        self.emitter
+            .send(FixEventInternal::AppMsgResend(
+                self.session_settings.session_id.clone(),
+                Some(msg),
+                Responder::new(sender),
+            ))
+            .await;
+        match receiver.await {
+            Ok(ResponderMsg::Send(msg)) => Some(msg),
+            Ok(ResponderMsg::GapFill(msg)) => {
+                info!(
+                    "Message {:?}/{} changed to gap fill by application",
+                    msg.msg_type(),
+                    msg.header().msg_seq_num()
+                );
+                None
+            }
+            Err(_do_not_send) => None,
+        }
+    }
+
+    async fn on_heartbeat(
+        &self,
+        message: Box<M>,
+        test_req_id: Option<FixString>,
+    ) -> Result<(), VerifyError> {
         trace!("got heartbeat");
 
         self.verify(message, true, true).await?;
 
-        self.state.borrow_mut().incr_next_target_msg_seq_num();
+        let mut state = self.state.borrow_mut();
+        // Only Heartbeat answering outstanding TestRequest proves
+        // the counterparty is alive after input timeout
+        if let Some(test_req_id) = test_req_id {
+            if state.complete_test_request(&test_req_id) {
+                debug!(
+                    "TestRequest answered, round-trip time: {:?}",
+                    state.round_trip_time()
+                );
+            } else {
+                debug!("Heartbeat with unexpected TestReqID<112> {test_req_id}");
+            }
+        }
+        state.incr_next_target_msg_seq_num();
         Ok(())
     }
 
     /// Got TestRequest, answer with Heartbeat and return.
-    async fn on_test_request(&self, message: Box<FixtMessage>) -> Result<(), VerifyError> {
+    async fn on_test_request(
+        &self,
+        message: Box<M>,
+        test_req_id: FixString,
+    ) -> Result<(), VerifyError> {
         trace!("on_test_request");
 
-        let Message::TestRequest(ref test_request) = *message.body else {
-            unreachable!();
-        };
-
-        let test_req_id = test_request.test_req_id.clone();
-
         self.verify(message, true, true).await?;
 
         trace!("Send Heartbeat");
-        self.send(Box::new(Message::Heartbeat(Heartbeat {
+        self.send(AdminMessage::Heartbeat {
             test_req_id: Some(test_req_id),
-        })));
+        });
 
         self.state.borrow_mut().incr_next_target_msg_seq_num();
 
         Ok(())
     }
 
-    async fn on_resend_request(&self, msg: Box<FixtMessage>) -> Result<(), VerifyError> {
+    async fn on_resend_request(
+        &self,
+        msg: Box<M>,
+        begin_seq_no: SeqNum,
+        end_seq_no: SeqNum,
+    ) -> Result<(), VerifyError> {
         trace!("on_resend_request");
 
-        let Message::ResendRequest(ref resend_request) = *msg.body else {
-            // Enum is matched in on_message_in_impl
-            unreachable!();
-        };
-
-        let begin_seq_no = resend_request.begin_seq_no;
-        let end_seq_no = resend_request.end_seq_no;
-
-        let msg_seq_num = msg.header.msg_seq_num;
+        let msg_seq_num = msg.header().msg_seq_num();
 
         // XXX: Do not check if message is too high here - in such case message
         //      would be enqueued for later processing. This might lead to
@@ -683,21 +787,17 @@ impl<S: MessagesStorage> Session<S> {
 
         info!("Received ResendRequest FROM: {begin_seq_no} TO: {end_seq_no}");
 
-        let mut state = self.state.borrow_mut();
+        self.resend_range(begin_seq_no, end_seq_no).await;
 
-        self.resend_range(&mut state, begin_seq_no, end_seq_no);
+        let mut state = self.state.borrow_mut();
 
         if Self::is_target_too_high(&state, msg_seq_num) {
             // XXX: This message will be ignored during queued messages
             //      processing, it's enqueued only to omaintain proper
             //      sequence numbers.
-            state.enqueue_msg(Box::new(FixtMessage {
-                header: Box::new(new_header(MsgType::ResendRequest)),
-                body: Box::new(Message::ResendRequest(ResendRequest {
-                    begin_seq_no,
-                    end_seq_no,
-                })),
-                trailer: Box::new(new_trailer()),
+            state.enqueue_msg(M::from_admin(AdminMessage::ResendRequest {
+                begin_seq_no,
+                end_seq_no,
             }));
             self.send_resend_request(&mut state, msg_seq_num);
         } else if state.next_target_msg_seq_num() == msg_seq_num {
@@ -707,7 +807,7 @@ impl<S: MessagesStorage> Session<S> {
         Ok(())
     }
 
-    async fn on_reject(&self, message: Box<FixtMessage>) -> Result<(), VerifyError> {
+    async fn on_reject(&self, message: Box<M>) -> Result<(), VerifyError> {
         trace!("on_reject");
 
         self.verify(message, false, true).await?;
@@ -717,18 +817,17 @@ impl<S: MessagesStorage> Session<S> {
         Ok(())
     }
 
-    async fn on_sequence_reset(&self, message: Box<FixtMessage>) -> Result<(), VerifyError> {
+    async fn on_sequence_reset(
+        &self,
+        message: Box<M>,
+        gap_fill_flag: Option<bool>,
+        new_seq_no: SeqNum,
+    ) -> Result<(), VerifyError> {
         trace!("on_sequence_reset");
 
-        let Message::SequenceReset(ref sequence_reset) = *message.body else {
-            // Enum is matched in on_message_in_impl
-            unreachable!();
-        };
-
-        let ref_msg_type = message.header.msg_type.as_fix_str().to_owned();
-        let ref_seq_num = message.header.msg_seq_num;
-        let is_gap_fill = sequence_reset.gap_fill_flag.unwrap_or(false);
-        let new_seq_no = sequence_reset.new_seq_no;
+        let ref_msg_type = M::msg_type_as_fix_str(message.msg_type()).to_owned();
+        let ref_seq_num = message.header().msg_seq_num();
+        let is_gap_fill = gap_fill_flag.unwrap_or(false);
 
         self.verify(message, is_gap_fill, is_gap_fill).await?;
 
@@ -754,7 +853,7 @@ impl<S: MessagesStorage> Session<S> {
         Ok(())
     }
 
-    async fn on_logout(&self, message: Box<FixtMessage>) -> Result<DisconnectReason, VerifyError> {
+    async fn on_logout(&self, message: Box<M>) -> Result<DisconnectReason, VerifyError> {
         if self.session_settings.verify_logout {
             self.verify(message, true, true).await?;
         } else if let Err(e) = self.verify(message, false, false).await {
@@ -791,32 +890,20 @@ impl<S: MessagesStorage> Session<S> {
     // https://github.com/rust-lang/rust-clippy/issues/6353
     async fn on_logon(
         &self,
-        message: Box<FixtMessage>,
+        message: Box<M>,
+        reset_seq_num_flag: Option<bool>,
+        heart_bt_int: Int,
+        next_expected_msg_seq_num: Option<SeqNum>,
     ) -> Result<Option<DisconnectReason>, VerifyError> {
-        let (
-            enabled,
-            initiate,
-            should_send_logon,
-            reset_received,
-            reset_sent,
-            reset_seq_num_flag,
-            heart_bt_int,
-            next_expected_msg_seq_num,
-        ) = {
+        let reset_seq_num_flag = reset_seq_num_flag.unwrap_or(false);
+        let (enabled, initiate, should_send_logon, reset_received, reset_sent) = {
             let state = self.state.borrow_mut();
-
-            let Message::Logon(ref logon) = *message.body else {
-                unreachable!()
-            };
             (
                 state.enabled(),
                 state.initiate(),
                 state.should_send_logon(),
                 state.reset_received(),
                 state.reset_sent(),
-                logon.reset_seq_num_flag.unwrap_or(false),
-                logon.heart_bt_int,
-                logon.next_expected_msg_seq_num,
             )
         };
 
@@ -825,12 +912,35 @@ impl<S: MessagesStorage> Session<S> {
             return Ok(Some(DisconnectReason::InvalidLogonState));
         }
 
-        if !self.is_logon_time(message.header.sending_time) {
+        if !self.is_session_time(Utc::now()) {
+            error!("Received logon outside of session time");
+            self.send_logout(
+                &mut self.state.borrow_mut(),
+                None,
+                Some(FixString::from_ascii_lossy(
+                    b"Logon<A> outside of session time".to_vec(),
+                )),
+            );
+            return Ok(Some(DisconnectReason::InvalidLogonState));
+        }
+
+        if !self.is_logon_time(message.header().sending_time()) {
             error!("Received logon outside of valid logon time");
             return Ok(Some(DisconnectReason::InvalidLogonState));
         }
 
-        let msg_seq_num = message.header.msg_seq_num;
+        if !initiate && !self.is_heart_bt_int_valid(heart_bt_int) {
+            let error_msg = format!("HeartBtInt<108> {heart_bt_int} out of allowed range");
+            error!(error_msg);
+            self.send_logout(
+                &mut self.state.borrow_mut(),
+                None,
+                Some(FixString::from_ascii_lossy(error_msg.into_bytes())),
+            );
+            return Ok(Some(DisconnectReason::InvalidLogonState));
+        }
+
+        let msg_seq_num = message.header().msg_seq_num();
 
         let enable_next_expected_msg_seq_num =
             self.session_settings.enable_next_expected_msg_seq_num
@@ -862,6 +972,7 @@ impl<S: MessagesStorage> Session<S> {
         let mut state = self.state.borrow_mut();
 
         state.set_logon_received(true);
+        state.set_logon_completed(true);
 
         let next_sender_msg_num_at_logon_received = state.next_sender_msg_seq_num();
 
@@ -935,56 +1046,46 @@ impl<S: MessagesStorage> Session<S> {
                 state.next_target_msg_seq_num()
             );
 
-            state.enqueue_msg(
-                // No need to clone input message. Pass empty message
-                // as it will be skipped during enqueued messages processing.
-                Box::new(FixtMessage {
-                    header: {
-                        let mut header = Box::new(new_header(MsgType::Logon));
-                        header.msg_seq_num = msg_seq_num;
-                        header
-                    },
-                    body: Box::new(Message::Logon(Logon::default())),
-                    trailer: Box::new(new_trailer()),
-                }),
-            );
+            // No need to clone input message. Pass empty message
+            // as it will be skipped during enqueued messages processing.
+            let mut logon = M::from_admin(AdminMessage::Logon {
+                encrypt_method: FixString::new(),
+                heart_bt_int: 0,
+                reset_seq_num_flag: None,
+                next_expected_msg_seq_num: None,
+                default_appl_ver_id: None,
+                username: None,
+                password: None,
+            });
+            logon.header_mut().set_msg_seq_num(msg_seq_num);
+            state.enqueue_msg(logon);
             ret = Err(VerifyError::ResendRequest { msg_seq_num });
         } else {
             state.incr_next_target_msg_seq_num();
         }
 
+        let is_logged_on = Self::is_logged_on(&state);
+        drop(state);
+
         if enable_next_expected_msg_seq_num {
             if let Some(next_expected_msg_seq_num) = next_expected_msg_seq_num {
                 // is the 789 lower (we checked for higher previously) than our next message after receiving the logon
                 if next_expected_msg_seq_num != next_sender_msg_num_at_logon_received {
-                    let mut end_seq_no = next_sender_msg_num_at_logon_received;
-
-                    // TODO: self.resend_range() will handle this !!!
-                    if !self.session_settings.persist {
-                        end_seq_no += 1;
-                        let next = state.next_sender_msg_seq_num();
-                        if end_seq_no > next {
-                            end_seq_no = next;
-                        }
-                        info!(
-                            "Received implicit ResendRequest via Logon FROM: {next_expected_msg_seq_num}, \
-                             TO: {next_sender_msg_num_at_logon_received} will be reset"
-                        );
-                        self.send_sequence_reset(next_expected_msg_seq_num, end_seq_no);
-                    } else {
-                        // resend missed messages
-                        info!(
-                            "Received implicit ResendRequest via Logon FROM: {next_expected_msg_seq_num} \
-                             TO: {next_sender_msg_num_at_logon_received} will be resent"
-                        );
-                        self.resend_range(&mut state, next_expected_msg_seq_num, end_seq_no)
-                    }
+                    // resend missed messages (or gap fill when messages aren't persisted)
+                    info!(
+                        "Received implicit ResendRequest via Logon FROM: {next_expected_msg_seq_num} \
+                         TO: {next_sender_msg_num_at_logon_received} will be resent"
+                    );
+                    self.resend_range(
+                        next_expected_msg_seq_num,
+                        next_sender_msg_num_at_logon_received,
+                    )
+                    .await;
                 }
             }
         }
 
-        if Self::is_logged_on(&state) {
-            drop(state);
+        if is_logged_on {
             self.emitter
                 .send(FixEventInternal::Logon(
                     self.session_settings.session_id.clone(),
@@ -1001,36 +1102,59 @@ impl<S: MessagesStorage> Session<S> {
         level = "trace",
         skip_all,
         fields(
-            msg_seq_num = msg.header.msg_seq_num,
+            msg_seq_num = msg.header().msg_seq_num(),
             msg_type = ?msg.msg_type()
             )
         )]
     #[expect(clippy::await_holding_refcell_ref)]
     // Make sure `state` is dropped before await points, see
     // https://github.com/rust-lang/rust-clippy/issues/6353
-    async fn on_message_in_impl(&self, msg: Box<FixtMessage>) -> Option<DisconnectReason> {
-        let msg_type = msg.header.msg_type;
-        let msg_seq_num = msg.header.msg_seq_num;
-        trace!(msg_type = format!("{msg_type:?}<{}>", msg_type.as_fix_str()));
-
-        let result = match *msg.body {
-            Message::Heartbeat(ref _heartbeat) => self.on_heartbeat(msg).await,
-            Message::TestRequest(ref _test_request) => self.on_test_request(msg).await,
-            Message::ResendRequest(ref _resend_request) => self.on_resend_request(msg).await,
-            Message::Reject(ref _reject) => self.on_reject(msg).await,
-            Message::SequenceReset(ref _sequence_reset) => self.on_sequence_reset(msg).await,
-            Message::Logout(ref _logout) => match self.on_logout(msg).await {
+    async fn on_message_in_impl(&self, msg: Box<M>) -> Option<DisconnectReason> {
+        let msg_type = M::msg_type_as_fix_str(msg.msg_type());
+        let msg_seq_num = msg.header().msg_seq_num();
+        trace!(msg_type = format!("{:?}<{msg_type}>", msg.msg_type()));
+
+        let result = match msg.to_admin() {
+            Some(AdminMessage::Heartbeat { test_req_id }) => {
+                self.on_heartbeat(msg, test_req_id).await
+            }
+            Some(AdminMessage::TestRequest { test_req_id }) => {
+                self.on_test_request(msg, test_req_id).await
+            }
+            Some(AdminMessage::ResendRequest {
+                begin_seq_no,
+                end_seq_no,
+            }) => self.on_resend_request(msg, begin_seq_no, end_seq_no).await,
+            Some(AdminMessage::Reject { .. }) => self.on_reject(msg).await,
+            Some(AdminMessage::SequenceReset {
+                gap_fill_flag,
+                new_seq_no,
+            }) => self.on_sequence_reset(msg, gap_fill_flag, new_seq_no).await,
+            Some(AdminMessage::Logout { .. }) => match self.on_logout(msg).await {
                 Ok(disconnect_reason) => return Some(disconnect_reason),
                 Err(e) => Err(e),
             },
-            Message::Logon(ref _logon) => match self.on_logon(msg).await {
+            Some(AdminMessage::Logon {
+                reset_seq_num_flag,
+                heart_bt_int,
+                next_expected_msg_seq_num,
+                ..
+            }) => match self
+                .on_logon(
+                    msg,
+                    reset_seq_num_flag,
+                    heart_bt_int,
+                    next_expected_msg_seq_num,
+                )
+                .await
+            {
                 Ok(Some(disconnect_reason)) => {
                     return Some(disconnect_reason);
                 }
                 Ok(None) => Ok(()),
                 Err(e) => Err(e),
             },
-            _ => self
+            None => self
                 .verify(msg, true, true)
                 .await
                 .map(|_| self.state.borrow_mut().incr_next_target_msg_seq_num()),
@@ -1065,7 +1189,7 @@ impl<S: MessagesStorage> Session<S> {
                 let tag_as_i64 = tag.map(|t| t as i64);
                 self.send_reject(
                     &mut state,
-                    Some(msg_type.as_fix_str().to_owned()),
+                    Some(msg_type.to_owned()),
                     msg_seq_num,
                     reason,
                     if let Some(tag) = tag_as_i64 {
@@ -1080,7 +1204,7 @@ impl<S: MessagesStorage> Session<S> {
                     .send(FixEventInternal::DeserializeError(
                         self.session_id().clone(),
                         DeserializeError::Reject {
-                            msg_type: Some(msg_type.as_fix_str().to_fix_string()),
+                            msg_type: Some(msg_type.to_owned()),
                             seq_num: msg_seq_num,
                             tag: tag.map(|t| t as u16),
                             reason,
@@ -1146,7 +1270,14 @@ impl<S: MessagesStorage> Session<S> {
         None
     }
 
-    pub async fn on_message_in(&self, msg: Box<FixtMessage>) -> Option<DisconnectReason> {
+    /// Process message received from counterparty, `raw` are its bytes
+    /// as received, persisted when `SessionSettings::persist_inbound` is set.
+    pub async fn on_message_in(&self, msg: Box<M>, raw: &[u8]) -> Option<DisconnectReason> {
+        if self.session_settings.persist_inbound {
+            self.state
+                .borrow_mut()
+                .store_inbound(msg.header().msg_seq_num(), raw);
+        }
         if let Some(disconnect_reason) = self.on_message_in_impl(msg).await {
             return Some(disconnect_reason);
         }
@@ -1155,9 +1286,12 @@ impl<S: MessagesStorage> Session<S> {
                 break;
             };
 
-            info!("Processing queued message {}", msg.header.msg_seq_num);
+            info!("Processing queued message {}", msg.header().msg_seq_num());
 
-            if matches!(msg.msg_type(), MsgType::Logon | MsgType::ResendRequest) {
+            if matches!(
+                msg.admin_msg_type(),
+                Some(AdminMsgType::Logon | AdminMsgType::ResendRequest)
+            ) {
                 // Logon and ResendRequest processing has already been done,
                 // just increment the target sequence nummber.
                 self.state.borrow_mut().incr_next_target_msg_seq_num();
@@ -1168,7 +1302,10 @@ impl<S: MessagesStorage> Session<S> {
         None
     }
 
-    pub async fn on_message_out(&self, msg: Box<FixtMessage>) -> Option<Box<FixtMessage>> {
+    /// Let application decide about outgoing message. Returns message
+    /// to write, `ResponderMsg::GapFill` holds SequenceReset-GapFill<4>
+    /// replacing the original message, `None` when message is discarded.
+    pub(crate) async fn on_message_out(&self, msg: Box<M>) -> Option<ResponderMsg<M>> {
         let (sender, receiver) = tokio::sync::oneshot::channel();
         match msg.msg_cat() {
             MsgCat::Admin => {
@@ -1179,7 +1316,13 @@ impl<S: MessagesStorage> Session<S> {
                     ))
                     .await;
                 // TODO: maybe change unwrap() to None ?
-                Some(receiver.await.unwrap())
+                match receiver.await.unwrap() {
+                    // Responder is not exposed for admin messages,
+                    // so they are never changed to gap fill
+                    ResponderMsg::Send(msg) | ResponderMsg::GapFill(msg) => {
+                        Some(ResponderMsg::Send(msg))
+                    }
+                }
             }
             MsgCat::App => {
                 self.emitter
@@ -1189,26 +1332,57 @@ impl<S: MessagesStorage> Session<S> {
                     ))
                     .await;
                 match receiver.await {
-                    Ok(msg) => Some(msg),
-                    Err(_gap_fill) => {
-                        // TODO: GAP FILL!
-                        // let mut header = self.new_header(MsgType::SequenceReset);
-                        // header.msg_seq_num = msg.header.msg_seq_num;
-                        // Ok(Some(Box::new(FixtMessage {
-                        //     header,
-                        //     body: Message::SequenceReset(SequenceReset {
-                        //         gap_fill_flag: Some(true),
-                        //         new_seq_no: msg.header.msg_seq_num + 1,
-                        //     }),
-                        //     trailer: self.new_trailer(),
-                        // })))
-                        None
-                    } // TODO: Err(_no_gap_fill) => None,
+                    Ok(ResponderMsg::Send(msg)) => Some(ResponderMsg::Send(msg)),
+                    Ok(ResponderMsg::GapFill(msg)) => {
+                        Some(ResponderMsg::GapFill(self.change_to_gap_fill(msg).await))
+                    }
+                    Err(_do_not_send) => None,
                 }
             }
         }
     }
 
+    /// Replace application message with SequenceReset-GapFill<4>,
+    /// keeping its header (MsgSeqNum<34>, PossDupFlag<43> etc.).
+    async fn change_to_gap_fill(&self, msg: Box<M>) -> Box<M> {
+        let seq_num = msg.header().msg_seq_num();
+        info!("Message {:?}/{seq_num} changed to gap fill", msg.msg_type());
+        let mut gap_fill = M::from_admin(AdminMessage::SequenceReset {
+            gap_fill_flag: Some(true),
+            new_seq_no: seq_num + 1,
+        });
+        *gap_fill.header_mut() = msg.header().clone();
+        gap_fill.update_msg_type();
+        self.emitter
+            .send(FixEventInternal::AppMsgGapFilled(
+                self.session_settings.session_id.clone(),
+                msg,
+            ))
+            .await;
+        gap_fill
+    }
+
+    pub(crate) async fn on_message_throttled(&self, msg: &M, delay: Duration) {
+        debug!("Message {:?} throttled for {delay:?}", msg.msg_type());
+        self.emitter
+            .send(FixEventInternal::MsgThrottled(
+                self.session_settings.session_id.clone(),
+                msg.msg_type(),
+                delay,
+            ))
+            .await;
+    }
+
+    pub(crate) async fn on_message_throttle_rejected(&self, msg: Box<M>) {
+        warn!("Message {:?} rejected by throttle", msg.msg_type());
+        self.emitter
+            .send(FixEventInternal::MsgThrottleRejected(
+                self.session_settings.session_id.clone(),
+                msg,
+            ))
+            .await;
+    }
+
     pub async fn on_deserialize_error(&self, error: DeserializeError) -> Option<DisconnectReason> {
         trace!("on_deserialize_error");
 
@@ -1271,30 +1445,44 @@ impl<S: MessagesStorage> Session<S> {
         }
         state.set_input_timoeut_cnt(new_timeout_cnt);
 
-        self.send(Box::new(Message::TestRequest(TestRequest {
-            // Use current time as TestReqId as recommended in FIX Session
-            // Protocol (FIX) Version 1.1 Errata March 2008
-            test_req_id: FixString::from_ascii_lossy(
-                format!("{}", Utc::now().format("%Y%m%d-%H:%M:%S.%f")).into_bytes(),
-            ),
-        })));
+        // Use current time as TestReqId as recommended in FIX Session
+        // Protocol (FIX) Version 1.1 Errata March 2008
+        let test_req_id = FixString::from_ascii_lossy(
+            format!("{}", Utc::now().format("%Y%m%d-%H:%M:%S.%f")).into_bytes(),
+        );
+        state.set_test_request(Some(test_req_id.clone()));
+
+        self.send(AdminMessage::TestRequest { test_req_id });
 
         false
     }
 
     pub async fn on_out_timeout(self: &Rc<Self>) {
         trace!("on_out_timeout");
-        self.send(Box::new(Message::Heartbeat(Heartbeat {
-            test_req_id: None,
-        })));
+        self.send(AdminMessage::Heartbeat { test_req_id: None });
     }
 
+    /// Heartbeat interval negotiated during logon.
+    ///
+    /// `HeartBtInt<108>=0` (no heartbeats) is not supported, configured
+    /// interval is used then.
     pub fn heartbeat_interval(&self) -> Duration {
-        // TODO: logon.heartbeat_interval, value from settings is for n8 only (implement as Reject
-        // on Logon)
+        match self.state.borrow().heart_bt_int() {
+            heart_bt_int @ 1.. => Duration::from_secs(heart_bt_int as u64),
+            _ => self.settings.heartbeat_interval,
+        }
+    }
 
-        //let inbound_test_request_timeout_duration =
-        //    self.settings.heartbeat_interval + NO_INBOUND_TIMEOUT_PADDING;
-        self.settings.heartbeat_interval
+    fn is_heart_bt_int_valid(&self, heart_bt_int: Int) -> bool {
+        let Ok(heartbeat_interval) = u64::try_from(heart_bt_int).map(Duration::from_secs) else {
+            return false;
+        };
+        self.settings
+            .min_heartbeat_interval
+            .is_none_or(|min| heartbeat_interval >= min)
+            && self
+                .settings
+                .max_heartbeat_interval
+                .is_none_or(|max| heartbeat_interval <= max)
     }
 }
@@ -2,7 +2,7 @@ use core::fmt;
 
 use easyfix_messages::{
     fields::{FixStr, FixString},
-    messages::{FixtMessage, Header},
+    session::{SessionHeader, SessionMessage},
 };
 use serde::Deserialize;
 
@@ -60,27 +60,27 @@ impl SessionId {
         }
     }
 
-    pub fn from_input_msg(msg: &FixtMessage) -> SessionId {
-        SessionId::from_input_header(&msg.header)
+    pub fn from_input_msg(msg: &impl SessionMessage) -> SessionId {
+        SessionId::from_input_header(msg.header())
     }
 
-    pub fn from_input_header(header: &Header) -> SessionId {
+    pub fn from_input_header(header: &impl SessionHeader) -> SessionId {
         SessionId::new(
-            header.begin_string.clone(),
-            header.target_comp_id.clone(),
-            header.sender_comp_id.clone(),
+            header.begin_string().to_owned(),
+            header.target_comp_id().to_owned(),
+            header.sender_comp_id().to_owned(),
         )
     }
 
-    pub fn from_output_msg(msg: &FixtMessage) -> SessionId {
-        SessionId::from_output_header(&msg.header)
+    pub fn from_output_msg(msg: &impl SessionMessage) -> SessionId {
+        SessionId::from_output_header(msg.header())
     }
 
-    pub fn from_output_header(header: &Header) -> SessionId {
+    pub fn from_output_header(header: &impl SessionHeader) -> SessionId {
         SessionId::new(
-            header.begin_string.clone(),
-            header.sender_comp_id.clone(),
-            header.target_comp_id.clone(),
+            header.begin_string().to_owned(),
+            header.sender_comp_id().to_owned(),
+            header.target_comp_id().to_owned(),
         )
     }
 
@@ -1,26 +1,29 @@
-use std::{collections::BTreeMap, ops::RangeInclusive};
+use std::{collections::BTreeMap, ops::RangeInclusive, time::Duration};
 
+use chrono::{DateTime, Utc};
 use easyfix_messages::{
-    fields::{Int, SeqNum},
+    fields::{FixString, Int, SeqNum},
     messages::FixtMessage,
+    session::{SessionHeader, SessionMessage},
 };
+use futures::{future::LocalBoxFuture, stream::LocalBoxStream};
 use tokio::time::Instant;
 
 use crate::messages_storage::MessagesStorage;
 
 #[derive(Debug)]
-struct Messages(BTreeMap<SeqNum, Box<FixtMessage>>);
+struct Messages<M>(BTreeMap<SeqNum, Box<M>>);
 
-impl Messages {
-    fn new() -> Messages {
+impl<M> Messages<M> {
+    fn new() -> Messages<M> {
         Messages(BTreeMap::new())
     }
 
-    fn enqueue(&mut self, seq_num: SeqNum, msg: Box<FixtMessage>) {
+    fn enqueue(&mut self, seq_num: SeqNum, msg: Box<M>) {
         self.0.insert(seq_num, msg);
     }
 
-    fn retrieve(&mut self, seq_num: SeqNum) -> Option<Box<FixtMessage>> {
+    fn retrieve(&mut self, seq_num: SeqNum) -> Option<Box<M>> {
         self.0.remove(&seq_num)
     }
 
@@ -30,7 +33,7 @@ impl Messages {
 }
 
 #[derive(Debug)]
-pub(crate) struct State<S> {
+pub(crate) struct State<S, M = FixtMessage> {
     enabled: bool,
     received_logon: bool,
     sent_logout: bool,
@@ -38,7 +41,11 @@ pub(crate) struct State<S> {
     sent_reset: bool,
     reset_received: bool,
     initiate: bool,
-    test_request: Int,
+    /// TestReqID<112> of the last TestRequest<1> not answered yet,
+    /// with the time it was sent.
+    test_request: Option<(FixString, Instant)>,
+    /// Time between the last answered TestRequest<1> and its Heartbeat<0>.
+    round_trip_time: Option<Duration>,
     resend_range: Option<RangeInclusive<SeqNum>>,
     heart_bt_int: Int,
     last_sent_time: Instant,
@@ -46,6 +53,9 @@ pub(crate) struct State<S> {
     input_timeout_cnt: u32,
 
     disconnected: bool,
+    /// Logon<A> was received since connection was established, unlike
+    /// `received_logon` it's not cleared on disconnection.
+    logon_completed: bool,
 
     /// If this is anything other than zero it's the value of
     /// the 789/NextExpectedMsgSeqNum tag in the last Logon message sent.
@@ -55,12 +65,14 @@ pub(crate) struct State<S> {
     /// This value is used to populate the resendRange if necessary.
     next_expected_msg_seq_num: SeqNum,
 
-    queue: Messages,
+    queue: Messages<M>,
     messages_storage: S,
+    /// Used when storage doesn't track creation time.
+    creation_time: DateTime<Utc>,
 }
 
-impl<S: MessagesStorage> State<S> {
-    pub(crate) fn new(messages_storage: S) -> State<S> {
+impl<S: MessagesStorage, M: SessionMessage> State<S, M> {
+    pub(crate) fn new(messages_storage: S) -> State<S, M> {
         State {
             enabled: true,
             received_logon: false,
@@ -69,16 +81,19 @@ impl<S: MessagesStorage> State<S> {
             sent_reset: false,
             reset_received: false,
             initiate: false,
-            test_request: 0,
+            test_request: None,
+            round_trip_time: None,
             resend_range: None,
             heart_bt_int: 10,
             last_sent_time: Instant::now(),
             last_received_time: Instant::now(),
             input_timeout_cnt: 0,
             disconnected: true,
+            logon_completed: false,
             next_expected_msg_seq_num: 0,
             queue: Messages::new(),
             messages_storage,
+            creation_time: Utc::now(),
         }
     }
 
@@ -132,8 +147,40 @@ impl<S: MessagesStorage> State<S> {
         self.initiate
     }
 
-    pub fn set_test_request(&mut self, test_request: Int) {
-        self.test_request = test_request;
+    pub fn set_initiate(&mut self, initiate: bool) {
+        self.initiate = initiate;
+    }
+
+    pub fn test_request(&self) -> Option<&FixString> {
+        self.test_request
+            .as_ref()
+            .map(|(test_req_id, _)| test_req_id)
+    }
+
+    pub fn set_test_request(&mut self, test_req_id: Option<FixString>) {
+        self.test_request = test_req_id.map(|test_req_id| (test_req_id, Instant::now()));
+    }
+
+    /// Match Heartbeat<0> against outstanding TestRequest<1>.
+    ///
+    /// When `test_req_id` matches, the request is completed, round-trip
+    /// time is measured and input timeout counter is cleared.
+    pub fn complete_test_request(&mut self, test_req_id: &FixString) -> bool {
+        match self.test_request.take() {
+            Some((expected, sent_time)) if expected == *test_req_id => {
+                self.round_trip_time = Some(sent_time.elapsed());
+                self.input_timeout_cnt = 0;
+                true
+            }
+            test_request => {
+                self.test_request = test_request;
+                false
+            }
+        }
+    }
+
+    pub fn round_trip_time(&self) -> Option<Duration> {
+        self.round_trip_time
     }
 
     pub fn set_resend_range(&mut self, resend_range: Option<RangeInclusive<SeqNum>>) {
@@ -186,11 +233,11 @@ impl<S: MessagesStorage> State<S> {
         self.next_expected_msg_seq_num != 0
     }
 
-    pub fn enqueue_msg(&mut self, msg: Box<FixtMessage>) {
-        self.queue.enqueue(msg.header.msg_seq_num, msg);
+    pub fn enqueue_msg(&mut self, msg: Box<M>) {
+        self.queue.enqueue(msg.header().msg_seq_num(), msg);
     }
 
-    pub fn retrieve_msg(&mut self) -> Option<Box<FixtMessage>> {
+    pub fn retrieve_msg(&mut self) -> Option<Box<M>> {
         self.queue.retrieve(self.next_target_msg_seq_num())
     }
 
@@ -198,7 +245,10 @@ impl<S: MessagesStorage> State<S> {
         self.queue.clear();
     }
 
-    pub fn fetch_range(&mut self, range: RangeInclusive<SeqNum>) -> Vec<Vec<u8>> {
+    pub fn fetch_range(
+        &mut self,
+        range: RangeInclusive<SeqNum>,
+    ) -> LocalBoxStream<'static, Vec<u8>> {
         self.messages_storage.fetch_range(range)
     }
 
@@ -206,6 +256,20 @@ impl<S: MessagesStorage> State<S> {
         self.messages_storage.store(seq_num, data);
     }
 
+    pub fn creation_time(&self) -> DateTime<Utc> {
+        self.messages_storage
+            .creation_time()
+            .unwrap_or(self.creation_time)
+    }
+
+    pub fn flush(&mut self) -> LocalBoxFuture<'static, ()> {
+        self.messages_storage.flush()
+    }
+
+    pub fn store_inbound(&mut self, seq_num: SeqNum, data: &[u8]) {
+        self.messages_storage.store_inbound(seq_num, data);
+    }
+
     pub fn next_sender_msg_seq_num(&self) -> SeqNum {
         self.messages_storage.next_sender_msg_seq_num()
     }
@@ -232,6 +296,7 @@ impl<S: MessagesStorage> State<S> {
 
     pub fn reset(&mut self) {
         self.messages_storage.reset();
+        self.creation_time = Utc::now();
     }
 
     pub fn disconnected(&self) -> bool {
@@ -242,6 +307,14 @@ impl<S: MessagesStorage> State<S> {
         self.disconnected = disconnected;
     }
 
+    pub fn logon_completed(&self) -> bool {
+        self.logon_completed
+    }
+
+    pub fn set_logon_completed(&mut self, logon_completed: bool) {
+        self.logon_completed = logon_completed;
+    }
+
     pub fn input_timoeut_cnt(&self) -> u32 {
         self.input_timeout_cnt
     }
@@ -250,3 +323,26 @@ impl<S: MessagesStorage> State<S> {
         self.input_timeout_cnt = timoeut_cnt;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::messages_storage::NullStorage;
+
+    #[test]
+    fn only_matching_heartbeat_completes_test_request() {
+        let mut state: State<_> = State::new(NullStorage::new());
+        state.set_input_timoeut_cnt(1);
+        state.set_test_request(Some(FixString::from_ascii_lossy(b"1".to_vec())));
+
+        assert!(!state.complete_test_request(&FixString::from_ascii_lossy(b"2".to_vec())));
+        assert_eq!(state.input_timoeut_cnt(), 1);
+        assert!(state.test_request().is_some());
+        assert_eq!(state.round_trip_time(), None);
+
+        assert!(state.complete_test_request(&FixString::from_ascii_lossy(b"1".to_vec())));
+        assert_eq!(state.input_timoeut_cnt(), 0);
+        assert!(state.test_request().is_none());
+        assert!(state.round_trip_time().is_some());
+    }
+}
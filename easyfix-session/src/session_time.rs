@@ -0,0 +1,295 @@
+use std::ops::RangeInclusive;
+
+use chrono::{DateTime, Datelike, Days, NaiveDateTime, NaiveTime, Offset, TimeZone, Utc, Weekday};
+use chrono_tz::Tz;
+use serde::Deserialize;
+
+/// Session schedule.
+///
+/// Session period starts at `start_time` and ends at `end_time` every day,
+/// or, when both `start_day` and `end_day` are set, once a week. Times are
+/// interpreted in `time_zone` (UTC by default). Period may cross midnight
+/// (or end of week), i.e. `start_time` 22:00 and `end_time` 06:00 define
+/// period lasting from 22:00 to 06:00 next day. When `start_time` equals
+/// `end_time` (and days are the same), period lasts whole day (week).
+///
+/// Setting only one of `start_day` and `end_day` is rejected when
+/// deserializing.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(try_from = "SessionTimeDef")]
+pub struct SessionTime {
+    pub start_time: NaiveTime,
+    pub end_time: NaiveTime,
+    pub start_day: Option<Weekday>,
+    pub end_day: Option<Weekday>,
+    pub time_zone: Tz,
+}
+
+/// Deserialized `SessionTime`, before it's validated.
+#[derive(Deserialize)]
+struct SessionTimeDef {
+    #[serde(alias = "start")]
+    start_time: NaiveTime,
+    #[serde(alias = "end")]
+    end_time: NaiveTime,
+    #[serde(default)]
+    start_day: Option<Weekday>,
+    #[serde(default)]
+    end_day: Option<Weekday>,
+    #[serde(default = "default_time_zone")]
+    time_zone: Tz,
+}
+
+impl TryFrom<SessionTimeDef> for SessionTime {
+    type Error = &'static str;
+
+    fn try_from(def: SessionTimeDef) -> Result<SessionTime, Self::Error> {
+        if def.start_day.is_some() != def.end_day.is_some() {
+            return Err("both `start_day` and `end_day` must be set for weekly session");
+        }
+        Ok(SessionTime {
+            start_time: def.start_time,
+            end_time: def.end_time,
+            start_day: def.start_day,
+            end_day: def.end_day,
+            time_zone: def.time_zone,
+        })
+    }
+}
+
+fn default_time_zone() -> Tz {
+    Tz::UTC
+}
+
+impl From<RangeInclusive<NaiveTime>> for SessionTime {
+    fn from(range: RangeInclusive<NaiveTime>) -> SessionTime {
+        let (start_time, end_time) = range.into_inner();
+        SessionTime {
+            start_time,
+            end_time,
+            start_day: None,
+            end_day: None,
+            time_zone: Tz::UTC,
+        }
+    }
+}
+
+impl SessionTime {
+    /// Most recent session period started before (or at) `time`, in local
+    /// time of session time zone.
+    fn period(&self, time: DateTime<Utc>) -> (NaiveDateTime, NaiveDateTime) {
+        let now = time.with_timezone(&self.time_zone).naive_local();
+        match (self.start_day, self.end_day) {
+            (Some(start_day), Some(end_day)) => {
+                let days_since_start = now.weekday().days_since(start_day);
+                let mut start =
+                    (now.date() - Days::new(days_since_start.into())).and_time(self.start_time);
+                if start > now {
+                    start = start - Days::new(7);
+                }
+                let mut end = (start.date() + Days::new(end_day.days_since(start_day).into()))
+                    .and_time(self.end_time);
+                if end <= start {
+                    end = end + Days::new(7);
+                }
+                (start, end)
+            }
+            _ => {
+                let mut start = now.date().and_time(self.start_time);
+                if start > now {
+                    start = start - Days::new(1);
+                }
+                let mut end = start.date().and_time(self.end_time);
+                if end <= start {
+                    end = end + Days::new(1);
+                }
+                (start, end)
+            }
+        }
+    }
+
+    fn to_utc(&self, time: NaiveDateTime) -> DateTime<Utc> {
+        match self.time_zone.from_local_datetime(&time).earliest() {
+            Some(time) => time.with_timezone(&Utc),
+            // Time skipped due to DST change, use offset from before the change
+            None => (time - self.time_zone.offset_from_utc_datetime(&time).fix()).and_utc(),
+        }
+    }
+
+    /// Check if `time` is inside session period.
+    pub fn is_session_time(&self, time: DateTime<Utc>) -> bool {
+        let (_, end) = self.period(time);
+        time.with_timezone(&self.time_zone).naive_local() <= end
+    }
+
+    /// Start of session period `time` belongs to, `None` when `time`
+    /// is outside of session period.
+    pub fn session_start(&self, time: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.is_session_time(time)
+            .then(|| self.to_utc(self.period(time).0))
+    }
+
+    /// End of session period `time` belongs to, `None` when `time`
+    /// is outside of session period.
+    pub fn session_end(&self, time: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.is_session_time(time)
+            .then(|| self.to_utc(self.period(time).1))
+    }
+
+    /// Start of the first session period not ended before `time`,
+    /// `time` itself when it's inside session period.
+    pub fn next_session_start(&self, time: DateTime<Utc>) -> DateTime<Utc> {
+        if self.is_session_time(time) {
+            return time;
+        }
+        let (start, _) = self.period(time);
+        let period_days = match (self.start_day, self.end_day) {
+            (Some(_), Some(_)) => 7,
+            _ => 1,
+        };
+        self.to_utc(start + Days::new(period_days))
+    }
+
+    /// Check if both timestamps belong to the same session period.
+    pub fn is_same_session(&self, time1: DateTime<Utc>, time2: DateTime<Utc>) -> bool {
+        match (self.session_start(time1), self.session_start(time2)) {
+            (Some(start1), Some(start2)) => start1 == start2,
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(time: &str) -> NaiveTime {
+        NaiveTime::parse_from_str(time, "%H:%M:%S").unwrap()
+    }
+
+    fn utc(datetime: &str) -> DateTime<Utc> {
+        NaiveDateTime::parse_from_str(datetime, "%Y-%m-%d %H:%M:%S")
+            .unwrap()
+            .and_utc()
+    }
+
+    #[test]
+    fn daily() {
+        let session_time = SessionTime::from(time("08:00:00")..=time("17:00:00"));
+        assert!(!session_time.is_session_time(utc("2024-06-03 07:59:59")));
+        assert!(session_time.is_session_time(utc("2024-06-03 08:00:00")));
+        assert!(session_time.is_session_time(utc("2024-06-03 17:00:00")));
+        assert!(!session_time.is_session_time(utc("2024-06-03 17:00:01")));
+        assert_eq!(
+            session_time.session_end(utc("2024-06-03 12:00:00")),
+            Some(utc("2024-06-03 17:00:00"))
+        );
+        assert!(
+            session_time.is_same_session(utc("2024-06-03 08:00:00"), utc("2024-06-03 16:00:00"))
+        );
+        assert!(
+            !session_time.is_same_session(utc("2024-06-03 08:00:00"), utc("2024-06-04 09:00:00"))
+        );
+        assert_eq!(
+            session_time.next_session_start(utc("2024-06-03 07:00:00")),
+            utc("2024-06-03 08:00:00")
+        );
+        assert_eq!(
+            session_time.next_session_start(utc("2024-06-03 18:00:00")),
+            utc("2024-06-04 08:00:00")
+        );
+        assert_eq!(
+            session_time.next_session_start(utc("2024-06-03 12:00:00")),
+            utc("2024-06-03 12:00:00")
+        );
+    }
+
+    #[test]
+    fn daily_over_midnight() {
+        let session_time = SessionTime::from(time("22:00:00")..=time("06:00:00"));
+        assert!(session_time.is_session_time(utc("2024-06-03 23:00:00")));
+        assert!(session_time.is_session_time(utc("2024-06-04 05:00:00")));
+        assert!(!session_time.is_session_time(utc("2024-06-04 12:00:00")));
+        assert!(
+            session_time.is_same_session(utc("2024-06-03 23:00:00"), utc("2024-06-04 05:00:00"))
+        );
+        assert_eq!(
+            session_time.session_start(utc("2024-06-04 05:00:00")),
+            Some(utc("2024-06-03 22:00:00"))
+        );
+    }
+
+    #[test]
+    fn whole_day() {
+        let session_time = SessionTime::from(time("17:00:00")..=time("17:00:00"));
+        assert!(session_time.is_session_time(utc("2024-06-03 12:00:00")));
+        assert!(
+            session_time.is_same_session(utc("2024-06-02 17:00:00"), utc("2024-06-03 16:59:59"))
+        );
+        assert!(
+            !session_time.is_same_session(utc("2024-06-03 16:59:59"), utc("2024-06-03 17:00:01"))
+        );
+    }
+
+    #[test]
+    fn weekly_with_time_zone() {
+        // Sunday 17:00 to Friday 17:00 New York time (EDT, UTC-4)
+        let session_time = SessionTime {
+            start_time: time("17:00:00"),
+            end_time: time("17:00:00"),
+            start_day: Some(Weekday::Sun),
+            end_day: Some(Weekday::Fri),
+            time_zone: chrono_tz::America::New_York,
+        };
+        // Saturday
+        assert!(!session_time.is_session_time(utc("2024-06-08 12:00:00")));
+        // Sunday 16:00 EDT
+        assert!(!session_time.is_session_time(utc("2024-06-09 20:00:00")));
+        // Sunday 17:00 EDT
+        assert!(session_time.is_session_time(utc("2024-06-09 21:00:00")));
+        // Wednesday
+        assert!(session_time.is_session_time(utc("2024-06-12 12:00:00")));
+        assert_eq!(
+            session_time.session_end(utc("2024-06-12 12:00:00")),
+            Some(utc("2024-06-14 21:00:00"))
+        );
+        // Friday 17:00:01 EDT
+        assert!(!session_time.is_session_time(utc("2024-06-14 21:00:01")));
+        assert_eq!(
+            session_time.next_session_start(utc("2024-06-14 21:00:01")),
+            utc("2024-06-16 21:00:00")
+        );
+        assert!(
+            !session_time.is_same_session(utc("2024-06-07 12:00:00"), utc("2024-06-10 12:00:00"))
+        );
+    }
+
+    #[test]
+    fn deserialize() {
+        let session_time: SessionTime = serde_json::from_str(
+            r#"{"start": "17:00:00", "end": "17:00:00", "start_day": "Sun", "end_day": "Fri",
+                "time_zone": "America/New_York"}"#,
+        )
+        .unwrap();
+        assert_eq!(session_time.start_day, Some(Weekday::Sun));
+        assert_eq!(session_time.end_day, Some(Weekday::Fri));
+        assert_eq!(session_time.time_zone, chrono_tz::America::New_York);
+
+        let session_time: SessionTime =
+            serde_json::from_str(r#"{"start_time": "08:00:00", "end_time": "17:00:00"}"#).unwrap();
+        assert_eq!(
+            session_time,
+            SessionTime::from(time("08:00:00")..=time("17:00:00"))
+        );
+
+        // Weekly session requires both days
+        assert!(serde_json::from_str::<SessionTime>(
+            r#"{"start": "17:00:00", "end": "17:00:00", "start_day": "Sun"}"#
+        )
+        .is_err());
+        assert!(serde_json::from_str::<SessionTime>(
+            r#"{"start": "17:00:00", "end": "17:00:00", "end_day": "Fri"}"#
+        )
+        .is_err());
+    }
+}
@@ -1,11 +1,11 @@
-use std::ops::RangeInclusive;
+use std::{collections::HashMap, num::NonZeroU32, ops::RangeInclusive};
 
 use chrono::NaiveTime;
-use easyfix_messages::fields::FixString;
+use easyfix_messages::{deserializer::UnknownFields, fields::FixString};
 use serde::{Deserialize, Deserializer};
 use tokio::time::Duration;
 
-use crate::session_id::SessionId;
+use crate::{session_id::SessionId, session_time::SessionTime};
 
 fn duration_from_seconds<'de, D>(deserializer: D) -> Result<Duration, D::Error>
 where
@@ -14,6 +14,13 @@ where
     Ok(Duration::from_secs(u64::deserialize(deserializer)?))
 }
 
+fn option_duration_from_seconds<'de, D>(deserializer: D) -> Result<Option<Duration>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(Option::<u64>::deserialize(deserializer)?.map(Duration::from_secs))
+}
+
 /// FIX Trading Port session configuration.
 #[derive(Clone, Debug, Deserialize)]
 pub struct Settings {
@@ -24,8 +31,18 @@ pub struct Settings {
     /// Timeout \[s\] for inbound/outbound messages. When reached, `TestRequest<1>`
     /// is sent when inbound message is missing or `Heartbeat<0>` is sent when
     /// outbound message is missing.
+    ///
+    /// Acceptor uses `HeartBtInt<108>` received in `Logon<A>` instead.
     #[serde(deserialize_with = "duration_from_seconds")]
     pub heartbeat_interval: Duration,
+    /// Lowest `HeartBtInt<108>` \[s\] accepted in `Logon<A>`, logon with
+    /// lower value is rejected.
+    #[serde(default, deserialize_with = "option_duration_from_seconds")]
+    pub min_heartbeat_interval: Option<Duration>,
+    /// Highest `HeartBtInt<108>` \[s\] accepted in `Logon<A>`, logon with
+    /// higher value is rejected.
+    #[serde(default, deserialize_with = "option_duration_from_seconds")]
+    pub max_heartbeat_interval: Option<Duration>,
     /// Timeout \[s\] for `Logon<A>` message, when reached, connection is dropped.
     #[serde(deserialize_with = "duration_from_seconds")]
     pub auto_disconnect_after_no_logon_received: Duration,
@@ -34,11 +51,83 @@ pub struct Settings {
     pub auto_disconnect_after_no_heartbeat: u32,
 }
 
+/// Initiator reconnection configuration.
+///
+/// Zero `initial_delay`, `multiplier` or `connect_timeout` is rejected
+/// when deserializing (and by `Initiator::start`), as initiator would
+/// reconnect in a tight loop.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(try_from = "ReconnectSettingsDef")]
+pub struct ReconnectSettings {
+    /// Delay \[s\] before reconnection, after connection is closed or when
+    /// all addresses failed for the first time.
+    pub initial_delay: Duration,
+    /// Maximum delay \[s\] between reconnection attempts.
+    pub max_delay: Duration,
+    /// Delay multiplier, applied every time all addresses failed.
+    pub multiplier: u32,
+    /// Timeout \[s\] of single connection attempt, 10 seconds by default.
+    pub connect_timeout: Duration,
+}
+
+/// Deserialized `ReconnectSettings`, before it's validated.
+#[derive(Deserialize)]
+struct ReconnectSettingsDef {
+    #[serde(deserialize_with = "duration_from_seconds")]
+    initial_delay: Duration,
+    #[serde(deserialize_with = "duration_from_seconds")]
+    max_delay: Duration,
+    multiplier: u32,
+    #[serde(
+        default = "default_connect_timeout",
+        deserialize_with = "duration_from_seconds"
+    )]
+    connect_timeout: Duration,
+}
+
+fn default_connect_timeout() -> Duration {
+    Duration::from_secs(10)
+}
+
+impl ReconnectSettings {
+    pub(crate) fn validate(&self) -> Result<(), &'static str> {
+        if self.initial_delay.is_zero() {
+            return Err("`initial_delay` must be greater than zero");
+        }
+        if self.multiplier < 1 {
+            return Err("`multiplier` must be at least 1");
+        }
+        if self.connect_timeout.is_zero() {
+            return Err("`connect_timeout` must be greater than zero");
+        }
+        Ok(())
+    }
+}
+
+impl TryFrom<ReconnectSettingsDef> for ReconnectSettings {
+    type Error = &'static str;
+
+    fn try_from(def: ReconnectSettingsDef) -> Result<ReconnectSettings, Self::Error> {
+        let reconnect_settings = ReconnectSettings {
+            initial_delay: def.initial_delay,
+            max_delay: def.max_delay,
+            multiplier: def.multiplier,
+            connect_timeout: def.connect_timeout,
+        };
+        reconnect_settings.validate()?;
+        Ok(reconnect_settings)
+    }
+}
+
 #[derive(Clone, Debug, Deserialize)]
 pub struct SessionSettings {
     pub session_id: SessionId,
-    // TODO: Optional
-    pub session_time: RangeInclusive<NaiveTime>,
+    /// Session schedule, when set, logons are accepted only inside session
+    /// period, session is logged out at the end of the period and sequence
+    /// numbers are reset when new period starts. Session is never closed
+    /// when not set.
+    #[serde(default)]
+    pub session_time: Option<SessionTime>,
     pub logon_time: RangeInclusive<NaiveTime>,
 
     pub send_redundant_resend_requests: bool,
@@ -63,6 +152,97 @@ pub struct SessionSettings {
     // Enable messages persistence.
     pub persist: bool,
 
+    // Enable inbound messages persistence.
+    #[serde(default)]
+    pub persist_inbound: bool,
+
     // Enable Logout<5> verification.
     pub verify_logout: bool,
+
+    /// Required identity (certificate Common Name) of TLS client,
+    /// connections from other clients are rejected.
+    #[serde(default)]
+    pub tls_client_identity: Option<String>,
+
+    /// Maximum number of messages waiting to be sent to counterparty
+    /// for `Sender::reserve` and `Sender::try_reserve` to reserve room
+    /// for another one. Messages queued with `Sender::send` (and by the
+    /// session itself) are counted too, but never rejected. Unlimited
+    /// when not set.
+    #[serde(default)]
+    pub outbound_queue_limit: Option<usize>,
+
+    /// Outbound messages rate limits, messages are not throttled when
+    /// not set.
+    #[serde(default)]
+    pub throttle: Option<ThrottleSettings>,
+
+    /// Handling of received fields undefined in dictionary (e.g. venue
+    /// specific tags): `Reject` (default), `Ignore` or `Keep` them in
+    /// `unknown_fields` of message and group entries.
+    #[serde(default)]
+    pub unknown_fields: UnknownFields,
+}
+
+/// Token bucket rate limit.
+#[derive(Clone, Debug, Deserialize)]
+pub struct RateLimit {
+    /// Rate of the limit, zero is rejected.
+    pub messages_per_second: NonZeroU32,
+    /// Maximum number of messages sent at once, `messages_per_second`
+    /// when not set.
+    #[serde(default)]
+    pub burst: Option<NonZeroU32>,
+}
+
+/// What to do with message exceeding rate limit.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+pub enum ThrottleAction {
+    /// Wait until message can be sent, `FixEvent::MsgThrottled` is emitted.
+    #[default]
+    Delay,
+    /// Drop the message, `FixEvent::MsgThrottleRejected` is emitted.
+    /// Administrative and resent messages are always delayed.
+    Reject,
+}
+
+/// Outbound messages throttle configuration.
+///
+/// Message is sent when it fits in all limits it's subject to, i.e.
+/// `app` or `admin` limit and limit of its MsgType<35>.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct ThrottleSettings {
+    /// Limit of application messages.
+    #[serde(default)]
+    pub app: Option<RateLimit>,
+    /// Limit of administrative messages.
+    #[serde(default)]
+    pub admin: Option<RateLimit>,
+    /// Limits of particular message types, keyed with MsgType<35> value.
+    #[serde(default)]
+    pub msg_types: HashMap<FixString, RateLimit>,
+    #[serde(default)]
+    pub action: ThrottleAction,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deserialize_reconnect_settings() {
+        let reconnect_settings: ReconnectSettings =
+            serde_json::from_str(r#"{"initial_delay": 1, "max_delay": 30, "multiplier": 2}"#)
+                .unwrap();
+        assert_eq!(reconnect_settings.initial_delay, Duration::from_secs(1));
+        assert_eq!(reconnect_settings.connect_timeout, Duration::from_secs(10));
+
+        for invalid in [
+            r#"{"initial_delay": 0, "max_delay": 30, "multiplier": 2}"#,
+            r#"{"initial_delay": 1, "max_delay": 30, "multiplier": 0}"#,
+            r#"{"initial_delay": 1, "max_delay": 30, "multiplier": 2, "connect_timeout": 0}"#,
+        ] {
+            assert!(serde_json::from_str::<ReconnectSettings>(invalid).is_err());
+        }
+    }
 }
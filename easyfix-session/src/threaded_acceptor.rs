@@ -0,0 +1,658 @@
+//! Multi-threaded acceptor.
+//!
+//! Sessions are single-threaded (see [Acceptor]), so to use all cores
+//! they are spread over worker threads, each running its own `LocalSet`.
+//! Every session is bound to one worker (by hash of `SessionId`),
+//! incoming connection is routed to the worker after its first message
+//! (Logon<A>) is read.
+
+use std::{
+    future::Future,
+    hash::{DefaultHasher, Hash, Hasher},
+    io,
+    net::SocketAddr,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    thread,
+    time::Duration,
+};
+
+use bytes::BytesMut;
+use easyfix_messages::{
+    fields::{FixString, SessionStatus},
+    messages::FixtMessage,
+    session::SessionMessage,
+};
+use futures::{
+    future::{self, BoxFuture, FutureExt},
+    Stream,
+};
+use pin_project::pin_project;
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite},
+    net::{TcpListener, TcpStream},
+    sync::{mpsc, oneshot},
+    task::{JoinHandle, LocalSet},
+};
+#[cfg(feature = "tls")]
+use tokio_rustls::{rustls::ServerConfig, TlsAcceptor};
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, warn};
+
+#[cfg(feature = "tls")]
+use crate::tls;
+use crate::{
+    acceptor::{Acceptor, LogonRejection, LogonRequest, PeerInfo, SessionInfo},
+    application::{events_channel, AsEvent, EventStream},
+    io::{read_session_id, time::timeout},
+    messages_storage::MessagesStorage,
+    session_id::SessionId,
+    settings::{SessionSettings, Settings},
+    Sender, NO_INBOUND_TIMEOUT_PADDING,
+};
+
+/// Command executed by worker thread on its state.
+pub(crate) type WorkerCommand<T> = Box<dyn FnOnce(&mut T) + Send>;
+
+type Command<S, M> = WorkerCommand<Acceptor<S, M>>;
+
+/// Thread-safe version of [DynamicSessionBuilder](crate::acceptor::DynamicSessionBuilder).
+pub type SyncDynamicSessionBuilder =
+    Arc<dyn Fn(&SessionId) -> Option<SessionSettings> + Send + Sync>;
+
+/// Thread-safe version of [Authenticator](crate::acceptor::Authenticator).
+pub type SyncAuthenticator =
+    Arc<dyn Fn(LogonRequest) -> BoxFuture<'static, Result<(), LogonRejection>> + Send + Sync>;
+
+/// Acceptor running sessions on multiple worker threads.
+///
+/// Unlike [Acceptor], it's `Send + Sync` and has to be started inside
+/// multi-threaded tokio runtime. Events from all workers are delivered
+/// through this acceptor stream, `Sender` received with
+/// `FixEvent::Logon` can be used from any task.
+#[pin_project]
+pub struct ThreadedAcceptor<S, M: SessionMessage = FixtMessage> {
+    settings: Settings,
+    workers: Arc<[mpsc::UnboundedSender<Command<S, M>>]>,
+    shutdown_token: CancellationToken,
+    #[pin]
+    event_stream: EventStream<M>,
+}
+
+/// Connection accepted by server task, moved to worker thread after its
+/// first message is read.
+trait RoutedStream: AsyncRead + Unpin + Send + 'static {
+    /// Form in which stream is moved to worker thread.
+    type Detached: Send + 'static;
+
+    fn detach(self) -> Result<Self::Detached, io::Error>;
+
+    /// Split stream on worker thread.
+    #[allow(clippy::type_complexity)]
+    fn attach(
+        detached: Self::Detached,
+    ) -> Result<
+        (
+            impl AsyncRead + Unpin + 'static,
+            impl AsyncWrite + Unpin + 'static,
+        ),
+        io::Error,
+    >;
+}
+
+impl RoutedStream for TcpStream {
+    type Detached = std::net::TcpStream;
+
+    // Stream is deregistered from runtime of server task and registered
+    // in worker runtime
+    fn detach(self) -> Result<Self::Detached, io::Error> {
+        self.set_nodelay(true)?;
+        self.into_std()
+    }
+
+    fn attach(
+        detached: Self::Detached,
+    ) -> Result<
+        (
+            impl AsyncRead + Unpin + 'static,
+            impl AsyncWrite + Unpin + 'static,
+        ),
+        io::Error,
+    > {
+        Ok(TcpStream::from_std(detached)?.into_split())
+    }
+}
+
+// TLS stream can't be deregistered, it stays driven by runtime
+// of server task
+#[cfg(feature = "tls")]
+impl RoutedStream for tokio_rustls::server::TlsStream<TcpStream> {
+    type Detached = Self;
+
+    fn detach(self) -> Result<Self::Detached, io::Error> {
+        Ok(self)
+    }
+
+    fn attach(
+        detached: Self::Detached,
+    ) -> Result<
+        (
+            impl AsyncRead + Unpin + 'static,
+            impl AsyncWrite + Unpin + 'static,
+        ),
+        io::Error,
+    > {
+        Ok(tokio::io::split(detached))
+    }
+}
+
+pub(crate) fn worker_index(session_id: &SessionId, workers_cnt: usize) -> usize {
+    let mut hasher = DefaultHasher::new();
+    session_id.hash(&mut hasher);
+    (hasher.finish() % workers_cnt as u64) as usize
+}
+
+/// Spawn worker thread, running commands on state built by `build`
+/// (on the worker thread, so it doesn't have to be `Send`).
+pub(crate) fn spawn_worker<T: 'static>(
+    index: usize,
+    build: impl FnOnce() -> T + Send + 'static,
+) -> Result<mpsc::UnboundedSender<WorkerCommand<T>>, io::Error> {
+    let (commands_tx, mut commands_rx) = mpsc::unbounded_channel::<WorkerCommand<T>>();
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+    thread::Builder::new()
+        .name(format!("easyfix-worker-{index}"))
+        .spawn(move || {
+            let mut worker = build();
+            LocalSet::new().block_on(&runtime, async move {
+                while let Some(command) = commands_rx.recv().await {
+                    command(&mut worker);
+                }
+            });
+            info!("Worker {index} finished");
+        })?;
+    Ok(commands_tx)
+}
+
+impl<S: MessagesStorage + 'static> ThreadedAcceptor<S> {
+    /// Create acceptor with `worker_threads` worker threads (at least one).
+    ///
+    /// Messages storage is created by `message_storage_builder` on worker
+    /// thread the session is bound to, so it doesn't have to be `Send`.
+    pub fn new(
+        settings: Settings,
+        worker_threads: usize,
+        message_storage_builder: Arc<dyn Fn(&SessionId) -> S + Send + Sync>,
+    ) -> Result<ThreadedAcceptor<S>, io::Error> {
+        ThreadedAcceptor::with_codec(settings, worker_threads, message_storage_builder)
+    }
+}
+
+impl<S: MessagesStorage + 'static, M: SessionMessage> ThreadedAcceptor<S, M> {
+    /// Create acceptor running its sessions on codec `M`,
+    /// see [Acceptor::with_codec].
+    pub fn with_codec(
+        settings: Settings,
+        worker_threads: usize,
+        message_storage_builder: Arc<dyn Fn(&SessionId) -> S + Send + Sync>,
+    ) -> Result<ThreadedAcceptor<S, M>, io::Error> {
+        let (emitter, event_stream) = events_channel();
+        let workers = (0..worker_threads.max(1))
+            .map(|index| {
+                let settings = settings.clone();
+                let message_storage_builder = message_storage_builder.clone();
+                let emitter = emitter.clone();
+                spawn_worker(index, move || {
+                    // Worker acceptor reports events to the shared channel,
+                    // its own event stream is never used.
+                    let (_, event_stream) = events_channel();
+                    Acceptor::with_events_channel(
+                        settings,
+                        Box::new(move |session_id| message_storage_builder(session_id)),
+                        emitter,
+                        event_stream,
+                    )
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(ThreadedAcceptor {
+            settings,
+            workers,
+            shutdown_token: CancellationToken::new(),
+            event_stream,
+        })
+    }
+
+    fn execute(&self, session_id: &SessionId, command: Command<S, M>) {
+        let worker = &self.workers[worker_index(session_id, self.workers.len())];
+        if worker.send(command).is_err() {
+            error!("worker of session {session_id} is not running");
+        }
+    }
+
+    pub fn register_session(&self, session_id: SessionId, session_settings: SessionSettings) {
+        self.execute(
+            &session_id.clone(),
+            Box::new(move |acceptor| acceptor.register_session(session_id, session_settings)),
+        );
+    }
+
+    fn execute_on_all(&self, command: impl Fn() -> Command<S, M>) {
+        for worker in self.workers.iter() {
+            if worker.send(command()).is_err() {
+                error!("worker is not running");
+            }
+        }
+    }
+
+    /// Accept sessions which are not registered up front,
+    /// see [Acceptor::set_dynamic_session_builder].
+    pub fn set_dynamic_session_builder(&self, builder: SyncDynamicSessionBuilder) {
+        self.execute_on_all(|| {
+            let builder = builder.clone();
+            Box::new(move |acceptor| {
+                acceptor
+                    .set_dynamic_session_builder(Box::new(move |session_id| builder(session_id)))
+            })
+        });
+    }
+
+    /// Unregister dynamic sessions on disconnection,
+    /// see [Acceptor::set_unregister_dynamic_sessions].
+    pub fn set_unregister_dynamic_sessions(&self, unregister: bool) {
+        self.execute_on_all(|| {
+            Box::new(move |acceptor| acceptor.set_unregister_dynamic_sessions(unregister))
+        });
+    }
+
+    /// Verify every Logon<A> with `authenticator`,
+    /// see [Acceptor::set_authenticator].
+    pub fn set_authenticator(&self, authenticator: SyncAuthenticator) {
+        self.execute_on_all(|| {
+            let authenticator = authenticator.clone();
+            Box::new(move |acceptor| {
+                acceptor.set_authenticator(Box::new(move |logon_request| {
+                    authenticator(logon_request).boxed_local()
+                }))
+            })
+        });
+    }
+
+    /// Remove session at runtime, see [Acceptor::unregister_session].
+    pub fn unregister_session(&self, session_id: &SessionId) {
+        let id = session_id.clone();
+        self.execute(
+            session_id,
+            Box::new(move |acceptor| {
+                acceptor.unregister_session(&id);
+            }),
+        );
+    }
+
+    /// Replace settings of registered session at runtime,
+    /// see [Acceptor::update_session].
+    pub fn update_session(
+        &self,
+        session_id: &SessionId,
+        session_settings: SessionSettings,
+        disconnect: bool,
+    ) {
+        let id = session_id.clone();
+        self.execute(
+            session_id,
+            Box::new(move |acceptor| {
+                acceptor.update_session(&id, session_settings, disconnect);
+            }),
+        );
+    }
+
+    /// List sessions registered on all workers.
+    ///
+    /// Like other queries, returned future doesn't borrow the acceptor.
+    pub fn sessions(&self) -> impl Future<Output = Vec<SessionInfo>> + Send + 'static {
+        let responses = self
+            .workers
+            .iter()
+            .filter_map(|worker| {
+                let (sessions_tx, sessions_rx) = oneshot::channel();
+                worker
+                    .send(Box::new(move |acceptor: &mut Acceptor<S, M>| {
+                        let _ = sessions_tx.send(acceptor.sessions());
+                    }))
+                    .ok()?;
+                Some(sessions_rx)
+            })
+            .collect::<Vec<_>>();
+        future::join_all(responses)
+            .map(|responses| responses.into_iter().flatten().flatten().collect())
+    }
+
+    /// Details of registered session, see [Acceptor::session].
+    pub fn session(
+        &self,
+        session_id: &SessionId,
+    ) -> impl Future<Output = Option<SessionInfo>> + Send + 'static {
+        let (session_tx, session_rx) = oneshot::channel();
+        let id = session_id.clone();
+        self.execute(
+            session_id,
+            Box::new(move |acceptor| {
+                let _ = session_tx.send(acceptor.session(&id));
+            }),
+        );
+        session_rx.map(|session| session.ok().flatten())
+    }
+
+    /// Sender of connected session, see [Acceptor::sender].
+    pub fn sender(
+        &self,
+        session_id: &SessionId,
+    ) -> impl Future<Output = Option<Sender<M>>> + Send + 'static {
+        let (sender_tx, sender_rx) = oneshot::channel();
+        let id = session_id.clone();
+        self.execute(
+            session_id,
+            Box::new(move |acceptor| {
+                let _ = sender_tx.send(acceptor.sender(&id));
+            }),
+        );
+        sender_rx.map(|sender| sender.ok().flatten())
+    }
+
+    /// Listen for connections on `socket_addr`.
+    ///
+    /// Must be called inside tokio runtime, accepting task is spawned
+    /// with `tokio::spawn`.
+    pub async fn start(
+        &self,
+        socket_addr: impl Into<SocketAddr>,
+    ) -> Result<JoinHandle<()>, io::Error> {
+        let listener = TcpListener::bind(socket_addr.into()).await?;
+        Ok(tokio::spawn(Self::server_task(
+            listener,
+            self.router(),
+            #[cfg(feature = "tls")]
+            None,
+        )))
+    }
+
+    /// Listen for TLS connections on `socket_addr`, see [Self::start].
+    ///
+    /// TLS handshake and first message are processed by accepting task,
+    /// TLS streams remain driven by runtime the acceptor was started in.
+    #[cfg(feature = "tls")]
+    pub async fn start_tls(
+        &self,
+        socket_addr: impl Into<SocketAddr>,
+        config: Arc<ServerConfig>,
+    ) -> Result<JoinHandle<()>, io::Error> {
+        let listener = TcpListener::bind(socket_addr.into()).await?;
+        Ok(tokio::spawn(Self::server_task(
+            listener,
+            self.router(),
+            Some(TlsAcceptor::from(config)),
+        )))
+    }
+
+    fn router(&self) -> Router<S, M> {
+        Router {
+            workers: self.workers.clone(),
+            logon_timeout: self.settings.auto_disconnect_after_no_logon_received
+                + NO_INBOUND_TIMEOUT_PADDING,
+            shutdown_token: self.shutdown_token.clone(),
+        }
+    }
+
+    async fn server_task(
+        listener: TcpListener,
+        router: Router<S, M>,
+        #[cfg(feature = "tls")] tls_acceptor: Option<TlsAcceptor>,
+    ) {
+        info!("Acceptor started");
+        loop {
+            let result = tokio::select! {
+                result = listener.accept() => result,
+                _ = router.shutdown_token.cancelled() => break,
+            };
+            match result {
+                Ok((tcp_stream, peer_addr)) => {
+                    #[cfg(feature = "tls")]
+                    if let Some(tls_acceptor) = tls_acceptor.clone() {
+                        tokio::spawn(router.clone().route_tls_connection(
+                            tls_acceptor,
+                            tcp_stream,
+                            peer_addr,
+                        ));
+                        continue;
+                    }
+                    tokio::spawn(
+                        router
+                            .clone()
+                            .route_connection(tcp_stream, peer_addr.into()),
+                    );
+                }
+                Err(err) => error!("server task failed to accept incoming connection: {err}"),
+            }
+        }
+        info!("Acceptor stopped");
+    }
+
+    /// Gracefully shut down the acceptor, see [Acceptor::shutdown].
+    ///
+    /// Returned future completes when sessions on all workers are closed.
+    pub fn shutdown(
+        &self,
+        reason: Option<FixString>,
+        logout_timeout: Duration,
+    ) -> impl Future<Output = ()> + Send + 'static {
+        self.shutdown_token.cancel();
+        let responses = self
+            .workers
+            .iter()
+            .filter_map(|worker| {
+                let (done_tx, done_rx) = oneshot::channel();
+                let reason = reason.clone();
+                worker
+                    .send(Box::new(move |acceptor: &mut Acceptor<S, M>| {
+                        let shutdown = acceptor.shutdown(reason, logout_timeout);
+                        tokio::task::spawn_local(async move {
+                            shutdown.await;
+                            let _ = done_tx.send(());
+                        });
+                    }))
+                    .ok()?;
+                Some(done_rx)
+            })
+            .collect::<Vec<_>>();
+        future::join_all(responses).map(|_| ())
+    }
+
+    pub fn logout(
+        &self,
+        session_id: &SessionId,
+        session_status: Option<SessionStatus>,
+        reason: Option<FixString>,
+    ) {
+        let id = session_id.clone();
+        self.execute(
+            session_id,
+            Box::new(move |acceptor| acceptor.logout(&id, session_status, reason)),
+        );
+    }
+
+    pub fn disconnect(&self, session_id: &SessionId) {
+        let id = session_id.clone();
+        self.execute(
+            session_id,
+            Box::new(move |acceptor| acceptor.disconnect(&id)),
+        );
+    }
+
+    /// Force reset of the session, see [Acceptor::reset].
+    pub fn reset(&self, session_id: &SessionId) {
+        let id = session_id.clone();
+        self.execute(session_id, Box::new(move |acceptor| acceptor.reset(&id)));
+    }
+}
+
+/// Routes accepted connections to workers.
+struct Router<S, M: SessionMessage> {
+    workers: Arc<[mpsc::UnboundedSender<Command<S, M>>]>,
+    logon_timeout: Duration,
+    shutdown_token: CancellationToken,
+}
+
+impl<S, M: SessionMessage> Clone for Router<S, M> {
+    fn clone(&self) -> Self {
+        Self {
+            workers: self.workers.clone(),
+            logon_timeout: self.logon_timeout,
+            shutdown_token: self.shutdown_token.clone(),
+        }
+    }
+}
+
+impl<S: MessagesStorage + 'static, M: SessionMessage> Router<S, M> {
+    #[cfg(feature = "tls")]
+    async fn route_tls_connection(
+        self,
+        tls_acceptor: TlsAcceptor,
+        tcp_stream: TcpStream,
+        peer_addr: SocketAddr,
+    ) {
+        let result = tcp_stream.set_nodelay(true);
+        let result = match result {
+            Ok(()) => tls::accept(&tls_acceptor, tcp_stream, peer_addr).await,
+            Err(err) => Err(err),
+        };
+        match result {
+            Ok((tls_stream, peer_info)) => self.route_connection(tls_stream, peer_info).await,
+            Err(err) => error!(%peer_addr, "failed to establish new session: {err}"),
+        }
+    }
+
+    async fn route_connection<T: RoutedStream>(self, mut stream: T, peer_info: PeerInfo) {
+        let peer_addr = peer_info.addr;
+        let mut buffer = BytesMut::new();
+        let session_id = match timeout(
+            self.logon_timeout,
+            read_session_id(&mut stream, &mut buffer),
+        )
+        .await
+        {
+            Ok(Ok(session_id)) => session_id,
+            Ok(Err(err)) => {
+                error!(%peer_addr, "failed to establish new session: {err}");
+                return;
+            }
+            Err(_) => {
+                error!(%peer_addr, "failed to establish new session: logon timeout");
+                return;
+            }
+        };
+        let stream = match stream.detach() {
+            Ok(stream) => stream,
+            Err(err) => {
+                error!(%peer_addr, "failed to establish new session: {err}");
+                return;
+            }
+        };
+        let worker = &self.workers[worker_index(&session_id, self.workers.len())];
+        let command: Command<S, M> = Box::new(move |acceptor| {
+            if acceptor.is_shut_down() {
+                warn!(%peer_addr, "acceptor is shut down, connection dropped");
+                return;
+            }
+            let (reader, writer) = match T::attach(stream) {
+                Ok(stream) => stream,
+                Err(err) => {
+                    error!(%peer_addr, "failed to establish new session: {err}");
+                    return;
+                }
+            };
+            // Bytes already read by router are processed first
+            let reader = io::Cursor::new(buffer.freeze()).chain(reader);
+            tokio::task::spawn_local(acceptor.session_task().run(peer_info, reader, writer));
+        });
+        if worker.send(command).is_err() {
+            warn!("worker of session {session_id} is not running, connection dropped");
+        }
+    }
+}
+
+impl<S: MessagesStorage, M: SessionMessage> Stream for ThreadedAcceptor<S, M> {
+    type Item = impl AsEvent<M> + Send;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.event_stream).poll_next(cx)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.event_stream.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use easyfix_messages::{
+        fields::{MsgType, UtcTimestamp},
+        messages::{FixtMessage, Logon, Message},
+    };
+
+    use super::*;
+    use crate::{messages_storage::NullStorage, new_header, new_trailer};
+
+    fn fix_string(s: &str) -> FixString {
+        FixString::from_ascii_lossy(s.as_bytes().to_vec())
+    }
+
+    #[test]
+    fn threaded_acceptor_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<ThreadedAcceptor<NullStorage>>();
+    }
+
+    #[tokio::test]
+    async fn first_message_is_routed_by_session_id() {
+        let mut header = new_header(MsgType::Logon);
+        header.begin_string = fix_string("FIXT.1.1");
+        header.sender_comp_id = fix_string("initiator");
+        header.target_comp_id = fix_string("acceptor");
+        header.msg_seq_num = 1;
+        header.sending_time = UtcTimestamp::now();
+        let logon = FixtMessage {
+            header: Box::new(header),
+            body: Box::new(Message::Logon(Logon {
+                heart_bt_int: 30,
+                ..Default::default()
+            })),
+            trailer: Box::new(new_trailer()),
+        }
+        .serialize();
+
+        // Message split in two reads, followed by part of the next one
+        let mut data = logon.clone();
+        data.extend_from_slice(b"8=FIXT");
+        let (first, second) = data.split_at(10);
+        let mut reader = first.chain(second);
+        let mut buffer = BytesMut::new();
+        let session_id = read_session_id(&mut reader, &mut buffer).await.unwrap();
+        assert_eq!(
+            session_id,
+            SessionId::new(
+                fix_string("FIXT.1.1"),
+                fix_string("acceptor"),
+                fix_string("initiator")
+            )
+        );
+        assert!(buffer.starts_with(&logon));
+
+        let mut reader: &[u8] = b"garbage";
+        assert!(read_session_id(&mut reader, &mut BytesMut::new())
+            .await
+            .is_err());
+    }
+}
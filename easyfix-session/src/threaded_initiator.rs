@@ -0,0 +1,279 @@
+//! Multi-threaded initiator.
+//!
+//! Like in [ThreadedAcceptor](crate::threaded_acceptor::ThreadedAcceptor),
+//! sessions are spread over worker threads, each running its own
+//! `LocalSet`, and every session is bound to one worker (by hash of
+//! `SessionId`).
+
+use std::{
+    collections::HashMap,
+    future::Future,
+    io,
+    net::SocketAddr,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use easyfix_messages::{messages::FixtMessage, session::SessionMessage};
+use futures::{FutureExt, Stream};
+use pin_project::pin_project;
+use tokio::{
+    sync::{mpsc, oneshot},
+    task::JoinHandle,
+};
+use tracing::{error, info};
+
+#[cfg(feature = "tls")]
+use crate::tls::TlsConnector;
+use crate::{
+    acceptor::SessionInfo,
+    application::{events_channel, AsEvent, Emitter, EventStream},
+    initiator::Initiator,
+    messages_storage::MessagesStorage,
+    session_id::SessionId,
+    settings::{ReconnectSettings, SessionSettings, Settings},
+    threaded_acceptor::{spawn_worker, worker_index, WorkerCommand},
+    Sender,
+};
+
+type Command<S, M> = WorkerCommand<Worker<S, M>>;
+
+/// Sessions of one worker thread.
+struct Worker<S: MessagesStorage, M: SessionMessage> {
+    settings: Settings,
+    message_storage_builder: Arc<dyn Fn(&SessionId) -> S + Send + Sync>,
+    emitter: Emitter<M>,
+    initiators: HashMap<SessionId, (Initiator<S, M>, JoinHandle<()>)>,
+}
+
+impl<S: MessagesStorage + 'static, M: SessionMessage> Worker<S, M> {
+    fn start(
+        &mut self,
+        session_settings: SessionSettings,
+        addrs: Vec<SocketAddr>,
+        reconnect_settings: ReconnectSettings,
+        #[cfg(feature = "tls")] tls_connector: Option<TlsConnector>,
+    ) {
+        let session_id = session_settings.session_id.clone();
+        self.stop(&session_id);
+        // Worker initiator reports events to the shared channel,
+        // its own event stream is never used.
+        let (_, event_stream) = events_channel();
+        #[allow(unused_mut)]
+        let mut initiator = Initiator::with_events_channel(
+            self.settings.clone(),
+            session_settings,
+            (self.message_storage_builder)(&session_id),
+            self.emitter.clone(),
+            event_stream,
+        );
+        #[cfg(feature = "tls")]
+        if let Some(tls_connector) = tls_connector {
+            initiator.set_tls_connector(tls_connector);
+        }
+        let handle = initiator.start(addrs, reconnect_settings);
+        self.initiators.insert(session_id, (initiator, handle));
+    }
+
+    fn stop(&mut self, session_id: &SessionId) -> bool {
+        match self.initiators.remove(session_id) {
+            Some((_, handle)) => {
+                info!("Initiator {session_id} stopped");
+                handle.abort();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Initiator running sessions on multiple worker threads.
+///
+/// Unlike [Initiator], it's `Send + Sync`, manages any number of
+/// sessions and has to be used inside multi-threaded tokio runtime.
+/// Events of all sessions are delivered through this initiator stream,
+/// `Sender` received with `FixEvent::Logon` can be used from any task.
+/// Futures returned by queries don't borrow the initiator.
+#[pin_project]
+pub struct ThreadedInitiator<S: MessagesStorage, M: SessionMessage = FixtMessage> {
+    workers: Arc<[mpsc::UnboundedSender<Command<S, M>>]>,
+    #[pin]
+    event_stream: EventStream<M>,
+}
+
+impl<S: MessagesStorage + 'static> ThreadedInitiator<S> {
+    /// Create initiator with `worker_threads` worker threads (at least one).
+    ///
+    /// Messages storage is created by `message_storage_builder` on worker
+    /// thread the session is bound to, so it doesn't have to be `Send`.
+    pub fn new(
+        settings: Settings,
+        worker_threads: usize,
+        message_storage_builder: Arc<dyn Fn(&SessionId) -> S + Send + Sync>,
+    ) -> Result<ThreadedInitiator<S>, io::Error> {
+        ThreadedInitiator::with_codec(settings, worker_threads, message_storage_builder)
+    }
+}
+
+impl<S: MessagesStorage + 'static, M: SessionMessage> ThreadedInitiator<S, M> {
+    /// Create initiator running its sessions on codec `M`,
+    /// see [Initiator::with_codec].
+    pub fn with_codec(
+        settings: Settings,
+        worker_threads: usize,
+        message_storage_builder: Arc<dyn Fn(&SessionId) -> S + Send + Sync>,
+    ) -> Result<ThreadedInitiator<S, M>, io::Error> {
+        let (emitter, event_stream) = events_channel();
+        let workers = (0..worker_threads.max(1))
+            .map(|index| {
+                let settings = settings.clone();
+                let message_storage_builder = message_storage_builder.clone();
+                let emitter = emitter.clone();
+                spawn_worker(index, move || Worker {
+                    settings,
+                    message_storage_builder,
+                    emitter,
+                    initiators: HashMap::new(),
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(ThreadedInitiator {
+            workers,
+            event_stream,
+        })
+    }
+
+    fn execute(&self, session_id: &SessionId, command: Command<S, M>) {
+        let worker = &self.workers[worker_index(session_id, self.workers.len())];
+        if worker.send(command).is_err() {
+            error!("worker of session {session_id} is not running");
+        }
+    }
+
+    /// Start managed connection of new session, see [Initiator::start].
+    ///
+    /// Session already started with the same `SessionId` is stopped first.
+    pub fn start(
+        &self,
+        session_settings: SessionSettings,
+        addrs: impl IntoIterator<Item = SocketAddr>,
+        reconnect_settings: ReconnectSettings,
+    ) {
+        let addrs = addrs.into_iter().collect::<Vec<_>>();
+        self.execute(
+            &session_settings.session_id.clone(),
+            Box::new(move |worker| {
+                worker.start(
+                    session_settings,
+                    addrs,
+                    reconnect_settings,
+                    #[cfg(feature = "tls")]
+                    None,
+                )
+            }),
+        );
+    }
+
+    /// Start managed TLS connection of new session, see [Self::start].
+    #[cfg(feature = "tls")]
+    pub fn start_tls(
+        &self,
+        session_settings: SessionSettings,
+        addrs: impl IntoIterator<Item = SocketAddr>,
+        reconnect_settings: ReconnectSettings,
+        tls_connector: TlsConnector,
+    ) {
+        let addrs = addrs.into_iter().collect::<Vec<_>>();
+        self.execute(
+            &session_settings.session_id.clone(),
+            Box::new(move |worker| {
+                worker.start(
+                    session_settings,
+                    addrs,
+                    reconnect_settings,
+                    Some(tls_connector),
+                )
+            }),
+        );
+    }
+
+    /// Stop managed connection of the session, current connection
+    /// (if any) is dropped without Logout<5>. Returns `false` when
+    /// session wasn't started.
+    pub fn stop(&self, session_id: &SessionId) -> impl Future<Output = bool> + Send + 'static {
+        let (stopped_tx, stopped_rx) = oneshot::channel();
+        let id = session_id.clone();
+        self.execute(
+            session_id,
+            Box::new(move |worker| {
+                let _ = stopped_tx.send(worker.stop(&id));
+            }),
+        );
+        stopped_rx.map(|stopped| stopped.unwrap_or(false))
+    }
+
+    /// Details of started session, see [Initiator::session].
+    pub fn session(
+        &self,
+        session_id: &SessionId,
+    ) -> impl Future<Output = Option<SessionInfo>> + Send + 'static {
+        let (session_tx, session_rx) = oneshot::channel();
+        let id = session_id.clone();
+        self.execute(
+            session_id,
+            Box::new(move |worker| {
+                let session = worker
+                    .initiators
+                    .get(&id)
+                    .map(|(initiator, _)| initiator.session());
+                let _ = session_tx.send(session);
+            }),
+        );
+        session_rx.map(|session| session.ok().flatten())
+    }
+
+    /// Sender of connected session, see [Initiator::sender].
+    pub fn sender(
+        &self,
+        session_id: &SessionId,
+    ) -> impl Future<Output = Option<Sender<M>>> + Send + 'static {
+        let (sender_tx, sender_rx) = oneshot::channel();
+        let id = session_id.clone();
+        self.execute(
+            session_id,
+            Box::new(move |worker| {
+                let sender = worker
+                    .initiators
+                    .get(&id)
+                    .and_then(|(initiator, _)| initiator.sender());
+                let _ = sender_tx.send(sender);
+            }),
+        );
+        sender_rx.map(|sender| sender.ok().flatten())
+    }
+}
+
+impl<S: MessagesStorage, M: SessionMessage> Stream for ThreadedInitiator<S, M> {
+    type Item = impl AsEvent<M> + Send;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.event_stream).poll_next(cx)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.event_stream.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::messages_storage::NullStorage;
+
+    #[test]
+    fn threaded_initiator_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<ThreadedInitiator<NullStorage>>();
+    }
+}
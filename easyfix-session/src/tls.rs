@@ -0,0 +1,241 @@
+//! TLS transport, enabled with `tls` feature.
+//!
+//! Certificates and private keys are loaded from PEM files.
+
+use std::{
+    fs::File,
+    io::{self, BufReader},
+    net::SocketAddr,
+    path::Path,
+    sync::Arc,
+    time::Duration,
+};
+
+use futures::{
+    future::{FutureExt, LocalBoxFuture},
+    stream::{FuturesUnordered, StreamExt},
+};
+use tokio::{
+    io::{split, AsyncRead, AsyncWrite},
+    net::{TcpListener, TcpStream},
+    time::timeout,
+};
+pub use tokio_rustls::rustls;
+use tokio_rustls::{
+    rustls::{
+        crypto::{ring, CryptoProvider},
+        pki_types::{CertificateDer, PrivateKeyDer, ServerName},
+        server::WebPkiClientVerifier,
+        ClientConfig, RootCertStore, ServerConfig,
+    },
+    server, TlsAcceptor,
+};
+
+use crate::acceptor::{Connection, PeerInfo};
+
+/// Time limit for TLS handshake of incoming connection.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+fn invalid_input(error: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, error)
+}
+
+fn provider() -> Arc<CryptoProvider> {
+    Arc::new(ring::default_provider())
+}
+
+fn load_certs(path: &Path) -> Result<Vec<CertificateDer<'static>>, io::Error> {
+    rustls_pemfile::certs(&mut BufReader::new(File::open(path)?)).collect()
+}
+
+fn load_private_key(path: &Path) -> Result<PrivateKeyDer<'static>, io::Error> {
+    rustls_pemfile::private_key(&mut BufReader::new(File::open(path)?))?
+        .ok_or_else(|| invalid_input(format!("no private key found in {}", path.display())))
+}
+
+fn load_root_certs(path: &Path) -> Result<RootCertStore, io::Error> {
+    let mut root_certs = RootCertStore::empty();
+    for cert in load_certs(path)? {
+        root_certs.add(cert).map_err(invalid_input)?;
+    }
+    Ok(root_certs)
+}
+
+/// Identity of the peer, Common Name from certificate subject.
+fn common_name(cert: &CertificateDer<'_>) -> Option<String> {
+    let (_, cert) = x509_parser::parse_x509_certificate(cert).ok()?;
+    let common_name = cert.subject().iter_common_name().next()?;
+    common_name.as_str().ok().map(ToOwned::to_owned)
+}
+
+/// Build acceptor TLS configuration.
+///
+/// When `client_ca` is set, clients have to present certificate signed
+/// by one of given CAs. Certificate Common Name is then available as
+/// `PeerInfo::tls_identity` and can be bound to session with
+/// `SessionSettings::tls_client_identity`.
+pub fn server_config(
+    cert_chain: impl AsRef<Path>,
+    private_key: impl AsRef<Path>,
+    client_ca: Option<&Path>,
+) -> Result<Arc<ServerConfig>, io::Error> {
+    let builder = ServerConfig::builder_with_provider(provider())
+        .with_safe_default_protocol_versions()
+        .map_err(invalid_input)?;
+    let builder = match client_ca {
+        Some(client_ca) => builder.with_client_cert_verifier(
+            WebPkiClientVerifier::builder_with_provider(
+                Arc::new(load_root_certs(client_ca)?),
+                provider(),
+            )
+            .build()
+            .map_err(invalid_input)?,
+        ),
+        None => builder.with_no_client_auth(),
+    };
+    builder
+        .with_single_cert(
+            load_certs(cert_chain.as_ref())?,
+            load_private_key(private_key.as_ref())?,
+        )
+        .map(Arc::new)
+        .map_err(invalid_input)
+}
+
+/// Build initiator TLS configuration.
+///
+/// Server certificate is verified against CAs from `ca` file, optional
+/// `client_cert` is pair of certificate chain and private key files.
+pub fn client_config(
+    ca: impl AsRef<Path>,
+    client_cert: Option<(&Path, &Path)>,
+) -> Result<Arc<ClientConfig>, io::Error> {
+    let builder = ClientConfig::builder_with_provider(provider())
+        .with_safe_default_protocol_versions()
+        .map_err(invalid_input)?
+        .with_root_certificates(load_root_certs(ca.as_ref())?);
+    let config = match client_cert {
+        Some((cert_chain, private_key)) => builder
+            .with_client_auth_cert(load_certs(cert_chain)?, load_private_key(private_key)?)
+            .map_err(invalid_input)?,
+        None => builder.with_no_client_auth(),
+    };
+    Ok(Arc::new(config))
+}
+
+/// TLS handshake of incoming connection, limited with `HANDSHAKE_TIMEOUT`.
+pub(crate) async fn accept(
+    acceptor: &TlsAcceptor,
+    tcp_stream: TcpStream,
+    peer_addr: SocketAddr,
+) -> Result<(server::TlsStream<TcpStream>, PeerInfo), io::Error> {
+    let tls_stream = timeout(HANDSHAKE_TIMEOUT, acceptor.accept(tcp_stream))
+        .await
+        .map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::TimedOut,
+                format!("TLS handshake with {peer_addr} timed out"),
+            )
+        })??;
+    let tls_identity = tls_stream
+        .get_ref()
+        .1
+        .peer_certificates()
+        .and_then(|certs| certs.first())
+        .and_then(common_name);
+    Ok((
+        tls_stream,
+        PeerInfo {
+            addr: peer_addr,
+            tls_identity,
+        },
+    ))
+}
+
+type Handshake =
+    LocalBoxFuture<'static, Result<(server::TlsStream<TcpStream>, PeerInfo), io::Error>>;
+
+/// TLS acceptor connection.
+pub struct TlsConnection {
+    listener: TcpListener,
+    acceptor: TlsAcceptor,
+    // Handshakes in progress, so slow client doesn't block others
+    handshakes: FuturesUnordered<Handshake>,
+}
+
+impl TlsConnection {
+    pub async fn new(
+        socket_addr: impl Into<SocketAddr>,
+        config: Arc<ServerConfig>,
+    ) -> Result<TlsConnection, io::Error> {
+        let socket_addr = socket_addr.into();
+        let listener = TcpListener::bind(&socket_addr).await?;
+        Ok(TlsConnection {
+            listener,
+            acceptor: TlsAcceptor::from(config),
+            handshakes: FuturesUnordered::new(),
+        })
+    }
+
+    /// Address the listener is bound to, e.g. to learn port assigned by OS.
+    pub fn local_addr(&self) -> Result<SocketAddr, io::Error> {
+        self.listener.local_addr()
+    }
+}
+
+impl Connection for TlsConnection {
+    async fn accept(
+        &mut self,
+    ) -> Result<
+        (
+            impl AsyncRead + Unpin + 'static,
+            impl AsyncWrite + Unpin + 'static,
+            PeerInfo,
+        ),
+        io::Error,
+    > {
+        loop {
+            tokio::select! {
+                result = self.listener.accept() => {
+                    let (tcp_stream, peer_addr) = result?;
+                    tcp_stream.set_nodelay(true)?;
+                    let acceptor = self.acceptor.clone();
+                    self.handshakes.push(
+                        async move { accept(&acceptor, tcp_stream, peer_addr).await }.boxed_local(),
+                    );
+                }
+                Some(result) = self.handshakes.next() => {
+                    let (tls_stream, peer_info) = result?;
+                    let (reader, writer) = split(tls_stream);
+                    return Ok((reader, writer, peer_info));
+                }
+            }
+        }
+    }
+}
+
+/// Initiator TLS configuration.
+#[derive(Clone)]
+pub struct TlsConnector {
+    connector: tokio_rustls::TlsConnector,
+    server_name: ServerName<'static>,
+}
+
+impl TlsConnector {
+    /// Create connector, `server_name` is verified against server certificate.
+    pub fn new(config: Arc<ClientConfig>, server_name: &str) -> Result<TlsConnector, io::Error> {
+        Ok(TlsConnector {
+            connector: tokio_rustls::TlsConnector::from(config),
+            server_name: ServerName::try_from(server_name.to_owned()).map_err(invalid_input)?,
+        })
+    }
+
+    pub(crate) async fn connect(
+        &self,
+        tcp_stream: TcpStream,
+    ) -> Result<tokio_rustls::client::TlsStream<TcpStream>, io::Error> {
+        self.connector
+            .connect(self.server_name.clone(), tcp_stream)
+            .await
+    }
+}
@@ -0,0 +1,716 @@
+mod common;
+
+use std::{cell::RefCell, ops::RangeInclusive, rc::Rc, time::Duration};
+
+use common::{
+    app_message, drain_events, fix_string, logon, message, session_id, session_settings, settings,
+    Counterparty, Event,
+};
+use easyfix_messages::{
+    fields::{MsgType, SeqNum, SessionStatus},
+    messages::{FixtMessage, Heartbeat, Logout, Message, TestRequest},
+};
+use easyfix_session::{
+    acceptor::{Acceptor, LogonRejection, LogonRequest},
+    messages_storage::{AsyncMessagesStorage, AsyncStorage, InMemoryStorage, MessagesStorage},
+    session_time::SessionTime,
+    settings::{SessionSettings, Settings},
+};
+use futures::{stream::LocalBoxStream, FutureExt, StreamExt};
+use tokio::{
+    task::LocalSet,
+    time::{timeout, Instant},
+};
+
+// Shared, so test can inspect backend state
+#[derive(Clone, Default)]
+struct Backend(Rc<RefCell<InMemoryStorage>>);
+
+impl AsyncMessagesStorage for Backend {
+    async fn seq_nums(&mut self) -> (SeqNum, SeqNum) {
+        let storage = self.0.borrow();
+        (
+            storage.next_sender_msg_seq_num(),
+            storage.next_target_msg_seq_num(),
+        )
+    }
+
+    async fn store_seq_nums(
+        &mut self,
+        next_sender_msg_seq_num: SeqNum,
+        next_target_msg_seq_num: SeqNum,
+    ) {
+        tokio::task::yield_now().await;
+        let mut storage = self.0.borrow_mut();
+        storage.set_next_sender_msg_seq_num(next_sender_msg_seq_num);
+        storage.set_next_target_msg_seq_num(next_target_msg_seq_num);
+    }
+
+    async fn fetch_range(
+        &mut self,
+        range: RangeInclusive<SeqNum>,
+    ) -> LocalBoxStream<'static, Vec<u8>> {
+        self.0.borrow_mut().fetch_range(range)
+    }
+
+    async fn store(&mut self, seq_num: SeqNum, data: Vec<u8>) {
+        tokio::task::yield_now().await;
+        self.0.borrow_mut().store(seq_num, &data);
+    }
+
+    async fn reset(&mut self) {
+        self.0.borrow_mut().reset();
+    }
+}
+
+#[tokio::test]
+async fn async_storage_from_storage_builder() {
+    LocalSet::new()
+        .run_until(async {
+            let backend = Backend::default();
+            let storage_backend = backend.clone();
+            let mut acceptor = Acceptor::new(
+                settings(),
+                Box::new(move |_| {
+                    let storage = storage_backend.0.borrow();
+                    AsyncStorage::with_state(
+                        storage_backend.clone(),
+                        storage.next_sender_msg_seq_num(),
+                        storage.next_target_msg_seq_num(),
+                        None,
+                    )
+                }),
+            );
+            acceptor.register_session(session_id("client"), session_settings(session_id("client")));
+            let acceptor = Rc::new(RefCell::new(acceptor));
+            let mut events = drain_events(acceptor.clone());
+
+            let mut counterparty = Counterparty::connect(&acceptor.borrow());
+            counterparty.send(&logon("client", 1)).await;
+            let msg = counterparty.recv().await.unwrap();
+            assert_eq!(msg.msg_type(), MsgType::Logon);
+            assert!(matches!(events.recv().await, Some(Event::Logon(_))));
+
+            counterparty
+                .send(&message("client", 2, Message::Logout(Logout::default())))
+                .await;
+            let msg = counterparty.recv().await.unwrap();
+            assert_eq!(msg.msg_type(), MsgType::Logout);
+            counterparty.closed().await;
+            assert!(matches!(events.recv().await, Some(Event::Logout(..))));
+
+            // Operations are executed by storage worker in background
+            let messages = timeout(Duration::from_secs(5), async {
+                loop {
+                    let messages = backend.0.borrow_mut().fetch_range(1..=2);
+                    let messages: Vec<_> = messages.collect().await;
+                    let seq_nums = {
+                        let storage = backend.0.borrow();
+                        (
+                            storage.next_sender_msg_seq_num(),
+                            storage.next_target_msg_seq_num(),
+                        )
+                    };
+                    if messages.len() == 2 && seq_nums == (3, 3) {
+                        break messages;
+                    }
+                    tokio::task::yield_now().await;
+                }
+            })
+            .await
+            .expect("storage not updated");
+            let msg = FixtMessage::from_bytes(&messages[0]).unwrap();
+            assert_eq!(msg.msg_type(), MsgType::Logon);
+        })
+        .await;
+}
+
+/// Storage recording inbound messages.
+#[derive(Default)]
+struct InboundStorage {
+    storage: InMemoryStorage,
+    inbound: Rc<RefCell<Vec<Vec<u8>>>>,
+}
+
+impl MessagesStorage for InboundStorage {
+    fn fetch_range(&mut self, range: RangeInclusive<SeqNum>) -> LocalBoxStream<'static, Vec<u8>> {
+        self.storage.fetch_range(range)
+    }
+
+    fn store(&mut self, seq_num: SeqNum, data: &[u8]) {
+        self.storage.store(seq_num, data)
+    }
+
+    fn store_inbound(&mut self, _seq_num: SeqNum, data: &[u8]) {
+        self.inbound.borrow_mut().push(data.to_vec());
+    }
+
+    fn next_sender_msg_seq_num(&self) -> SeqNum {
+        self.storage.next_sender_msg_seq_num()
+    }
+
+    fn next_target_msg_seq_num(&self) -> SeqNum {
+        self.storage.next_target_msg_seq_num()
+    }
+
+    fn set_next_sender_msg_seq_num(&mut self, seq_num: SeqNum) {
+        self.storage.set_next_sender_msg_seq_num(seq_num)
+    }
+
+    fn set_next_target_msg_seq_num(&mut self, seq_num: SeqNum) {
+        self.storage.set_next_target_msg_seq_num(seq_num)
+    }
+
+    fn incr_next_sender_msg_seq_num(&mut self) {
+        self.storage.incr_next_sender_msg_seq_num()
+    }
+
+    fn incr_next_target_msg_seq_num(&mut self) {
+        self.storage.incr_next_target_msg_seq_num()
+    }
+
+    fn reset(&mut self) {
+        self.storage.reset()
+    }
+}
+
+/// Replace `from` with `to` in serialized message, fixing CheckSum<10>.
+fn patch(msg: &[u8], from: &[u8], to: &[u8]) -> Vec<u8> {
+    let pos = msg
+        .windows(from.len())
+        .position(|window| window == from)
+        .unwrap();
+    let mut patched = [&msg[..pos], to, &msg[pos + from.len()..msg.len() - 7]].concat();
+    let checksum = patched.iter().fold(0u8, |sum, b| sum.wrapping_add(*b));
+    patched.extend_from_slice(format!("10={checksum:03}\x01").as_bytes());
+    patched
+}
+
+#[tokio::test]
+async fn inbound_messages_are_persisted_as_received() {
+    LocalSet::new()
+        .run_until(async {
+            let inbound = Rc::new(RefCell::new(Vec::new()));
+            let storage_inbound = inbound.clone();
+            let mut acceptor = Acceptor::new(
+                settings(),
+                Box::new(move |_| InboundStorage {
+                    storage: InMemoryStorage::new(),
+                    inbound: storage_inbound.clone(),
+                }),
+            );
+            let mut session_settings = session_settings(session_id("client"));
+            session_settings.persist_inbound = true;
+            acceptor.register_session(session_id("client"), session_settings);
+            let acceptor = Rc::new(RefCell::new(acceptor));
+            let _events = drain_events(acceptor.clone());
+
+            // BodyLength<9> without leading zeros differs from re-serialized message
+            let logon = patch(&logon("client", 1).serialize(), b"\x019=00", b"\x019=");
+            let heartbeat = patch(
+                &message("client", 2, Message::Heartbeat(Heartbeat::default())).serialize(),
+                b"\x019=00",
+                b"\x019=",
+            );
+            let mut counterparty = Counterparty::connect(&acceptor.borrow());
+            counterparty.send_bytes(&logon).await;
+            let msg = counterparty.recv().await.unwrap();
+            assert_eq!(msg.msg_type(), MsgType::Logon);
+            counterparty.send_bytes(&heartbeat).await;
+            counterparty
+                .send(&message("client", 3, Message::Logout(Logout::default())))
+                .await;
+            let msg = counterparty.recv().await.unwrap();
+            assert_eq!(msg.msg_type(), MsgType::Logout);
+            counterparty.closed().await;
+
+            let inbound = inbound.borrow();
+            assert_eq!(inbound.len(), 3);
+            assert_eq!(inbound[0], logon);
+            assert_eq!(inbound[1], heartbeat);
+        })
+        .await;
+}
+
+#[tokio::test]
+async fn rejected_logon_leaves_connected_session_intact() {
+    LocalSet::new()
+        .run_until(async {
+            let mut acceptor = Acceptor::new(settings(), Box::new(|_| InMemoryStorage::new()));
+            acceptor.register_session(session_id("client"), session_settings(session_id("client")));
+            acceptor.set_authenticator(Box::new(|logon_request: LogonRequest| {
+                async move {
+                    if logon_request.password == Some(fix_string("secret")) {
+                        Ok(())
+                    } else {
+                        Err(LogonRejection {
+                            session_status: Some(SessionStatus::InvalidUsernameOrPassword),
+                            text: Some(fix_string("Invalid password")),
+                        })
+                    }
+                }
+                .boxed_local()
+            }));
+            let acceptor = Rc::new(RefCell::new(acceptor));
+            let mut events = drain_events(acceptor.clone());
+
+            let mut logon_with_password = logon("client", 1);
+            if let Message::Logon(logon) = &mut *logon_with_password.body {
+                logon.password = Some(fix_string("secret"));
+            }
+            let mut counterparty = Counterparty::connect(&acceptor.borrow());
+            counterparty.send(&logon_with_password).await;
+            let msg = counterparty.recv().await.unwrap();
+            assert_eq!(msg.msg_type(), MsgType::Logon);
+            assert!(matches!(events.recv().await, Some(Event::Logon(_))));
+
+            // Same SessionId, wrong password
+            let mut intruder = Counterparty::connect(&acceptor.borrow());
+            intruder.send(&logon("client", 1)).await;
+            let msg = intruder.recv().await.unwrap();
+            let Message::Logout(logout) = &*msg.body else {
+                panic!("unexpected message: {:?}", msg.msg_type());
+            };
+            assert_eq!(msg.header.msg_seq_num, 1);
+            assert_eq!(
+                logout.session_status,
+                Some(SessionStatus::InvalidUsernameOrPassword)
+            );
+            intruder.closed().await;
+
+            let session = acceptor.borrow().session(&session_id("client")).unwrap();
+            assert!(session.connected);
+            assert!(session.logged_on);
+            assert_eq!(session.next_sender_msg_seq_num, 2);
+            assert_eq!(session.next_target_msg_seq_num, 2);
+
+            // Connected session is still reachable and keeps its sequence
+            let sender = acceptor.borrow().sender(&session_id("client")).unwrap();
+            sender
+                .send(Box::new(Message::Heartbeat(Heartbeat::default())))
+                .unwrap();
+            let msg = counterparty.recv().await.unwrap();
+            assert_eq!(msg.msg_type(), MsgType::Heartbeat);
+            assert_eq!(msg.header.msg_seq_num, 2);
+
+            counterparty
+                .send(&message("client", 2, Message::Logout(Logout::default())))
+                .await;
+            let msg = counterparty.recv().await.unwrap();
+            assert_eq!(msg.msg_type(), MsgType::Logout);
+            counterparty.closed().await;
+            assert!(matches!(events.recv().await, Some(Event::Logout(..))));
+        })
+        .await;
+}
+
+#[tokio::test]
+async fn dynamic_session_is_unregistered_on_disconnect() {
+    LocalSet::new()
+        .run_until(async {
+            let mut acceptor = Acceptor::new(settings(), Box::new(|_| InMemoryStorage::new()));
+            // Settings template with unrelated session id
+            acceptor.set_dynamic_session_builder(Box::new(|_| {
+                Some(session_settings(session_id("template")))
+            }));
+            acceptor.set_unregister_dynamic_sessions(true);
+            acceptor.set_authenticator(Box::new(|logon_request: LogonRequest| {
+                let accepted = logon_request.session_id == session_id("client");
+                async move {
+                    accepted.then_some(()).ok_or(LogonRejection {
+                        session_status: None,
+                        text: None,
+                    })
+                }
+                .boxed_local()
+            }));
+            let acceptor = Rc::new(RefCell::new(acceptor));
+            let mut events = drain_events(acceptor.clone());
+
+            let mut counterparty = Counterparty::connect(&acceptor.borrow());
+            counterparty.send(&logon("client", 1)).await;
+            let msg = counterparty.recv().await.unwrap();
+            assert_eq!(msg.msg_type(), MsgType::Logon);
+            assert!(matches!(events.recv().await, Some(Event::Logon(_))));
+            let session = acceptor.borrow().session(&session_id("client")).unwrap();
+            assert_eq!(session.session_settings.session_id, session_id("client"));
+
+            counterparty
+                .send(&message("client", 2, Message::Logout(Logout::default())))
+                .await;
+            let msg = counterparty.recv().await.unwrap();
+            assert_eq!(msg.msg_type(), MsgType::Logout);
+            counterparty.closed().await;
+            assert!(matches!(events.recv().await, Some(Event::Logout(..))));
+            assert!(acceptor.borrow().session(&session_id("client")).is_none());
+
+            // Rejected session is not kept either
+            let mut rejected = Counterparty::connect(&acceptor.borrow());
+            rejected.send(&logon("rejected", 1)).await;
+            let msg = rejected.recv().await.unwrap();
+            assert_eq!(msg.msg_type(), MsgType::Logout);
+            rejected.closed().await;
+            assert!(acceptor.borrow().sessions().is_empty());
+        })
+        .await;
+}
+
+#[tokio::test]
+async fn malformed_logon_is_answered_with_logout() {
+    LocalSet::new()
+        .run_until(async {
+            let mut acceptor = Acceptor::new(settings(), Box::new(|_| InMemoryStorage::new()));
+            acceptor.register_session(session_id("client"), session_settings(session_id("client")));
+            let acceptor = Rc::new(RefCell::new(acceptor));
+            let _events = drain_events(acceptor.clone());
+
+            // Custom field is rejected by session in `UnknownFields::Reject` mode
+            let mut logon = logon("client", 1);
+            if let Message::Logon(logon) = &mut *logon.body {
+                logon.unknown_fields.push((5001, fix_string("custom")));
+            }
+            let mut counterparty = Counterparty::connect(&acceptor.borrow());
+            counterparty.send(&logon).await;
+            let msg = counterparty.recv().await.unwrap();
+            let Message::Logout(logout) = &*msg.body else {
+                panic!("unexpected message: {:?}", msg.msg_type());
+            };
+            assert!(logout.text.is_some());
+            counterparty.closed().await;
+
+            let session = acceptor.borrow().session(&session_id("client")).unwrap();
+            assert!(!session.connected);
+            assert_eq!(session.next_target_msg_seq_num, 1);
+        })
+        .await;
+}
+
+#[tokio::test]
+async fn logon_outside_session_time_is_answered_with_logout() {
+    LocalSet::new()
+        .run_until(async {
+            // Session period starts in an hour
+            let now = chrono::Utc::now().time();
+            let session_time = SessionTime::from(
+                now + chrono::Duration::hours(1)..=now + chrono::Duration::hours(2),
+            );
+            let mut acceptor = Acceptor::new(settings(), Box::new(|_| InMemoryStorage::new()));
+            acceptor.register_session(
+                session_id("client"),
+                SessionSettings {
+                    session_time: Some(session_time),
+                    ..session_settings(session_id("client"))
+                },
+            );
+            let acceptor = Rc::new(RefCell::new(acceptor));
+            let _events = drain_events(acceptor.clone());
+
+            let mut counterparty = Counterparty::connect(&acceptor.borrow());
+            counterparty.send(&logon("client", 1)).await;
+            let msg = counterparty.recv().await.unwrap();
+            let Message::Logout(logout) = &*msg.body else {
+                panic!("unexpected message: {:?}", msg.msg_type());
+            };
+            assert_eq!(
+                logout.text,
+                Some(fix_string("Logon<A> outside of session time"))
+            );
+            counterparty.closed().await;
+        })
+        .await;
+}
+
+#[tokio::test]
+async fn heart_bt_int_out_of_range_is_answered_with_logout() {
+    LocalSet::new()
+        .run_until(async {
+            let mut acceptor = Acceptor::new(
+                Settings {
+                    min_heartbeat_interval: Some(Duration::from_secs(5)),
+                    max_heartbeat_interval: Some(Duration::from_secs(60)),
+                    ..settings()
+                },
+                Box::new(|_| InMemoryStorage::new()),
+            );
+            acceptor.register_session(session_id("client"), session_settings(session_id("client")));
+            let acceptor = Rc::new(RefCell::new(acceptor));
+            let _events = drain_events(acceptor.clone());
+
+            for heart_bt_int in [1, 61] {
+                let mut logon = logon("client", 1);
+                if let Message::Logon(logon) = &mut *logon.body {
+                    logon.heart_bt_int = heart_bt_int;
+                }
+                let mut counterparty = Counterparty::connect(&acceptor.borrow());
+                counterparty.send(&logon).await;
+                let msg = counterparty.recv().await.unwrap();
+                let Message::Logout(logout) = &*msg.body else {
+                    panic!("unexpected message: {:?}", msg.msg_type());
+                };
+                assert_eq!(
+                    logout.text,
+                    Some(fix_string(&format!(
+                        "HeartBtInt<108> {heart_bt_int} out of allowed range"
+                    )))
+                );
+                counterparty.closed().await;
+            }
+        })
+        .await;
+}
+
+#[tokio::test(start_paused = true)]
+async fn timeouts_follow_negotiated_heart_bt_int() {
+    LocalSet::new()
+        .run_until(async {
+            // Configured interval is 30 s
+            let mut acceptor = Acceptor::new(settings(), Box::new(|_| InMemoryStorage::new()));
+            acceptor.register_session(session_id("client"), session_settings(session_id("client")));
+            let acceptor = Rc::new(RefCell::new(acceptor));
+            let _events = drain_events(acceptor.clone());
+
+            let mut logon = logon("client", 1);
+            if let Message::Logon(logon) = &mut *logon.body {
+                logon.heart_bt_int = 2;
+            }
+            let mut counterparty = Counterparty::connect(&acceptor.borrow());
+            let start = Instant::now();
+            counterparty.send(&logon).await;
+            let msg = counterparty.recv().await.unwrap();
+            let Message::Logon(logon) = &*msg.body else {
+                panic!("unexpected message: {:?}", msg.msg_type());
+            };
+            assert_eq!(logon.heart_bt_int, 2);
+
+            // Output timeout
+            let msg = counterparty.recv().await.unwrap();
+            assert_eq!(msg.msg_type(), MsgType::Heartbeat);
+            assert_eq!(start.elapsed(), Duration::from_secs(2));
+
+            // Input timeout (with padding), counterparty is silent
+            let msg = counterparty.recv().await.unwrap();
+            let Message::Logout(logout) = &*msg.body else {
+                panic!("unexpected message: {:?}", msg.msg_type());
+            };
+            assert_eq!(logout.text, Some(fix_string("Grace period is over")));
+            assert_eq!(start.elapsed(), Duration::from_millis(2250));
+        })
+        .await;
+}
+
+/// Next message has to be Logout<5>, returns its text.
+async fn recv_logout(counterparty: &mut Counterparty) -> Option<String> {
+    let msg = counterparty.recv().await.unwrap();
+    let Message::Logout(logout) = &*msg.body else {
+        panic!("unexpected message: {:?}", msg.msg_type());
+    };
+    logout.text.as_ref().map(|text| text.to_string())
+}
+
+#[tokio::test]
+async fn unregistered_session_is_logged_out() {
+    LocalSet::new()
+        .run_until(async {
+            let mut acceptor = Acceptor::new(settings(), Box::new(|_| InMemoryStorage::new()));
+            acceptor.register_session(session_id("client"), session_settings(session_id("client")));
+            let acceptor = Rc::new(RefCell::new(acceptor));
+            let mut events = drain_events(acceptor.clone());
+
+            let mut counterparty = Counterparty::connect(&acceptor.borrow());
+            counterparty.send(&logon("client", 1)).await;
+            let msg = counterparty.recv().await.unwrap();
+            assert_eq!(msg.msg_type(), MsgType::Logon);
+            assert!(matches!(events.recv().await, Some(Event::Logon(_))));
+
+            let session_settings = acceptor
+                .borrow_mut()
+                .unregister_session(&session_id("client"))
+                .unwrap();
+            assert_eq!(session_settings.session_id, session_id("client"));
+            assert_eq!(
+                recv_logout(&mut counterparty).await.as_deref(),
+                Some("Session removed")
+            );
+            counterparty.closed().await;
+            assert!(matches!(events.recv().await, Some(Event::Logout(..))));
+            assert!(acceptor.borrow().session(&session_id("client")).is_none());
+            assert!(acceptor.borrow().sessions().is_empty());
+            assert!(acceptor
+                .borrow_mut()
+                .unregister_session(&session_id("client"))
+                .is_none());
+
+            // Unknown session is rejected on logon
+            let mut counterparty = Counterparty::connect(&acceptor.borrow());
+            counterparty.send(&logon("client", 1)).await;
+            let msg = counterparty.recv().await.unwrap();
+            assert_eq!(msg.msg_type(), MsgType::Logout);
+            counterparty.closed().await;
+        })
+        .await;
+}
+
+#[tokio::test]
+async fn updated_session_is_disconnected_on_request() {
+    LocalSet::new()
+        .run_until(async {
+            let mut acceptor = Acceptor::new(settings(), Box::new(|_| InMemoryStorage::new()));
+            acceptor.register_session(session_id("client"), session_settings(session_id("client")));
+            let acceptor = Rc::new(RefCell::new(acceptor));
+            let mut events = drain_events(acceptor.clone());
+
+            let mut counterparty = Counterparty::connect(&acceptor.borrow());
+            counterparty.send(&logon("client", 1)).await;
+            let msg = counterparty.recv().await.unwrap();
+            assert_eq!(msg.msg_type(), MsgType::Logon);
+            assert!(matches!(events.recv().await, Some(Event::Logon(_))));
+
+            // Connected session is kept
+            let mut new_settings = session_settings(session_id("client"));
+            new_settings.check_latency = false;
+            let prev_settings = acceptor
+                .borrow_mut()
+                .update_session(&session_id("client"), new_settings.clone(), false)
+                .unwrap();
+            assert!(prev_settings.check_latency);
+            let session = acceptor.borrow().session(&session_id("client")).unwrap();
+            assert!(session.logged_on);
+            assert!(!session.session_settings.check_latency);
+            counterparty
+                .send(&message(
+                    "client",
+                    2,
+                    Message::TestRequest(TestRequest {
+                        test_req_id: fix_string("test"),
+                        ..Default::default()
+                    }),
+                ))
+                .await;
+            let msg = counterparty.recv().await.unwrap();
+            assert_eq!(msg.msg_type(), MsgType::Heartbeat);
+
+            // Connected session is logged out
+            acceptor
+                .borrow_mut()
+                .update_session(&session_id("client"), new_settings.clone(), true)
+                .unwrap();
+            assert_eq!(
+                recv_logout(&mut counterparty).await.as_deref(),
+                Some("Session settings changed")
+            );
+            counterparty.closed().await;
+            assert!(matches!(events.recv().await, Some(Event::Logout(..))));
+
+            // Sequence numbers are preserved
+            let session = acceptor.borrow().session(&session_id("client")).unwrap();
+            assert!(!session.connected);
+            assert_eq!(session.next_sender_msg_seq_num, 4);
+            assert_eq!(session.next_target_msg_seq_num, 3);
+
+            // Unknown session is not registered
+            assert!(acceptor
+                .borrow_mut()
+                .update_session(&session_id("other"), new_settings, true)
+                .is_none());
+            assert!(acceptor.borrow().session(&session_id("other")).is_none());
+        })
+        .await;
+}
+
+#[tokio::test]
+async fn sessions_lists_registered_sessions() {
+    LocalSet::new()
+        .run_until(async {
+            let mut acceptor = Acceptor::new(settings(), Box::new(|_| InMemoryStorage::new()));
+            acceptor.register_session(session_id("client"), session_settings(session_id("client")));
+            acceptor.register_session(session_id("other"), session_settings(session_id("other")));
+            let acceptor = Rc::new(RefCell::new(acceptor));
+            let mut events = drain_events(acceptor.clone());
+
+            let mut counterparty = Counterparty::connect(&acceptor.borrow());
+            counterparty.send(&logon("client", 1)).await;
+            let msg = counterparty.recv().await.unwrap();
+            assert_eq!(msg.msg_type(), MsgType::Logon);
+            assert!(matches!(events.recv().await, Some(Event::Logon(_))));
+
+            let mut sessions = acceptor.borrow().sessions();
+            sessions.sort_by_key(|session| session.session_id.target_comp_id().to_string());
+            assert_eq!(sessions.len(), 2);
+            assert_eq!(sessions[0].session_id, session_id("client"));
+            assert!(sessions[0].connected);
+            assert!(sessions[0].logged_on);
+            assert_eq!(sessions[0].next_sender_msg_seq_num, 2);
+            assert_eq!(sessions[0].next_target_msg_seq_num, 2);
+            assert_eq!(sessions[1].session_id, session_id("other"));
+            assert!(!sessions[1].connected);
+            assert!(!sessions[1].logged_on);
+            assert_eq!(sessions[1].next_sender_msg_seq_num, 1);
+            assert_eq!(sessions[1].next_target_msg_seq_num, 1);
+        })
+        .await;
+}
+
+#[tokio::test]
+async fn acceptors_sharing_session_id_use_own_senders() {
+    LocalSet::new()
+        .run_until(async {
+            // Counterparty not used by other tests, global registry is
+            // shared by tests running in parallel
+            let mut connections = Vec::new();
+            for _ in 0..2 {
+                let mut acceptor = Acceptor::new(settings(), Box::new(|_| InMemoryStorage::new()));
+                acceptor
+                    .register_session(session_id("shared"), session_settings(session_id("shared")));
+                let acceptor = Rc::new(RefCell::new(acceptor));
+                let mut events = drain_events(acceptor.clone());
+                let mut counterparty = Counterparty::connect(&acceptor.borrow());
+                counterparty.send(&logon("shared", 1)).await;
+                let msg = counterparty.recv().await.unwrap();
+                assert_eq!(msg.msg_type(), MsgType::Logon);
+                assert!(matches!(events.recv().await, Some(Event::Logon(_))));
+                connections.push((acceptor, counterparty, events));
+            }
+
+            for (index, (acceptor, counterparty, _)) in connections.iter_mut().enumerate() {
+                let text = format!("acceptor {index}");
+                let sender = acceptor.borrow().sender(&session_id("shared")).unwrap();
+                sender.send(Box::new(app_message(&text))).unwrap();
+                let msg = counterparty.recv().await.unwrap();
+                let Message::BusinessMessageReject(reject) = &*msg.body else {
+                    panic!("unexpected message: {:?}", msg.msg_type());
+                };
+                assert_eq!(reject.text, Some(fix_string(&text)));
+            }
+
+            // Deprecated registry resolves sender of the first connected session,
+            // it's not unregistered when other session disconnects
+            #[allow(deprecated)]
+            let global_sender = || easyfix_session::io::sender(&session_id("shared"));
+            let (acceptor, mut counterparty, mut events) = connections.pop().unwrap();
+            counterparty
+                .send(&message("shared", 2, Message::Logout(Logout::default())))
+                .await;
+            let msg = counterparty.recv().await.unwrap();
+            assert_eq!(msg.msg_type(), MsgType::Logout);
+            counterparty.closed().await;
+            assert!(matches!(events.recv().await, Some(Event::Logout(..))));
+            assert!(acceptor.borrow().sender(&session_id("shared")).is_none());
+            global_sender()
+                .unwrap()
+                .send(Box::new(app_message("global")))
+                .unwrap();
+            let msg = connections[0].1.recv().await.unwrap();
+            assert_eq!(msg.header.msg_seq_num, 3);
+
+            let (_acceptor, mut counterparty, mut events) = connections.pop().unwrap();
+            counterparty
+                .send(&message("shared", 2, Message::Logout(Logout::default())))
+                .await;
+            let msg = counterparty.recv().await.unwrap();
+            assert_eq!(msg.msg_type(), MsgType::Logout);
+            counterparty.closed().await;
+            assert!(matches!(events.recv().await, Some(Event::Logout(..))));
+            assert!(global_sender().is_none());
+        })
+        .await;
+}
@@ -0,0 +1,254 @@
+//! In-process counterparty for driving acceptor sessions in tests.
+#![allow(dead_code)]
+
+use std::{cell::RefCell, future::poll_fn, net::SocketAddr, rc::Rc, task::Poll, time::Duration};
+
+use bytes::Bytes;
+use chrono::NaiveTime;
+use easyfix_messages::{
+    deserializer::UnknownFields,
+    fields::{DefaultApplVerId, FixString, SeqNum, UtcTimestamp},
+    messages::{BusinessMessageReject, FixtMessage, Logon, Message},
+    serializer::Serializer,
+    session::SessionMessage,
+};
+use easyfix_session::{
+    acceptor::{Acceptor, PeerInfo},
+    application::{AsEvent, FixEvent},
+    io::{input_stream_with_codec, InputEvent, InputStream},
+    messages_storage::MessagesStorage,
+    new_header, new_trailer,
+    session_id::SessionId,
+    settings::{SessionSettings, Settings},
+    DisconnectReason,
+};
+use futures::{Stream, StreamExt};
+use tokio::{
+    io::{duplex, AsyncWriteExt, DuplexStream, ReadHalf, WriteHalf},
+    sync::mpsc,
+    time::timeout,
+};
+
+pub const ACCEPTOR: &str = "acceptor";
+const RECV_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub fn fix_string(s: &str) -> FixString {
+    FixString::from_ascii_lossy(s.as_bytes().to_vec())
+}
+
+pub fn settings() -> Settings {
+    Settings {
+        sender_comp_id: fix_string(ACCEPTOR),
+        sender_sub_id: None,
+        heartbeat_interval: Duration::from_secs(30),
+        min_heartbeat_interval: None,
+        max_heartbeat_interval: None,
+        auto_disconnect_after_no_logon_received: Duration::from_secs(1),
+        auto_disconnect_after_no_heartbeat: 1,
+    }
+}
+
+/// Acceptor side id of session with `counterparty`.
+pub fn session_id(counterparty: &str) -> SessionId {
+    SessionId::new(
+        fix_string("FIXT.1.1"),
+        fix_string(ACCEPTOR),
+        fix_string(counterparty),
+    )
+}
+
+pub fn session_settings(session_id: SessionId) -> SessionSettings {
+    SessionSettings {
+        session_id,
+        session_time: None,
+        logon_time: NaiveTime::MIN..=NaiveTime::from_hms_opt(23, 59, 59).unwrap(),
+        send_redundant_resend_requests: false,
+        check_comp_id: true,
+        check_latency: true,
+        max_latency: Duration::from_secs(120),
+        reset_on_logon: false,
+        reset_on_logout: false,
+        reset_on_disconnect: false,
+        refresh_on_logon: false,
+        sender_default_appl_ver_id: fix_string("9"),
+        target_default_appl_ver_id: fix_string("9"),
+        enable_next_expected_msg_seq_num: false,
+        persist: true,
+        persist_inbound: false,
+        verify_logout: true,
+        tls_client_identity: None,
+        outbound_queue_limit: None,
+        throttle: None,
+        unknown_fields: UnknownFields::Reject,
+    }
+}
+
+/// Message sent by `counterparty` to the acceptor.
+pub fn message(counterparty: &str, msg_seq_num: SeqNum, body: Message) -> Box<FixtMessage> {
+    let mut header = new_header(body.msg_type());
+    header.begin_string = fix_string("FIXT.1.1");
+    header.sender_comp_id = fix_string(counterparty);
+    header.target_comp_id = fix_string(ACCEPTOR);
+    header.msg_seq_num = msg_seq_num;
+    header.sending_time = UtcTimestamp::now();
+    Box::new(FixtMessage {
+        header: Box::new(header),
+        body: Box::new(body),
+        trailer: Box::new(new_trailer()),
+    })
+}
+
+pub fn logon(counterparty: &str, msg_seq_num: SeqNum) -> Box<FixtMessage> {
+    message(
+        counterparty,
+        msg_seq_num,
+        Message::Logon(Logon {
+            heart_bt_int: 30,
+            default_appl_ver_id: DefaultApplVerId::Fix50Sp2,
+            ..Default::default()
+        }),
+    )
+}
+
+/// Application message with `text` (BusinessMessageReject<j>).
+pub fn app_message(text: &str) -> Message {
+    Message::BusinessMessageReject(BusinessMessageReject {
+        ref_msg_type: fix_string("D"),
+        text: Some(fix_string(text)),
+        ..Default::default()
+    })
+}
+
+/// Session events seen by the application.
+#[derive(Debug)]
+pub enum Event<M: SessionMessage = FixtMessage> {
+    Connecting(SocketAddr),
+    ConnectionFailed(SocketAddr),
+    Logon(SessionId),
+    Logout(SessionId, DisconnectReason),
+    AppMsgIn(Box<M>),
+    AppMsgGapFilled(Box<M>),
+    AppMsgResend(SessionId, Box<M>),
+    MsgThrottled(M::MsgType, Duration),
+    MsgThrottleRejected(Box<M>),
+}
+
+/// Poll acceptor (or initiator) events in background task, so sessions
+/// are never blocked on full events channel. Acceptor stays available to
+/// the test (it's borrowed only while polled).
+pub fn drain_events<T, M>(acceptor: Rc<RefCell<T>>) -> mpsc::UnboundedReceiver<Event<M>>
+where
+    T: Stream + Unpin + 'static,
+    T::Item: AsEvent<M>,
+    M: SessionMessage,
+{
+    handle_events(acceptor, event)
+}
+
+/// Like `drain_events`, with events passed to `handler` first, i.e. to
+/// use `Responder`.
+pub fn handle_events<T, M>(
+    acceptor: Rc<RefCell<T>>,
+    mut handler: impl FnMut(FixEvent<'_, M>) -> Option<Event<M>> + 'static,
+) -> mpsc::UnboundedReceiver<Event<M>>
+where
+    T: Stream + Unpin + 'static,
+    T::Item: AsEvent<M>,
+    M: SessionMessage,
+{
+    let (events_tx, events_rx) = mpsc::unbounded_channel();
+    tokio::task::spawn_local(async move {
+        loop {
+            let entry = poll_fn(|cx| match acceptor.try_borrow_mut() {
+                Ok(mut acceptor) => acceptor.poll_next_unpin(cx),
+                Err(_) => {
+                    // Used by the test right now, try again later
+                    cx.waker().wake_by_ref();
+                    Poll::Pending
+                }
+            })
+            .await;
+            let Some(mut entry) = entry else {
+                break;
+            };
+            if let Some(event) = handler(entry.as_event()) {
+                let _ = events_tx.send(event);
+            }
+        }
+    });
+    events_rx
+}
+
+/// Default mapping of `FixEvent` to `Event`.
+pub fn event<M: SessionMessage>(fix_event: FixEvent<'_, M>) -> Option<Event<M>> {
+    let event = match fix_event {
+        FixEvent::Connecting(_, addr) => Event::Connecting(addr),
+        FixEvent::ConnectionFailed(_, addr, _) => Event::ConnectionFailed(addr),
+        FixEvent::Logon(session_id, _) => Event::Logon(session_id.clone()),
+        FixEvent::Logout(session_id, reason) => Event::Logout(session_id.clone(), reason),
+        FixEvent::AppMsgIn(msg, _) => Event::AppMsgIn(msg),
+        FixEvent::AppMsgGapFilled(_, msg) => Event::AppMsgGapFilled(Box::new(msg.clone())),
+        FixEvent::AppMsgResend(session_id, msg, _) => {
+            Event::AppMsgResend(session_id.clone(), Box::new(msg.clone()))
+        }
+        FixEvent::MsgThrottled(_, msg_type, delay) => Event::MsgThrottled(msg_type, delay),
+        FixEvent::MsgThrottleRejected(_, msg) => Event::MsgThrottleRejected(Box::new(msg.clone())),
+        _ => return None,
+    };
+    Some(event)
+}
+
+/// Counterparty connected to the acceptor with in-memory stream.
+pub struct Counterparty<M: SessionMessage = FixtMessage> {
+    input: InputStream<ReadHalf<DuplexStream>, M>,
+    writer: WriteHalf<DuplexStream>,
+}
+
+impl<M: SessionMessage> Counterparty<M> {
+    pub fn connect<S: MessagesStorage + 'static>(acceptor: &Acceptor<S, M>) -> Counterparty<M> {
+        let (client, server) = duplex(64 * 1024);
+        let (reader, writer) = tokio::io::split(server);
+        let peer_info = PeerInfo::from("127.0.0.1:1".parse::<std::net::SocketAddr>().unwrap());
+        tokio::task::spawn_local(acceptor.run_session_task(peer_info, reader, writer));
+        let (reader, writer) = tokio::io::split(client);
+        Counterparty {
+            input: input_stream_with_codec(reader),
+            writer,
+        }
+    }
+
+    pub async fn send(&mut self, msg: &M) {
+        self.send_bytes(&msg.serialize_with(Serializer::new()))
+            .await;
+    }
+
+    pub async fn send_bytes(&mut self, bytes: &[u8]) {
+        self.writer.write_all(bytes).await.unwrap();
+        self.writer.flush().await.unwrap();
+    }
+
+    /// Next message from the acceptor, `None` when connection is closed.
+    pub async fn recv(&mut self) -> Option<Box<M>> {
+        self.recv_raw().await.map(|(msg, _)| msg)
+    }
+
+    /// Like `recv`, with message bytes as received.
+    pub async fn recv_raw(&mut self) -> Option<(Box<M>, Bytes)> {
+        match timeout(RECV_TIMEOUT, self.input.next())
+            .await
+            .expect("no message from acceptor")
+        {
+            Some(InputEvent::Message(msg, raw)) => Some((msg, raw)),
+            Some(InputEvent::DeserializeError(err)) => panic!("malformed message: {err}"),
+            Some(InputEvent::IoError(_)) | None => None,
+            Some(InputEvent::Timeout) => unreachable!(),
+        }
+    }
+
+    /// Wait until acceptor closes the connection, panics on any message.
+    pub async fn closed(&mut self) {
+        if let Some(msg) = self.recv().await {
+            panic!("unexpected message: {:?}", msg.msg_type());
+        }
+    }
+}
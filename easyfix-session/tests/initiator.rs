@@ -0,0 +1,95 @@
+mod common;
+
+use std::{cell::RefCell, net::SocketAddr, rc::Rc, time::Duration};
+
+use common::{drain_events, fix_string, session_settings, settings, Event, ACCEPTOR};
+use easyfix_session::{
+    acceptor::{Acceptor, TcpConnection},
+    initiator::Initiator,
+    messages_storage::InMemoryStorage,
+    session_id::SessionId,
+    settings::{ReconnectSettings, Settings},
+};
+use tokio::{net::TcpListener, task::LocalSet};
+
+const LOCALHOST: ([u8; 4], u16) = ([127, 0, 0, 1], 0);
+
+#[tokio::test]
+async fn failover_to_backup_address() {
+    LocalSet::new()
+        .run_until(async {
+            let mut acceptor = Acceptor::new(settings(), Box::new(|_| InMemoryStorage::new()));
+            acceptor.register_session(
+                common::session_id("client"),
+                session_settings(common::session_id("client")),
+            );
+            let connection = TcpConnection::new(LOCALHOST).await.unwrap();
+            let backup = connection.local_addr().unwrap();
+            acceptor.start(connection);
+            let acceptor = Rc::new(RefCell::new(acceptor));
+            let mut acceptor_events = drain_events(acceptor.clone());
+
+            // Nothing listens on primary address
+            let refused = TcpListener::bind(SocketAddr::from(LOCALHOST))
+                .await
+                .unwrap()
+                .local_addr()
+                .unwrap();
+            // Connection to secondary address is closed before logon
+            let listener = TcpListener::bind(SocketAddr::from(LOCALHOST))
+                .await
+                .unwrap();
+            let closing = listener.local_addr().unwrap();
+            tokio::task::spawn_local(async move {
+                while let Ok((tcp_stream, _)) = listener.accept().await {
+                    drop(tcp_stream);
+                }
+            });
+
+            let session_id = SessionId::new(
+                fix_string("FIXT.1.1"),
+                fix_string("client"),
+                fix_string(ACCEPTOR),
+            );
+            let initiator = Initiator::new(
+                Settings {
+                    sender_comp_id: fix_string("client"),
+                    ..settings()
+                },
+                session_settings(session_id),
+                InMemoryStorage::new(),
+            );
+            let handle = initiator.start(
+                [refused, closing, backup],
+                ReconnectSettings {
+                    initial_delay: Duration::from_secs(1),
+                    max_delay: Duration::from_secs(10),
+                    multiplier: 2,
+                    connect_timeout: Duration::from_secs(1),
+                },
+            );
+            let initiator = Rc::new(RefCell::new(initiator));
+            let mut events = drain_events(initiator.clone());
+
+            assert!(matches!(events.recv().await, Some(Event::Connecting(addr)) if addr == refused));
+            assert!(
+                matches!(events.recv().await, Some(Event::ConnectionFailed(addr)) if addr == refused)
+            );
+            assert!(matches!(events.recv().await, Some(Event::Connecting(addr)) if addr == closing));
+            assert!(matches!(events.recv().await, Some(Event::Connecting(addr)) if addr == backup));
+            assert!(matches!(events.recv().await, Some(Event::Logon(_))));
+            assert!(matches!(acceptor_events.recv().await, Some(Event::Logon(_))));
+            assert!(initiator.borrow().sender().is_some());
+
+            // Stopped initiator drops the connection
+            handle.abort();
+            assert!(handle.await.unwrap_err().is_cancelled());
+            assert!(initiator.borrow().sender().is_none());
+            assert!(!initiator.borrow().session().connected);
+            assert!(matches!(
+                acceptor_events.recv().await,
+                Some(Event::Logout(..))
+            ));
+        })
+        .await;
+}
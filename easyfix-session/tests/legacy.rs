@@ -0,0 +1,142 @@
+mod common;
+
+use std::{cell::RefCell, rc::Rc};
+
+use common::{drain_events, fix_string, session_settings, settings, Counterparty, Event, ACCEPTOR};
+use easyfix_messages::{fields::SeqNum, session::SessionMessage};
+use easyfix_messages_multi_version_test::fix42::{
+    fields::{
+        EncryptMethod, ExecTransType, ExecType, IdSource, OrdStatus, Price, Qty, Side, UtcTimestamp,
+    },
+    messages::{ExecutionReport, FixtMessage, Logon, Message, TestRequest},
+};
+use easyfix_session::{
+    acceptor::Acceptor, messages_storage::InMemoryStorage, session_id::SessionId,
+};
+use tokio::task::LocalSet;
+
+fn session_id() -> SessionId {
+    SessionId::new(
+        fix_string("FIX.4.2"),
+        fix_string(ACCEPTOR),
+        fix_string("client"),
+    )
+}
+
+/// Acceptor of FIX.4.2 session, on codec generated from FIX.4.2 dictionary.
+fn acceptor() -> Acceptor<InMemoryStorage, FixtMessage> {
+    let mut acceptor = Acceptor::with_codec(settings(), Box::new(|_| InMemoryStorage::new()));
+    acceptor.register_session(session_id(), session_settings(session_id()));
+    acceptor
+}
+
+/// FIX.4.2 message sent by the counterparty to the acceptor.
+fn message(msg_seq_num: SeqNum, body: Message) -> Box<FixtMessage> {
+    let mut msg = <FixtMessage as SessionMessage>::from_body(Box::new(body));
+    msg.header.begin_string = fix_string("FIX.4.2");
+    msg.header.sender_comp_id = fix_string("client");
+    msg.header.target_comp_id = fix_string(ACCEPTOR);
+    msg.header.msg_seq_num = msg_seq_num;
+    msg.header.sending_time = UtcTimestamp::now();
+    msg
+}
+
+fn logon() -> Box<FixtMessage> {
+    message(
+        1,
+        Message::Logon(Logon {
+            encrypt_method: EncryptMethod::NoneOther,
+            heart_bt_int: 30,
+            ..Default::default()
+        }),
+    )
+}
+
+fn has_tag(raw: &[u8], tag: u16) -> bool {
+    let tag = format!("\x01{tag}=");
+    raw.windows(tag.len()).any(|field| field == tag.as_bytes())
+}
+
+#[tokio::test]
+async fn fix42_logon_and_heartbeat() {
+    LocalSet::new()
+        .run_until(async {
+            let acceptor = Rc::new(RefCell::new(acceptor()));
+            let mut events = drain_events(acceptor.clone());
+
+            let mut counterparty = Counterparty::connect(&acceptor.borrow());
+            counterparty.send(&logon()).await;
+            let (msg, raw) = counterparty.recv_raw().await.unwrap();
+            assert!(matches!(*msg.body, Message::Logon(_)));
+            assert_eq!(msg.header.begin_string, fix_string("FIX.4.2"));
+            // No DefaultApplVerID<1137>, FIXT only
+            assert!(!has_tag(&raw, 1137));
+            assert!(matches!(
+                events.recv().await,
+                Some(Event::Logon(id)) if id == session_id()
+            ));
+
+            let test_request = message(
+                2,
+                Message::TestRequest(TestRequest {
+                    test_req_id: fix_string("test"),
+                    ..Default::default()
+                }),
+            );
+            counterparty.send(&test_request).await;
+            let msg = counterparty.recv().await.unwrap();
+            let Message::Heartbeat(heartbeat) = &*msg.body else {
+                panic!("unexpected message: {:?}", msg.msg_type());
+            };
+            assert_eq!(heartbeat.test_req_id, Some(fix_string("test")));
+            assert_eq!(msg.header.begin_string, fix_string("FIX.4.2"));
+        })
+        .await;
+}
+
+#[tokio::test]
+async fn fix42_fields_removed_from_fix50_are_accepted() {
+    LocalSet::new()
+        .run_until(async {
+            let acceptor = Rc::new(RefCell::new(acceptor()));
+            let mut events = drain_events(acceptor.clone());
+
+            let mut counterparty = Counterparty::connect(&acceptor.borrow());
+            counterparty.send(&logon()).await;
+            counterparty.recv().await.unwrap();
+
+            // ExecTransType<20> and IDSource<22>, not defined in FIX.5.0SP2
+            let execution_report = message(
+                2,
+                Message::ExecutionReport(ExecutionReport {
+                    order_id: fix_string("order"),
+                    exec_id: fix_string("exec"),
+                    exec_trans_type: ExecTransType::Correct,
+                    exec_type: ExecType::Fill,
+                    ord_status: OrdStatus::Filled,
+                    symbol: fix_string("EASY"),
+                    security_id: Some(fix_string("US0000000000")),
+                    id_source: Some(IdSource::IsinNumber),
+                    side: Side::Buy,
+                    leaves_qty: Qty::ZERO,
+                    cum_qty: Qty::from(100),
+                    avg_px: Price::from(10),
+                    ..Default::default()
+                }),
+            );
+            counterparty.send(&execution_report).await;
+            let msg = loop {
+                match events.recv().await {
+                    Some(Event::AppMsgIn(msg)) => break msg,
+                    Some(_) => continue,
+                    None => panic!("no ExecutionReport received"),
+                }
+            };
+            let Message::ExecutionReport(execution_report) = &*msg.body else {
+                panic!("unexpected message: {:?}", msg.msg_type());
+            };
+            assert_eq!(execution_report.exec_trans_type, ExecTransType::Correct);
+            assert_eq!(execution_report.id_source, Some(IdSource::IsinNumber));
+        })
+        .await;
+}
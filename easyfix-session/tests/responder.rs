@@ -0,0 +1,203 @@
+mod common;
+
+use std::{cell::RefCell, rc::Rc};
+
+use common::{
+    app_message, event, fix_string, handle_events, logon, message, session_id, session_settings,
+    settings, Counterparty, Event,
+};
+use easyfix_messages::{
+    fields::{FixString, MsgType},
+    messages::{FixtMessage, Message, ResendRequest},
+};
+use easyfix_session::{
+    acceptor::Acceptor, application::FixEvent, messages_storage::InMemoryStorage, Delivery,
+};
+use tokio::task::LocalSet;
+
+fn text(msg: &FixtMessage) -> Option<&FixString> {
+    match &*msg.body {
+        Message::BusinessMessageReject(msg) => msg.text.as_ref(),
+        _ => None,
+    }
+}
+
+#[tokio::test]
+async fn app_message_changed_to_gap_fill() {
+    LocalSet::new()
+        .run_until(async {
+            let mut acceptor = Acceptor::new(settings(), Box::new(|_| InMemoryStorage::new()));
+            acceptor.register_session(session_id("client"), session_settings(session_id("client")));
+            let acceptor = Rc::new(RefCell::new(acceptor));
+            let mut events = handle_events(acceptor.clone(), |fix_event| match fix_event {
+                FixEvent::AppMsgOut(msg, responder) => {
+                    if text(msg) == Some(&fix_string("gap fill")) {
+                        responder.change_to_gap_fill();
+                    }
+                    None
+                }
+                fix_event => event(fix_event),
+            });
+
+            let mut counterparty = Counterparty::connect(&acceptor.borrow());
+            counterparty.send(&logon("client", 1)).await;
+            let msg = counterparty.recv().await.unwrap();
+            assert_eq!(msg.msg_type(), MsgType::Logon);
+            assert!(matches!(events.recv().await, Some(Event::Logon(_))));
+
+            let sender = acceptor.borrow().sender(&session_id("client")).unwrap();
+            sender.send(Box::new(app_message("gap fill"))).unwrap();
+            sender.send(Box::new(app_message("send"))).unwrap();
+
+            let msg = counterparty.recv().await.unwrap();
+            let Message::SequenceReset(sequence_reset) = &*msg.body else {
+                panic!("unexpected message: {:?}", msg.msg_type());
+            };
+            assert_eq!(msg.header.msg_seq_num, 2);
+            assert_eq!(msg.header.poss_dup_flag, None);
+            assert_eq!(sequence_reset.gap_fill_flag, Some(true));
+            assert_eq!(sequence_reset.new_seq_no, 3);
+
+            let msg = counterparty.recv().await.unwrap();
+            assert_eq!(msg.header.msg_seq_num, 3);
+            assert_eq!(text(&msg), Some(&fix_string("send")));
+
+            let Some(Event::AppMsgGapFilled(msg)) = events.recv().await else {
+                panic!("AppMsgGapFilled expected");
+            };
+            assert_eq!(msg.header.msg_seq_num, 2);
+            assert_eq!(text(&msg), Some(&fix_string("gap fill")));
+        })
+        .await;
+}
+
+#[tokio::test]
+async fn resent_messages_dropped_gap_filled_or_refreshed() {
+    LocalSet::new()
+        .run_until(async {
+            let mut acceptor = Acceptor::new(settings(), Box::new(|_| InMemoryStorage::new()));
+            acceptor.register_session(session_id("client"), session_settings(session_id("client")));
+            let acceptor = Rc::new(RefCell::new(acceptor));
+            let mut events = handle_events(acceptor.clone(), |mut fix_event| {
+                if let FixEvent::AppMsgResend(_, msg, responder) = &mut fix_event {
+                    match text(msg).map(|text| text.as_bytes()) {
+                        Some(b"drop") => responder.do_not_send(),
+                        Some(b"gap fill") => responder.change_to_gap_fill(),
+                        Some(b"refresh") => {
+                            if let Message::BusinessMessageReject(msg) = &mut *msg.body {
+                                msg.text = Some(fix_string("refreshed"));
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                event(fix_event)
+            });
+
+            let mut counterparty = Counterparty::connect(&acceptor.borrow());
+            counterparty.send(&logon("client", 1)).await;
+            let msg = counterparty.recv().await.unwrap();
+            assert_eq!(msg.msg_type(), MsgType::Logon);
+            assert!(matches!(events.recv().await, Some(Event::Logon(_))));
+
+            let sender = acceptor.borrow().sender(&session_id("client")).unwrap();
+            for text in ["drop", "gap fill", "refresh", "keep"] {
+                sender.send(Box::new(app_message(text))).unwrap();
+            }
+            for msg_seq_num in 2..=5 {
+                let msg = counterparty.recv().await.unwrap();
+                assert_eq!(msg.header.msg_seq_num, msg_seq_num);
+            }
+
+            counterparty
+                .send(&message(
+                    "client",
+                    2,
+                    Message::ResendRequest(ResendRequest {
+                        begin_seq_no: 2,
+                        end_seq_no: 0,
+                        ..Default::default()
+                    }),
+                ))
+                .await;
+
+            // Dropped and gap filled messages are replaced with single gap fill
+            let msg = counterparty.recv().await.unwrap();
+            let Message::SequenceReset(sequence_reset) = &*msg.body else {
+                panic!("unexpected message: {:?}", msg.msg_type());
+            };
+            assert_eq!(msg.header.msg_seq_num, 2);
+            assert_eq!(msg.header.poss_dup_flag, Some(true));
+            assert_eq!(sequence_reset.gap_fill_flag, Some(true));
+            assert_eq!(sequence_reset.new_seq_no, 4);
+
+            let msg = counterparty.recv().await.unwrap();
+            assert_eq!(msg.header.msg_seq_num, 4);
+            assert_eq!(msg.header.poss_dup_flag, Some(true));
+            assert!(msg.header.orig_sending_time.is_some());
+            assert_eq!(text(&msg), Some(&fix_string("refreshed")));
+
+            let msg = counterparty.recv().await.unwrap();
+            assert_eq!(msg.header.msg_seq_num, 5);
+            assert_eq!(msg.header.poss_dup_flag, Some(true));
+            assert_eq!(text(&msg), Some(&fix_string("keep")));
+
+            for msg_seq_num in 2..=5 {
+                let Some(Event::AppMsgResend(resend_session_id, msg)) = events.recv().await else {
+                    panic!("AppMsgResend expected");
+                };
+                assert_eq!(resend_session_id, session_id("client"));
+                assert_eq!(msg.header.msg_seq_num, msg_seq_num);
+            }
+        })
+        .await;
+}
+
+#[tokio::test]
+async fn confirmed_messages_resolve_with_outcome() {
+    LocalSet::new()
+        .run_until(async {
+            let mut acceptor = Acceptor::new(settings(), Box::new(|_| InMemoryStorage::new()));
+            acceptor.register_session(session_id("client"), session_settings(session_id("client")));
+            let acceptor = Rc::new(RefCell::new(acceptor));
+            let mut events = handle_events(acceptor.clone(), |fix_event| match fix_event {
+                FixEvent::AppMsgOut(msg, responder) => {
+                    match text(msg).map(|text| text.as_bytes()) {
+                        Some(b"drop") => responder.do_not_send(),
+                        Some(b"gap fill") => responder.change_to_gap_fill(),
+                        _ => {}
+                    }
+                    None
+                }
+                fix_event => event(fix_event),
+            });
+
+            let mut counterparty = Counterparty::connect(&acceptor.borrow());
+            counterparty.send(&logon("client", 1)).await;
+            let msg = counterparty.recv().await.unwrap();
+            assert_eq!(msg.msg_type(), MsgType::Logon);
+            assert!(matches!(events.recv().await, Some(Event::Logon(_))));
+
+            let sender = acceptor.borrow().sender(&session_id("client")).unwrap();
+            let handles = ["send", "drop", "gap fill", "send again"]
+                .map(|text| sender.send(Box::new(app_message(text))).unwrap());
+
+            let msg = counterparty.recv().await.unwrap();
+            assert_eq!(msg.header.msg_seq_num, 2);
+            assert_eq!(text(&msg), Some(&fix_string("send")));
+            // Discarded message leaves a gap
+            let msg = counterparty.recv().await.unwrap();
+            assert_eq!(msg.msg_type(), MsgType::SequenceReset);
+            assert_eq!(msg.header.msg_seq_num, 4);
+            let msg = counterparty.recv().await.unwrap();
+            assert_eq!(msg.header.msg_seq_num, 5);
+            assert_eq!(text(&msg), Some(&fix_string("send again")));
+
+            let [sent, dropped, gap_filled, sent_again] = handles;
+            assert_eq!(sent.await, Delivery::Written(2));
+            assert_eq!(dropped.await, Delivery::Discarded(3));
+            assert_eq!(gap_filled.await, Delivery::GapFilled(4));
+            assert_eq!(sent_again.await, Delivery::Written(5));
+        })
+        .await;
+}
@@ -0,0 +1,217 @@
+mod common;
+
+use std::{cell::RefCell, ops::RangeInclusive, rc::Rc, time::Duration};
+
+use common::{
+    drain_events, fix_string, logon, message, session_id, session_settings, settings, Counterparty,
+    Event,
+};
+use easyfix_messages::{
+    fields::{MsgType, SeqNum},
+    messages::{Logout, Message},
+};
+use easyfix_session::{
+    acceptor::{Acceptor, TcpConnection},
+    messages_storage::{InMemoryStorage, MessagesStorage},
+    session_id::SessionId,
+    DisconnectReason,
+};
+use futures::{future::LocalBoxFuture, stream::LocalBoxStream, FutureExt};
+use tokio::{
+    net::TcpStream,
+    sync::Notify,
+    task::LocalSet,
+    time::{timeout, Instant},
+};
+
+const LOGOUT_TIMEOUT: Duration = Duration::from_secs(1);
+
+fn acceptor<S: MessagesStorage + 'static>(
+    storage_builder: Box<dyn Fn(&SessionId) -> S>,
+) -> Rc<RefCell<Acceptor<S>>> {
+    let mut acceptor = Acceptor::new(settings(), storage_builder);
+    acceptor.register_session(session_id("client"), session_settings(session_id("client")));
+    Rc::new(RefCell::new(acceptor))
+}
+
+/// Connect counterparty and complete Logon<A> exchange.
+async fn logged_on<S: MessagesStorage + 'static>(acceptor: &RefCell<Acceptor<S>>) -> Counterparty {
+    let mut counterparty = Counterparty::connect(&acceptor.borrow());
+    counterparty.send(&logon("client", 1)).await;
+    let msg = counterparty.recv().await.unwrap();
+    assert_eq!(msg.msg_type(), MsgType::Logon);
+    counterparty
+}
+
+/// Next message from the acceptor has to be Logout<5>, returns its text.
+async fn recv_logout(counterparty: &mut Counterparty) -> Option<String> {
+    let msg = counterparty.recv().await.unwrap();
+    let Message::Logout(logout) = &*msg.body else {
+        panic!("unexpected message: {:?}", msg.msg_type());
+    };
+    logout.text.as_ref().map(|text| text.to_string())
+}
+
+#[tokio::test(start_paused = true)]
+async fn confirmed_logout_completes_shutdown() {
+    LocalSet::new()
+        .run_until(async {
+            let acceptor = acceptor(Box::new(|_| InMemoryStorage::new()));
+            let mut events = drain_events(acceptor.clone());
+            let mut counterparty = logged_on(&acceptor).await;
+            assert!(matches!(events.recv().await, Some(Event::Logon(_))));
+
+            let start = Instant::now();
+            let shutdown = tokio::task::spawn_local(
+                acceptor
+                    .borrow()
+                    .shutdown(Some(fix_string("Maintenance")), LOGOUT_TIMEOUT),
+            );
+            assert_eq!(
+                recv_logout(&mut counterparty).await.as_deref(),
+                Some("Maintenance")
+            );
+            counterparty
+                .send(&message("client", 2, Message::Logout(Logout::default())))
+                .await;
+            counterparty.closed().await;
+
+            shutdown.await.unwrap();
+            assert!(start.elapsed() < LOGOUT_TIMEOUT);
+            assert!(matches!(events.recv().await, Some(Event::Logout(..))));
+        })
+        .await;
+}
+
+#[tokio::test(start_paused = true)]
+async fn unconfirmed_logout_is_disconnected_after_timeout() {
+    LocalSet::new()
+        .run_until(async {
+            let acceptor = acceptor(Box::new(|_| InMemoryStorage::new()));
+            let mut events = drain_events(acceptor.clone());
+            let mut counterparty = logged_on(&acceptor).await;
+            assert!(matches!(events.recv().await, Some(Event::Logon(_))));
+
+            let start = Instant::now();
+            let shutdown =
+                tokio::task::spawn_local(acceptor.borrow().shutdown(None, LOGOUT_TIMEOUT));
+            assert_eq!(recv_logout(&mut counterparty).await, None);
+            // Counterparty doesn't respond
+            counterparty.closed().await;
+
+            shutdown.await.unwrap();
+            assert_eq!(start.elapsed(), LOGOUT_TIMEOUT);
+            assert!(matches!(
+                events.recv().await,
+                Some(Event::Logout(_, DisconnectReason::LocalRequestedLogout))
+            ));
+        })
+        .await;
+}
+
+#[tokio::test]
+async fn new_connections_are_refused_after_shutdown() {
+    LocalSet::new()
+        .run_until(async {
+            let acceptor = acceptor(Box::new(|_| InMemoryStorage::new()));
+            let connection = TcpConnection::new(([127, 0, 0, 1], 0)).await.unwrap();
+            let addr = connection.local_addr().unwrap();
+            let server = acceptor.borrow().start(connection);
+            let _events = drain_events(acceptor.clone());
+
+            let shutdown = acceptor.borrow().shutdown(None, LOGOUT_TIMEOUT);
+            timeout(LOGOUT_TIMEOUT, shutdown).await.unwrap();
+            // Listener is closed when server task exits
+            timeout(LOGOUT_TIMEOUT, server).await.unwrap().unwrap();
+            assert!(TcpStream::connect(addr).await.is_err());
+
+            // Connections passed to the acceptor directly are closed
+            // without response
+            let mut counterparty = Counterparty::connect(&acceptor.borrow());
+            counterparty.send(&logon("client", 1)).await;
+            counterparty.closed().await;
+            let session = acceptor.borrow().session(&session_id("client")).unwrap();
+            assert!(!session.connected);
+        })
+        .await;
+}
+
+/// Storage with flush completed on demand.
+struct FlushStorage {
+    storage: InMemoryStorage,
+    flushed: Rc<Notify>,
+}
+
+impl MessagesStorage for FlushStorage {
+    fn fetch_range(&mut self, range: RangeInclusive<SeqNum>) -> LocalBoxStream<'static, Vec<u8>> {
+        self.storage.fetch_range(range)
+    }
+
+    fn store(&mut self, seq_num: SeqNum, data: &[u8]) {
+        self.storage.store(seq_num, data)
+    }
+
+    fn flush(&mut self) -> LocalBoxFuture<'static, ()> {
+        let flushed = self.flushed.clone();
+        async move { flushed.notified().await }.boxed_local()
+    }
+
+    fn next_sender_msg_seq_num(&self) -> SeqNum {
+        self.storage.next_sender_msg_seq_num()
+    }
+
+    fn next_target_msg_seq_num(&self) -> SeqNum {
+        self.storage.next_target_msg_seq_num()
+    }
+
+    fn set_next_sender_msg_seq_num(&mut self, seq_num: SeqNum) {
+        self.storage.set_next_sender_msg_seq_num(seq_num)
+    }
+
+    fn set_next_target_msg_seq_num(&mut self, seq_num: SeqNum) {
+        self.storage.set_next_target_msg_seq_num(seq_num)
+    }
+
+    fn incr_next_sender_msg_seq_num(&mut self) {
+        self.storage.incr_next_sender_msg_seq_num()
+    }
+
+    fn incr_next_target_msg_seq_num(&mut self) {
+        self.storage.incr_next_target_msg_seq_num()
+    }
+
+    fn reset(&mut self) {
+        self.storage.reset()
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn shutdown_waits_for_storage_flush() {
+    LocalSet::new()
+        .run_until(async {
+            let flushed = Rc::new(Notify::new());
+            let storage_flushed = flushed.clone();
+            let acceptor = acceptor(Box::new(move |_| FlushStorage {
+                storage: InMemoryStorage::new(),
+                flushed: storage_flushed.clone(),
+            }));
+            let _events = drain_events(acceptor.clone());
+            let mut counterparty = logged_on(&acceptor).await;
+
+            let mut shutdown = acceptor
+                .borrow()
+                .shutdown(None, LOGOUT_TIMEOUT)
+                .boxed_local();
+            recv_logout(&mut counterparty).await;
+            counterparty
+                .send(&message("client", 2, Message::Logout(Logout::default())))
+                .await;
+            counterparty.closed().await;
+
+            // Sessions are closed, storage is not flushed yet
+            assert!(timeout(LOGOUT_TIMEOUT * 2, &mut shutdown).await.is_err());
+            flushed.notify_one();
+            timeout(LOGOUT_TIMEOUT, shutdown).await.unwrap();
+        })
+        .await;
+}
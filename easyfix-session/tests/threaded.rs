@@ -0,0 +1,145 @@
+mod common;
+
+use std::{
+    future::poll_fn,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use common::{
+    app_message, event, fix_string, session_id, session_settings, settings, Event, ACCEPTOR,
+};
+use easyfix_messages::messages::{FixtMessage, Message};
+use easyfix_session::{
+    application::AsEvent,
+    messages_storage::InMemoryStorage,
+    session_id::SessionId,
+    settings::{ReconnectSettings, Settings},
+    threaded_acceptor::ThreadedAcceptor,
+    threaded_initiator::ThreadedInitiator,
+};
+use futures::{Stream, StreamExt};
+use tokio::{sync::mpsc, time::timeout};
+
+const RECV_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Poll events in background task, stream stays available to the test.
+fn forward_events<T>(stream: Arc<Mutex<T>>) -> mpsc::UnboundedReceiver<Event>
+where
+    T: Stream + Unpin + Send + 'static,
+    T::Item: AsEvent + Send,
+{
+    let (events_tx, events_rx) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        while let Some(mut entry) = poll_fn(|cx| stream.lock().unwrap().poll_next_unpin(cx)).await {
+            if let Some(event) = event(entry.as_event()) {
+                let _ = events_tx.send(event);
+            }
+        }
+    });
+    events_rx
+}
+
+async fn recv(events: &mut mpsc::UnboundedReceiver<Event>) -> Event {
+    timeout(RECV_TIMEOUT, events.recv())
+        .await
+        .expect("no event")
+        .unwrap()
+}
+
+fn text(msg: &FixtMessage) -> String {
+    match &*msg.body {
+        Message::BusinessMessageReject(msg) => msg.text.as_ref().unwrap().to_string(),
+        _ => panic!("unexpected message: {:?}", msg.msg_type()),
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn sessions_on_worker_threads() {
+    let acceptor =
+        ThreadedAcceptor::new(settings(), 2, Arc::new(|_| InMemoryStorage::new())).unwrap();
+    acceptor.register_session(session_id("client"), session_settings(session_id("client")));
+    // Free port, assigned by OS
+    let addr = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    acceptor.start(addr).await.unwrap();
+    let acceptor = Arc::new(Mutex::new(acceptor));
+    let mut acceptor_events = forward_events(acceptor.clone());
+
+    let initiator = ThreadedInitiator::new(
+        Settings {
+            sender_comp_id: fix_string("client"),
+            ..settings()
+        },
+        2,
+        Arc::new(|_| InMemoryStorage::new()),
+    )
+    .unwrap();
+    let initiator_session_id = SessionId::new(
+        fix_string("FIXT.1.1"),
+        fix_string("client"),
+        fix_string(ACCEPTOR),
+    );
+    initiator.start(
+        session_settings(initiator_session_id.clone()),
+        [addr],
+        ReconnectSettings {
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(1),
+            multiplier: 1,
+            connect_timeout: Duration::from_secs(1),
+        },
+    );
+    let initiator = Arc::new(Mutex::new(initiator));
+    let mut initiator_events = forward_events(initiator.clone());
+
+    assert!(matches!(
+        recv(&mut initiator_events).await,
+        Event::Connecting(_)
+    ));
+    assert!(matches!(recv(&mut initiator_events).await, Event::Logon(_)));
+    assert!(matches!(recv(&mut acceptor_events).await, Event::Logon(_)));
+
+    // Senders are used from other tasks
+    let sender = initiator.lock().unwrap().sender(&initiator_session_id);
+    let sender = sender.await.unwrap();
+    tokio::spawn(async move { sender.send(Box::new(app_message("from initiator"))) })
+        .await
+        .unwrap()
+        .unwrap();
+    let Event::AppMsgIn(msg) = recv(&mut acceptor_events).await else {
+        panic!("AppMsgIn expected");
+    };
+    assert_eq!(text(&msg), "from initiator");
+
+    let sender = acceptor.lock().unwrap().sender(&session_id("client"));
+    let sender = sender.await.unwrap();
+    tokio::spawn(async move { sender.send(Box::new(app_message("from acceptor"))) })
+        .await
+        .unwrap()
+        .unwrap();
+    let Event::AppMsgIn(msg) = recv(&mut initiator_events).await else {
+        panic!("AppMsgIn expected");
+    };
+    assert_eq!(text(&msg), "from acceptor");
+
+    // Logout is exchanged and confirmed before timeout
+    let shutdown = acceptor
+        .lock()
+        .unwrap()
+        .shutdown(Some(fix_string("shutdown")), Duration::from_secs(5));
+    timeout(Duration::from_secs(1), shutdown).await.unwrap();
+    assert!(matches!(
+        recv(&mut acceptor_events).await,
+        Event::Logout(..)
+    ));
+    assert!(matches!(
+        recv(&mut initiator_events).await,
+        Event::Logout(..)
+    ));
+
+    let stopped = initiator.lock().unwrap().stop(&initiator_session_id);
+    assert!(stopped.await);
+}
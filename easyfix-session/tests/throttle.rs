@@ -0,0 +1,211 @@
+mod common;
+
+use std::{cell::RefCell, num::NonZeroU32, rc::Rc, time::Duration};
+
+use common::{
+    app_message, drain_events, fix_string, logon, message, session_id, session_settings, settings,
+    Counterparty, Event,
+};
+use easyfix_messages::{
+    fields::MsgType,
+    messages::{Message, ResendRequest, TestRequest},
+};
+use easyfix_session::{
+    acceptor::Acceptor,
+    messages_storage::InMemoryStorage,
+    settings::{RateLimit, ThrottleAction, ThrottleSettings},
+};
+use tokio::{sync::mpsc, task::LocalSet, time::Instant};
+
+/// One message per second, without burst.
+fn rate_limit() -> Option<RateLimit> {
+    Some(RateLimit {
+        messages_per_second: NonZeroU32::MIN,
+        burst: None,
+    })
+}
+
+/// Acceptor with logged on counterparty.
+async fn logged_on(
+    throttle: ThrottleSettings,
+) -> (
+    Rc<RefCell<Acceptor<InMemoryStorage>>>,
+    Counterparty,
+    mpsc::UnboundedReceiver<Event>,
+) {
+    let mut acceptor = Acceptor::new(settings(), Box::new(|_| InMemoryStorage::new()));
+    let mut session_settings = session_settings(session_id("client"));
+    session_settings.throttle = Some(throttle);
+    acceptor.register_session(session_id("client"), session_settings);
+    let acceptor = Rc::new(RefCell::new(acceptor));
+    let mut events = drain_events(acceptor.clone());
+
+    let mut counterparty = Counterparty::connect(&acceptor.borrow());
+    counterparty.send(&logon("client", 1)).await;
+    let msg = counterparty.recv().await.unwrap();
+    assert_eq!(msg.msg_type(), MsgType::Logon);
+    assert!(matches!(events.recv().await, Some(Event::Logon(_))));
+    (acceptor, counterparty, events)
+}
+
+/// No more events pending, throttle events are emitted before related
+/// message is written.
+fn assert_no_events(events: &mut mpsc::UnboundedReceiver<Event>) {
+    if let Ok(event) = events.try_recv() {
+        panic!("unexpected event: {event:?}");
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn exceeding_message_is_delayed() {
+    LocalSet::new()
+        .run_until(async {
+            let (acceptor, mut counterparty, mut events) = logged_on(ThrottleSettings {
+                app: rate_limit(),
+                action: ThrottleAction::Delay,
+                ..Default::default()
+            })
+            .await;
+
+            let start = Instant::now();
+            let sender = acceptor.borrow().sender(&session_id("client")).unwrap();
+            sender.send(Box::new(app_message("first"))).unwrap();
+            sender.send(Box::new(app_message("second"))).unwrap();
+
+            let msg = counterparty.recv().await.unwrap();
+            assert_eq!(msg.header.msg_seq_num, 2);
+            assert_eq!(start.elapsed(), Duration::ZERO);
+            let msg = counterparty.recv().await.unwrap();
+            assert_eq!(msg.header.msg_seq_num, 3);
+            assert_eq!(start.elapsed(), Duration::from_secs(1));
+            assert!(matches!(
+                events.recv().await,
+                Some(Event::MsgThrottled(MsgType::BusinessMessageReject, delay))
+                    if delay == Duration::from_secs(1)
+            ));
+            assert_no_events(&mut events);
+        })
+        .await;
+}
+
+#[tokio::test(start_paused = true)]
+async fn exceeding_message_is_rejected() {
+    LocalSet::new()
+        .run_until(async {
+            let (acceptor, mut counterparty, mut events) = logged_on(ThrottleSettings {
+                app: rate_limit(),
+                action: ThrottleAction::Reject,
+                ..Default::default()
+            })
+            .await;
+
+            let sender = acceptor.borrow().sender(&session_id("client")).unwrap();
+            sender.send(Box::new(app_message("first"))).unwrap();
+            sender.send(Box::new(app_message("rejected"))).unwrap();
+            let msg = counterparty.recv().await.unwrap();
+            assert_eq!(msg.header.msg_seq_num, 2);
+
+            // Rejected message has no MsgSeqNum<34>
+            let Some(Event::MsgThrottleRejected(msg)) = events.recv().await else {
+                panic!("rejected message expected");
+            };
+            assert_eq!(msg.header.msg_seq_num, 0);
+            let Message::BusinessMessageReject(reject) = &*msg.body else {
+                panic!("unexpected message: {:?}", msg.msg_type());
+            };
+            assert_eq!(reject.text, Some(fix_string("rejected")));
+
+            // ...so it doesn't make a gap
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            sender.send(Box::new(app_message("third"))).unwrap();
+            let msg = counterparty.recv().await.unwrap();
+            assert_eq!(msg.header.msg_seq_num, 3);
+            assert_no_events(&mut events);
+        })
+        .await;
+}
+
+#[tokio::test(start_paused = true)]
+async fn admin_messages_are_never_rejected() {
+    LocalSet::new()
+        .run_until(async {
+            // Logon<A> response takes the only token
+            let (_acceptor, mut counterparty, mut events) = logged_on(ThrottleSettings {
+                admin: rate_limit(),
+                action: ThrottleAction::Reject,
+                ..Default::default()
+            })
+            .await;
+
+            let start = Instant::now();
+            counterparty
+                .send(&message(
+                    "client",
+                    2,
+                    Message::TestRequest(TestRequest {
+                        test_req_id: fix_string("test"),
+                        ..Default::default()
+                    }),
+                ))
+                .await;
+            let msg = counterparty.recv().await.unwrap();
+            assert_eq!(msg.msg_type(), MsgType::Heartbeat);
+            assert_eq!(msg.header.msg_seq_num, 2);
+            assert_eq!(start.elapsed(), Duration::from_secs(1));
+            assert!(matches!(
+                events.recv().await,
+                Some(Event::MsgThrottled(MsgType::Heartbeat, _))
+            ));
+            assert_no_events(&mut events);
+        })
+        .await;
+}
+
+#[tokio::test(start_paused = true)]
+async fn resent_messages_are_never_rejected() {
+    LocalSet::new()
+        .run_until(async {
+            let (acceptor, mut counterparty, mut events) = logged_on(ThrottleSettings {
+                app: rate_limit(),
+                action: ThrottleAction::Reject,
+                ..Default::default()
+            })
+            .await;
+
+            let sender = acceptor.borrow().sender(&session_id("client")).unwrap();
+            sender.send(Box::new(app_message("first"))).unwrap();
+            let msg = counterparty.recv().await.unwrap();
+            assert_eq!(msg.header.msg_seq_num, 2);
+
+            let start = Instant::now();
+            counterparty
+                .send(&message(
+                    "client",
+                    2,
+                    Message::ResendRequest(ResendRequest {
+                        begin_seq_no: 2,
+                        end_seq_no: 0,
+                        ..Default::default()
+                    }),
+                ))
+                .await;
+
+            // PossDupFlag<43> message waits for the token
+            let msg = counterparty.recv().await.unwrap();
+            assert_eq!(msg.header.msg_seq_num, 2);
+            assert_eq!(msg.header.poss_dup_flag, Some(true));
+            assert_eq!(start.elapsed(), Duration::from_secs(1));
+            let event = loop {
+                match events.recv().await {
+                    Some(Event::AppMsgResend(..)) => continue,
+                    event => break event,
+                }
+            };
+            assert!(matches!(
+                event,
+                Some(Event::MsgThrottled(MsgType::BusinessMessageReject, _))
+            ));
+            assert_no_events(&mut events);
+        })
+        .await;
+}
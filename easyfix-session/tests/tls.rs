@@ -0,0 +1,192 @@
+//! TLS transport, run with `--features tls`.
+//!
+//! Certificates in `tests/certs` are signed by self-signed test CA,
+//! server certificate is issued for `localhost`, client one has
+//! Common Name `client`.
+#![cfg(feature = "tls")]
+
+mod common;
+
+use std::{
+    io::{self, Read, Write},
+    net::{SocketAddr, TcpStream},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use common::{event, logon, session_id, session_settings, settings, Event};
+use easyfix_messages::{fields::MsgType, messages::FixtMessage};
+use easyfix_session::{
+    acceptor::{Acceptor, Connection},
+    application::AsEvent,
+    messages_storage::InMemoryStorage,
+    threaded_acceptor::ThreadedAcceptor,
+    tls::{
+        client_config,
+        rustls::{pki_types::ServerName, ClientConnection, ServerConfig, StreamOwned},
+        server_config, TlsConnection,
+    },
+};
+use futures::StreamExt;
+use tokio::{io::AsyncReadExt, task::JoinHandle, time::timeout};
+
+const COUNTERPARTY: &str = "initiator";
+const TEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+fn cert(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/certs")
+        .join(name)
+}
+
+async fn tls_connection() -> (TlsConnection, SocketAddr) {
+    let connection = TlsConnection::new(([127, 0, 0, 1], 0), tls_config())
+        .await
+        .unwrap();
+    let addr = connection.local_addr().unwrap();
+    (connection, addr)
+}
+
+fn tls_config() -> Arc<ServerConfig> {
+    server_config(
+        cert("server.pem"),
+        cert("server.key"),
+        Some(&cert("ca.pem")),
+    )
+    .unwrap()
+}
+
+/// Blocking TLS client, sends `data` and reads everything until the
+/// server closes connection.
+fn tls_client(
+    addr: SocketAddr,
+    with_client_cert: bool,
+    data: Vec<u8>,
+) -> JoinHandle<io::Result<Vec<u8>>> {
+    tokio::task::spawn_blocking(move || {
+        let (cert_chain, private_key) = (cert("client.pem"), cert("client.key"));
+        let config = client_config(
+            cert("ca.pem"),
+            with_client_cert.then_some((cert_chain.as_path(), private_key.as_path())),
+        )?;
+        let server_name = ServerName::try_from("localhost").unwrap();
+        let connection = ClientConnection::new(config, server_name).map_err(io::Error::other)?;
+        let mut stream = StreamOwned::new(connection, TcpStream::connect(addr)?);
+        stream.write_all(&data)?;
+        stream.flush()?;
+        let mut response = Vec::new();
+        match stream.read_to_end(&mut response) {
+            // Server may close connection without close_notify
+            Ok(_) => Ok(response),
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => Ok(response),
+            Err(err) => Err(err),
+        }
+    })
+}
+
+#[tokio::test]
+async fn handshake_exposes_client_identity() {
+    let (mut connection, addr) = tls_connection().await;
+    let client = tls_client(addr, true, b"ping".to_vec());
+
+    let (mut reader, writer, peer_info) = timeout(TEST_TIMEOUT, connection.accept())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(peer_info.tls_identity.as_deref(), Some("client"));
+
+    let mut data = [0; 4];
+    reader.read_exact(&mut data).await.unwrap();
+    assert_eq!(&data, b"ping");
+
+    drop((reader, writer));
+    assert!(client.await.unwrap().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn client_without_certificate_is_not_accepted() {
+    let (mut connection, addr) = tls_connection().await;
+    let client = tls_client(addr, false, b"ping".to_vec());
+
+    let result = timeout(TEST_TIMEOUT, connection.accept()).await.unwrap();
+    assert!(result.is_err());
+    assert!(client.await.unwrap().is_err());
+}
+
+#[tokio::test]
+async fn session_rejects_mismatched_client_identity() {
+    let mut acceptor = Acceptor::new(settings(), Box::new(|_| InMemoryStorage::new()));
+    let mut session_settings = session_settings(session_id(COUNTERPARTY));
+    session_settings.tls_client_identity = Some("other".to_owned());
+    acceptor.register_session(session_id(COUNTERPARTY), session_settings);
+
+    let (mut connection, addr) = tls_connection().await;
+    let client = tls_client(addr, true, logon(COUNTERPARTY, 1).serialize());
+    let (reader, writer, peer_info) = timeout(TEST_TIMEOUT, connection.accept())
+        .await
+        .unwrap()
+        .unwrap();
+    timeout(
+        TEST_TIMEOUT,
+        acceptor.run_session_task(peer_info, reader, writer),
+    )
+    .await
+    .unwrap();
+
+    let response = client.await.unwrap().unwrap();
+    let msg = FixtMessage::from_bytes(&response).unwrap();
+    assert_eq!(msg.msg_type(), MsgType::Logout);
+    let session = acceptor.session(&session_id(COUNTERPARTY)).unwrap();
+    assert!(!session.connected);
+    assert!(!session.logged_on);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn threaded_acceptor_serves_tls_sessions() {
+    let mut acceptor =
+        ThreadedAcceptor::new(settings(), 2, Arc::new(|_| InMemoryStorage::new())).unwrap();
+    acceptor.register_session(
+        session_id(COUNTERPARTY),
+        session_settings(session_id(COUNTERPARTY)),
+    );
+    // Free port, assigned by OS
+    let addr = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    acceptor.start_tls(addr, tls_config()).await.unwrap();
+
+    let client = tls_client(addr, true, logon(COUNTERPARTY, 1).serialize());
+    timeout(TEST_TIMEOUT, async {
+        while let Some(mut entry) = acceptor.next().await {
+            if let Some(Event::Logon(_)) = event(entry.as_event()) {
+                break;
+            }
+        }
+    })
+    .await
+    .unwrap();
+
+    // Logout<5> is not acknowledged by client, connection is closed
+    // after timeout
+    let shutdown = acceptor.shutdown(None, Duration::from_millis(100));
+    let events = tokio::spawn(async move { while acceptor.next().await.is_some() {} });
+    timeout(TEST_TIMEOUT, shutdown).await.unwrap();
+    events.abort();
+
+    let response = timeout(TEST_TIMEOUT, client)
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    let logon_len = response
+        .windows(4)
+        .position(|tag| tag == b"\x0110=")
+        .unwrap()
+        + 8;
+    let msg = FixtMessage::from_bytes(&response[..logon_len]).unwrap();
+    assert_eq!(msg.msg_type(), MsgType::Logon);
+    let msg = FixtMessage::from_bytes(&response[logon_len..]).unwrap();
+    assert_eq!(msg.msg_type(), MsgType::Logout);
+}
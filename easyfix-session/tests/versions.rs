@@ -0,0 +1,167 @@
+mod common;
+
+use std::future::poll_fn;
+
+use common::{app_message, fix_string, session_settings, settings, ACCEPTOR};
+use easyfix_messages::{messages::Message, session::SessionMessage};
+use easyfix_messages_multi_version_test::fix42;
+use easyfix_session::{
+    acceptor::{Acceptor, TcpConnection},
+    application::{AsEvent, FixEvent},
+    initiator::Initiator,
+    messages_storage::InMemoryStorage,
+    session_id::SessionId,
+    settings::Settings,
+    Sender,
+};
+use futures::{Stream, StreamExt};
+use tokio::{sync::mpsc, task::LocalSet};
+
+const LOCALHOST: ([u8; 4], u16) = ([127, 0, 0, 1], 0);
+
+enum Event<M: SessionMessage> {
+    Logon(SessionId, Sender<M>),
+    AppMsgIn(Box<M>),
+}
+
+/// Poll events of acceptor (or initiator) running on codec `M`.
+fn forward_events<T, M>(mut stream: T) -> mpsc::UnboundedReceiver<Event<M>>
+where
+    T: Stream + Unpin + 'static,
+    T::Item: AsEvent<M>,
+    M: SessionMessage,
+{
+    let (events_tx, events_rx) = mpsc::unbounded_channel();
+    tokio::task::spawn_local(async move {
+        while let Some(mut entry) = poll_fn(|cx| stream.poll_next_unpin(cx)).await {
+            let event = match entry.as_event() {
+                FixEvent::Logon(session_id, sender) => Event::Logon(session_id.clone(), sender),
+                FixEvent::AppMsgIn(msg, _) => Event::AppMsgIn(msg),
+                _ => continue,
+            };
+            let _ = events_tx.send(event);
+        }
+    });
+    events_rx
+}
+
+async fn logon<M: SessionMessage>(
+    events: &mut mpsc::UnboundedReceiver<Event<M>>,
+) -> (SessionId, Sender<M>) {
+    loop {
+        if let Some(Event::Logon(session_id, sender)) = events.recv().await {
+            return (session_id, sender);
+        }
+    }
+}
+
+async fn app_msg_in<M: SessionMessage>(events: &mut mpsc::UnboundedReceiver<Event<M>>) -> Box<M> {
+    loop {
+        if let Some(Event::AppMsgIn(msg)) = events.recv().await {
+            return msg;
+        }
+    }
+}
+
+fn session_id(begin_string: &str, sender_comp_id: &str, target_comp_id: &str) -> SessionId {
+    SessionId::new(
+        fix_string(begin_string),
+        fix_string(sender_comp_id),
+        fix_string(target_comp_id),
+    )
+}
+
+fn initiator_settings() -> Settings {
+    Settings {
+        sender_comp_id: fix_string("client"),
+        ..settings()
+    }
+}
+
+fn fix42_order() -> Box<fix42::messages::Message> {
+    use fix42::{
+        fields::{HandlInst, OrdType, Side, UtcTimestamp},
+        messages::{Message, NewOrderSingle},
+    };
+
+    Box::new(Message::NewOrderSingle(NewOrderSingle {
+        cl_ord_id: fix_string("order"),
+        handl_inst: HandlInst::ManualOrderBestExecution,
+        symbol: fix_string("EASY"),
+        side: Side::Buy,
+        transact_time: UtcTimestamp::now(),
+        ord_type: OrdType::Market,
+        ..Default::default()
+    }))
+}
+
+#[tokio::test]
+async fn fix42_and_fixt_sessions_side_by_side() {
+    LocalSet::new()
+        .run_until(async {
+            // FIXT.1.1 sessions on default codec
+            let mut fixt_acceptor = Acceptor::new(settings(), Box::new(|_| InMemoryStorage::new()));
+            let fixt_id = session_id("FIXT.1.1", ACCEPTOR, "client");
+            fixt_acceptor.register_session(fixt_id.clone(), session_settings(fixt_id.clone()));
+            let connection = TcpConnection::new(LOCALHOST).await.unwrap();
+            let fixt_addr = connection.local_addr().unwrap();
+            fixt_acceptor.start(connection);
+            let mut fixt_acceptor_events = forward_events(fixt_acceptor);
+
+            // FIX.4.2 sessions on codec generated from FIX.4.2 dictionary
+            let mut fix42_acceptor = Acceptor::<_, fix42::messages::FixtMessage>::with_codec(
+                settings(),
+                Box::new(|_| InMemoryStorage::new()),
+            );
+            let fix42_id = session_id("FIX.4.2", ACCEPTOR, "client");
+            fix42_acceptor.register_session(fix42_id.clone(), session_settings(fix42_id.clone()));
+            let connection = TcpConnection::new(LOCALHOST).await.unwrap();
+            let fix42_addr = connection.local_addr().unwrap();
+            fix42_acceptor.start(connection);
+            let mut fix42_acceptor_events = forward_events(fix42_acceptor);
+
+            let fixt_initiator = Initiator::new(
+                initiator_settings(),
+                session_settings(session_id("FIXT.1.1", "client", ACCEPTOR)),
+                InMemoryStorage::new(),
+            );
+            fixt_initiator.connect(fixt_addr).await.unwrap();
+            let mut fixt_initiator_events = forward_events(fixt_initiator);
+
+            let fix42_initiator = Initiator::<_, fix42::messages::FixtMessage>::with_codec(
+                initiator_settings(),
+                session_settings(session_id("FIX.4.2", "client", ACCEPTOR)),
+                InMemoryStorage::new(),
+            );
+            fix42_initiator.connect(fix42_addr).await.unwrap();
+            let mut fix42_initiator_events = forward_events(fix42_initiator);
+
+            assert_eq!(logon(&mut fixt_acceptor_events).await.0, fixt_id);
+            assert_eq!(logon(&mut fix42_acceptor_events).await.0, fix42_id);
+
+            logon(&mut fix42_initiator_events)
+                .await
+                .1
+                .send(fix42_order())
+                .unwrap();
+            let msg = app_msg_in(&mut fix42_acceptor_events).await;
+            assert_eq!(msg.header.begin_string, fix_string("FIX.4.2"));
+            let fix42::messages::Message::NewOrderSingle(order) = &*msg.body else {
+                panic!("unexpected message: {:?}", msg.msg_type());
+            };
+            assert_eq!(order.cl_ord_id, fix_string("order"));
+
+            logon(&mut fixt_initiator_events)
+                .await
+                .1
+                .send(Box::new(app_message("fixt")))
+                .unwrap();
+            let msg = app_msg_in(&mut fixt_acceptor_events).await;
+            assert_eq!(msg.header.begin_string, fix_string("FIXT.1.1"));
+            let Message::BusinessMessageReject(reject) = &*msg.body else {
+                panic!("unexpected message: {:?}", msg.msg_type());
+            };
+            assert_eq!(reject.text, Some(fix_string("fixt")));
+        })
+        .await;
+}
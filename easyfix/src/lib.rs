@@ -1,4 +1,6 @@
 pub use easyfix_dictionary as dictionary;
 pub use easyfix_macros::fix_str;
-pub use easyfix_messages::{deserializer, fields, fix_format, groups, messages, serializer};
+pub use easyfix_messages::{
+    deserializer, dynamic, fields, fix_format, groups, messages, serializer,
+};
 pub use easyfix_session as session;